@@ -2,37 +2,127 @@ import { invoke } from "@tauri-apps/api/core";
 import type {
 	ProviderType,
 	ChatRequest,
+	CredentialProfile,
+	CredentialValidation,
+	ExportFormat,
+	GatewayClient,
+	GatewaySettings,
+	IntegrityReport,
+	NetworkSettings,
 	StreamResponse,
 	Topic,
-	Message,
+	TopicExchange,
+	StoredMessage,
 	ModelSettings,
+	SearchFilters,
+	SearchHit,
 } from "./types";
+import type { ContentType } from "../src-tauri/bindings/ContentType";
 
 export const llmApi = {
-	sendMessage: (provider: ProviderType, model: string, request: ChatRequest) =>
-		invoke<StreamResponse>("send_message", { provider, model, request }),
+	sendMessage: (
+		provider: ProviderType,
+		model: string,
+		request: ChatRequest,
+		requestId?: string,
+		topicId?: number,
+	) =>
+		invoke<StreamResponse>("send_message", {
+			provider,
+			model,
+			request,
+			requestId,
+			topicId,
+		}),
+
+	sendMessageForTopic: (
+		topicId: number,
+		userContent: ContentType,
+		requestId?: string,
+	) =>
+		invoke<TopicExchange>("send_message_for_topic", {
+			topicId,
+			userContent,
+			requestId,
+		}),
+
+	regenerateMessage: (messageId: number, requestId?: string) =>
+		invoke<TopicExchange>("regenerate_message", { messageId, requestId }),
 
-	listModels: (provider: ProviderType) =>
-		invoke<string[]>("list_models", { provider }),
+	cancelGeneration: (requestId: string) =>
+		invoke<boolean>("cancel_generation", { requestId }),
+
+	listModels: (provider: ProviderType, profile?: string) =>
+		invoke<string[]>("list_models", { provider, profile }),
 
 	// getModelDetails: (provider: ProviderType, modelId: string) =>
 	// 	invoke<ModelDetails>("get_model_details", { provider, model_id: modelId }),
 
-	hasCredentials: (provider: ProviderType) =>
-		invoke<boolean>("has_credentials", { provider }),
+	hasCredentials: (provider: ProviderType, profile?: string) =>
+		invoke<boolean>("has_credentials", { provider, profile }),
+
+	validateCredentials: (provider: ProviderType, profile?: string) =>
+		invoke<CredentialValidation>("validate_credentials", { provider, profile }),
 
 	saveCredentials: (provider: ProviderType, apiKey: string) =>
 		invoke<boolean>("save_credentials", { provider, api_key: apiKey }),
 
+	listCredentialProfiles: (provider?: ProviderType) =>
+		invoke<CredentialProfile[]>("list_credential_profiles", { provider }),
+
+	createCredentialProfile: (
+		profile: string,
+		creds: { type: ProviderType } & Record<string, string | null>,
+	) => invoke<void>("create_credential_profile", { profile, creds }),
+
+	renameCredentialProfile: (
+		provider: ProviderType,
+		profile: string,
+		newName: string,
+	) =>
+		invoke<void>("rename_credential_profile", { provider, profile, newName }),
+
+	deleteCredentialProfile: (provider: ProviderType, profile: string) =>
+		invoke<void>("delete_credential_profile", { provider, profile }),
+
+	setDefaultCredentialProfile: (provider: ProviderType, profile: string) =>
+		invoke<void>("set_default_credential_profile", { provider, profile }),
+
+	isCredentialsLocked: () => invoke<boolean>("is_credentials_locked"),
+
+	unlockCredentials: (passphrase: string) =>
+		invoke<void>("unlock_credentials", { passphrase }),
+
+	setCredentialsPassphrase: (passphrase: string | null) =>
+		invoke<void>("set_credentials_passphrase", { passphrase }),
+
 	getSupportedProviders: () =>
 		invoke<ProviderType[]>("get_supported_providers"),
+
+	getNetworkSettings: () => invoke<NetworkSettings>("get_network_settings"),
+
+	saveNetworkSettings: (settings: NetworkSettings) =>
+		invoke<void>("save_network_settings", { settings }),
+
+	getGatewaySettings: () => invoke<GatewaySettings>("get_gateway_settings"),
+
+	saveGatewaySettings: (settings: GatewaySettings) =>
+		invoke<void>("save_gateway_settings", { settings }),
+
+	listGatewayClients: () => invoke<GatewayClient[]>("list_gateway_clients"),
+
+	createGatewayClient: (name: string) =>
+		invoke<string>("create_gateway_client", { name }),
+
+	deleteGatewayClient: (name: string) =>
+		invoke<void>("delete_gateway_client", { name }),
 };
 
 export const dbApi = {
 	getAllTopics: () => invoke<Topic[]>("get_all_topics"),
 
 	getMessagesByTopic: (topicId: number) =>
-		invoke<Message[]>("get_messages_by_topic", { topicId }),
+		invoke<StoredMessage[]>("get_messages_by_topic", { topicId }),
 
 	addTopic: (name: string) => invoke<Topic>("add_topic", { name }),
 
@@ -50,9 +140,10 @@ export const dbApi = {
 	addMessage: (
 		topicId: number,
 		role: string,
-		content: string,
+		content: ContentType,
 		tokensUsed: number | null,
-	) => invoke<Message>("add_message", { topicId, role, content, tokensUsed }),
+	) =>
+		invoke<StoredMessage>("add_message", { topicId, role, content, tokensUsed }),
 
 	removeMessages: (messageIds: number[]) =>
 		invoke<boolean>("remove_messages", { messageIds }),
@@ -62,4 +153,28 @@ export const dbApi = {
 
 	getSettings: (topicId: number) =>
 		invoke<ModelSettings>("get_settings", { topicId }),
+
+	searchMessages: (query: string, filters?: Partial<SearchFilters>) =>
+		invoke<SearchHit[]>("search_messages", { query, filters }),
+
+	exportTopic: (topicId: number, format: ExportFormat) =>
+		invoke<string>("export_topic", { topicId, format }),
+
+	importTopic: (path: string) => invoke<Topic[]>("import_topic", { path }),
+
+	backupDatabase: (compress?: boolean) =>
+		invoke<string>("backup_database", { compress }),
+
+	restoreDatabase: (path: string) => invoke<void>("restore_database", { path }),
+
+	checkDatabase: () => invoke<IntegrityReport>("check_database"),
+
+	forkMessage: (messageId: number, content: ContentType) =>
+		invoke<StoredMessage>("fork_message", { messageId, content }),
+
+	listSiblingBranches: (messageId: number) =>
+		invoke<StoredMessage[]>("list_sibling_branches", { messageId }),
+
+	switchBranch: (messageId: number) =>
+		invoke<StoredMessage[]>("switch_branch", { messageId }),
 };
@@ -1,7 +1,34 @@
 import * as tauri from '@tauri-apps/api/tauri';
 declare module '@tauri-apps/api/tauri' {
     type Commands = 
-		  'send_message'
+		  'regenerate_message'
+		| 'cancel_generation'
+		| 'validate_credentials'
+		| 'list_credential_profiles'
+		| 'create_credential_profile'
+		| 'rename_credential_profile'
+		| 'delete_credential_profile'
+		| 'set_default_credential_profile'
+		| 'is_credentials_locked'
+		| 'unlock_credentials'
+		| 'set_credentials_passphrase'
+		| 'list_tools'
+		| 'get_network_settings'
+		| 'save_network_settings'
+		| 'get_gateway_settings'
+		| 'save_gateway_settings'
+		| 'list_gateway_clients'
+		| 'create_gateway_client'
+		| 'delete_gateway_client'
+		| 'backup_database'
+		| 'restore_database'
+		| 'check_database'
+		| 'fork_message'
+		| 'list_sibling_branches'
+		| 'switch_branch'
+		| 'export_topic'
+		| 'import_topic'
+		| 'search_messages'
 		| 'get_all_topics'
 		| 'get_messages_by_topic'
 		| 'add_topic'
@@ -0,0 +1,9 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+
+export type IntegrityReport = {
+  ok: boolean;
+  /**
+   * What `PRAGMA integrity_check` and `PRAGMA foreign_key_check` found.
+   */
+  problems: Array<string>;
+};
@@ -1,5 +1,13 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 
+export type BlockType =
+  | "text"
+  | "image"
+  | "thinking"
+  | "redacted_thinking"
+  | "tool_use"
+  | "tool_result";
+
 export type Role = "system" | "user" | "assistant";
 
 export type Theme = "system" | "dark" | "light";
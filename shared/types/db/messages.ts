@@ -1,5 +1,8 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { BlockType } from "./enum.js";
+import type { ContentType } from "../../../src-tauri/bindings/ContentType.js";
 import type { Role } from "./enum.js";
+import type { StreamResponse } from "../llm/core.js";
 
 export type Message = {
   id: number;
@@ -9,4 +12,42 @@ export type Message = {
   createdAt: string;
   tokensUsed: number;
   updatedAt: string | null;
+  /**
+   * The message this one answers or follows, `None` for the first one.
+   * Siblings are alternative branches of the conversation.
+   */
+  parentId: number | null;
+};
+
+export type MessageBlock = {
+  id: number;
+  messageId: number;
+  position: number;
+  blockType: BlockType;
+  text: string | null;
+  sourceType: string | null;
+  mediaType: string | null;
+  data: string | null;
+  signature: string | null;
+  toolUseId: string | null;
+  toolName: string | null;
+  toolInput: string | null;
+  isError: boolean | null;
+};
+
+export type StoredMessage = {
+  id: number;
+  topicId: number;
+  role: Role;
+  content: ContentType;
+  createdAt: string;
+  tokensUsed: number;
+  updatedAt: string | null;
+  parentId: number | null;
+};
+
+export type TopicExchange = {
+  user: StoredMessage;
+  assistant: StoredMessage;
+  response: StreamResponse;
 };
@@ -13,4 +13,5 @@ export type ModelSettings = {
 	temperature?: number;
 	topK?: number;
 	topP?: number;
+	credentialProfile?: string;
 };
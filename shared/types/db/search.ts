@@ -0,0 +1,37 @@
+// This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
+import type { Role } from "./enum.js";
+
+/**
+ * Part of a snippet matching the query, in UTF-16 code units so it can
+ * index JavaScript strings directly.
+ */
+export type Highlight = { start: number; end: number };
+
+export type SearchFilters = {
+  /**
+   * Only topics that were configured with this provider.
+   */
+  provider: string | null;
+  model: string | null;
+  role: Role | null;
+  /**
+   * RFC 3339 dates, both inclusive.
+   */
+  from: string | null;
+  to: string | null;
+  limit: number | null;
+};
+
+export type SearchHit = {
+  messageId: number;
+  topicId: number;
+  topicName: string;
+  role: Role;
+  createdAt: string;
+  snippet: string;
+  highlights: Array<Highlight>;
+  /**
+   * BM25 score, lower is better.
+   */
+  rank: number;
+};
@@ -5,4 +5,8 @@ export type Topic = {
   name: string;
   createdAt: string;
   lastAccessedAt: string;
+  /**
+   * Last message of the branch shown and sent to the model.
+   */
+  activeLeafId: number | null;
 };
@@ -1,6 +1,9 @@
 export * from "./llm/core.ts";
 export * from "./db/app-apperance.ts";
+export * from "./db/backup.ts";
 export * from "./db/enum.ts";
+export * from "./db/export.ts";
 export * from "./db/messages.ts";
 export * from "./db/models-settings.ts";
+export * from "./db/search.ts";
 export * from "./db/topics.ts";
@@ -3,7 +3,30 @@ import type { AnthropicSystemPrompt } from "../../../../shared/types/llm/core.js
 import type { AnthropicThinkingConfig } from "./providers/anthropic.js";
 import type { AnthropicTool } from "../../../src-tauri/bindings/AnthropicTool.js";
 import type { AnthropicToolChoice } from "../../../src-tauri/bindings/AnthropicToolChoice.js";
+import type { ContentItem } from "../../../types/core.js";
 import type { ContentType } from "../../../src-tauri/bindings/ContentType.js";
+import type { JsonValue } from "../../../src-tauri/bindings/serde_json/JsonValue.js";
+
+export type AccountMetadata = {
+  organization_id?: string;
+  account_name?: string;
+  models: Array<string>;
+};
+
+export type AppError =
+  | {
+      kind: "api";
+      provider: string;
+      status?: number;
+      error_type?: string;
+      /**
+       * Seconds to wait before retrying, from the `retry-after` header.
+       */
+      retry_after?: number;
+      message: string;
+    }
+  | { kind: "network"; provider: string; message: string }
+  | { kind: "internal"; message: string };
 
 export type ChatMessage = { role: ChatRole; content: ContentType };
 
@@ -28,12 +51,130 @@ export type ChatRequest = {
 
 export type ChatRole = "system" | "user" | "assistant";
 
-export type ProviderType = "Anthropic" | "Cloudflare" | "Mistral" | "OpenAI";
+export type CredentialProfile = {
+  provider: ProviderType;
+  name: string;
+  isDefault: boolean;
+};
+
+export type CredentialStatus =
+  | "valid"
+  | "missing_credentials"
+  | "invalid_key"
+  | "wrong_account_id"
+  | "rate_limited"
+  | "network_error"
+  | "unexpected";
+
+export type CredentialValidation = {
+  status: CredentialStatus;
+  message?: string;
+  account?: AccountMetadata;
+};
+
+export type GatewayClient = {
+  name: string;
+  created_at: string;
+  usage: GatewayUsage;
+};
+
+/**
+ * The local OpenAI-compatible server, persisted in the gateway store. It
+ * only ever listens on 127.0.0.1.
+ */
+export type GatewaySettings = { enabled: boolean; port: number };
+
+/**
+ * What a client sent through the gateway since it was created.
+ */
+export type GatewayUsage = {
+  requests: number;
+  /**
+   * Requests the provider failed or refused.
+   */
+  errors: number;
+  prompt_tokens: number;
+  completion_tokens: number;
+  last_used_at?: string;
+};
+
+/**
+ * How the app reaches the providers, persisted in the network store.
+ */
+export type NetworkSettings = {
+  connect_timeout_secs: number;
+  /**
+   * Longest silence allowed between two reads, so a stalled stream fails
+   * instead of hanging. Long thinking pauses need some slack here.
+   */
+  read_timeout_secs: number;
+  /**
+   * `http://`, `https://`, `socks5://` or `socks5h://` proxy used for
+   * every request.
+   */
+  proxy_url?: string;
+  /**
+   * PEM certificates trusted on top of the system roots, e.g. the CA of
+   * a corporate TLS proxy.
+   */
+  root_certificates: Array<string>;
+  user_agent?: string;
+  /**
+   * Attempts per provider request, including the first one. 1 disables
+   * retries.
+   */
+  max_attempts: number;
+  retry_base_delay_ms: number;
+  /**
+   * Longest wait between two attempts. A provider asking to wait longer
+   * fails the request instead.
+   */
+  retry_max_delay_ms: number;
+};
+
+export type ProviderType =
+  | "Anthropic"
+  | "Cloudflare"
+  | "Mistral"
+  | "OpenAI"
+  | "Ollama";
+
+export type RetryAttempt = {
+  /**
+   * The attempt that just failed, starting at 1.
+   */
+  attempt: number;
+  max_attempts: number;
+  delay_ms: number;
+  error: AppError;
+};
+
+export type StopReason =
+  | "end_turn"
+  | "max_tokens"
+  | "stop_sequence"
+  | "tool_use"
+  | "cancelled";
+
+export type StreamEvent =
+  | { "type": "start"; request_id: string }
+  | { "type": "retrying"; request_id: string; retry: RetryAttempt }
+  | { "type": "text_delta"; request_id: string; text: string }
+  | { "type": "thinking_delta"; request_id: string; thinking: string }
+  | { "type": "tool_call"; request_id: string; tool_call: ToolCallEvent }
+  | { "type": "usage"; request_id: string; usage: TokenUsage }
+  | { "type": "stop"; request_id: string; reason: StopReason }
+  | { "type": "error"; request_id: string; error: AppError };
 
 export type StreamResponse = {
   response: string;
   usage: TokenUsage | null;
   thinking: string | null;
+  /**
+   * Full assistant content, thinking signatures and tool calls included.
+   */
+  content: Array<ContentItem> | null;
+  stop_reason: StopReason | null;
 };
 
 export type TokenUsage = {
@@ -41,3 +182,12 @@ export type TokenUsage = {
   completion_tokens: number;
   total_tokens: number;
 };
+
+export type ToolCallEvent = {
+  iteration: number;
+  tool_use_id: string;
+  name: string;
+  input: JsonValue;
+  output: string;
+  is_error: boolean;
+};
@@ -1,7 +1,16 @@
 // This file was generated by [ts-rs](https://github.com/Aleph-Alpha/ts-rs). Do not edit this file manually.
 import type { AnthropicImageSource } from "./AnthropicImageSource.js";
+import type { JsonValue } from "./serde_json/JsonValue.js";
 
-export type ContentBlock = { "type": "text"; text: string } | {
-  "type": "image";
-  source: AnthropicImageSource;
-};
+export type ContentBlock =
+  | { "type": "text"; text: string }
+  | { "type": "image"; source: AnthropicImageSource }
+  | { "type": "thinking"; thinking: string; signature: string }
+  | { "type": "redacted_thinking"; data: string }
+  | { "type": "tool_use"; id: string; name: string; input: JsonValue }
+  | {
+    "type": "tool_result";
+    tool_use_id: string;
+    content: string;
+    is_error?: boolean;
+  };
@@ -1,11 +1,26 @@
+use futures_util::future::{self, Aborted};
+use sea_orm::DatabaseConnection;
 use serde::{Deserialize, Serialize};
-use tauri::{AppHandle, Runtime, Window};
+use std::future::Future;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime, State, Window};
 
 use crate::core::credentials::{
-    self, AnthropicCredentials, CloudflareCredentials, MistralCredentials,
+    self, AnthropicCredentials, CloudflareCredentials, CredentialProfile, MistralCredentials,
+    OllamaCredentials, OpenAICredentials,
 };
-use crate::core::llm_trait;
-use crate::core::models::{ChatRequest, ProviderType, StreamResponse};
+use crate::core::error::AppError;
+use crate::core::llm_trait::{self, ProviderRegistry};
+use crate::core::models::{ChatRequest, ContentType, ProviderType, StreamResponse};
+use crate::core::network::{self, NetworkSettings};
+use crate::core::stream::{self, GenerationRegistry, StopReason, StreamEmitter};
+use crate::core::tools::ToolRegistry;
+use crate::core::validation::CredentialValidation;
+use crate::core::vault::CredentialVault;
+use crate::db::conversation::{self, TopicExchange, TopicRequest};
+use crate::db::topics;
+use crate::gateway::{self, GatewayClient, GatewaySettings};
+use crate::providers::anthropic::AnthropicTool;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
@@ -20,17 +35,41 @@ pub enum ProviderCredentials {
     Mistral {
         api_key: String,
     },
+    OpenAI {
+        api_key: String,
+        base_url: Option<String>,
+        organization: Option<String>,
+    },
+    Ollama {
+        host: String,
+    },
+}
+
+impl ProviderCredentials {
+    pub fn provider(&self) -> ProviderType {
+        match self {
+            ProviderCredentials::Cloudflare { .. } => ProviderType::Cloudflare,
+            ProviderCredentials::Anthropic { .. } => ProviderType::Anthropic,
+            ProviderCredentials::Mistral { .. } => ProviderType::Mistral,
+            ProviderCredentials::OpenAI { .. } => ProviderType::OpenAI,
+            ProviderCredentials::Ollama { .. } => ProviderType::Ollama,
+        }
+    }
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn send_message<R: Runtime>(
     window: Window<R>,
     app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
+    generations: State<'_, GenerationRegistry>,
     provider: ProviderType,
     model: String,
     request: serde_json::Value,
-) -> Result<StreamResponse, String> {
-    let provider_id = provider.as_str();
+    request_id: Option<String>,
+    topic_id: Option<i32>,
+) -> Result<StreamResponse, AppError> {
     let request: ChatRequest = match serde_json::from_value(request.clone()) {
         Ok(req) => req,
         Err(e) => {
@@ -39,57 +78,207 @@ pub async fn send_message<R: Runtime>(
                 "JSON structure: {}",
                 serde_json::to_string_pretty(&request).unwrap()
             );
-            return Err(format!("Invalid request format: {}", e));
+            return Err(format!("Invalid request format: {}", e).into());
         }
     };
 
-    let provider_impl = llm_trait::get_provider(provider_id)
-        .ok_or(format!("Unsupported provider: {}", provider_id))?;
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
+
+    let profile = match (topic_id, app.try_state::<DatabaseConnection>()) {
+        (Some(topic_id), Some(db)) => topics::get_topic_credential_profile(&db, topic_id).await?,
+        _ => None,
+    };
+
+    let events = StreamEmitter::new(
+        Arc::new(window),
+        request_id.unwrap_or_else(stream::new_request_id),
+    );
+
+    generate(
+        &events,
+        &generations,
+        provider_impl.send_message(events.clone(), &app, profile, model, request),
+    )
+    .await
+}
+
+/// Sends `user_content` with the topic's saved settings and active branch,
+/// then stores the prompt and the reply in the topic.
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn send_message_for_topic<R: Runtime>(
+    window: Window<R>,
+    app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
+    generations: State<'_, GenerationRegistry>,
+    db: State<'_, DatabaseConnection>,
+    topic_id: i32,
+    user_content: ContentType,
+    request_id: Option<String>,
+) -> Result<TopicExchange, AppError> {
+    let TopicRequest {
+        provider,
+        model,
+        profile,
+        request,
+        parent_id,
+    } = conversation::build_request(&db, topic_id, user_content.clone()).await?;
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
+
+    let events = StreamEmitter::new(
+        Arc::new(window),
+        request_id.unwrap_or_else(stream::new_request_id),
+    );
+    let response = generate(
+        &events,
+        &generations,
+        provider_impl.send_message(events.clone(), &app, profile, model, request),
+    )
+    .await?;
+
+    let (user, assistant) =
+        conversation::save_exchange(&db, topic_id, parent_id, &user_content, &response).await?;
+
+    Ok(TopicExchange {
+        user,
+        assistant,
+        response,
+    })
+}
+
+/// Generates a new reply to the prompt of an assistant reply. The new reply
+/// is stored next to the previous one and becomes the active branch once
+/// the generation succeeded.
+#[tauri::command]
+pub async fn regenerate_message<R: Runtime>(
+    window: Window<R>,
+    app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
+    generations: State<'_, GenerationRegistry>,
+    db: State<'_, DatabaseConnection>,
+    message_id: i32,
+    request_id: Option<String>,
+) -> Result<TopicExchange, AppError> {
+    let (
+        TopicRequest {
+            provider,
+            model,
+            profile,
+            request,
+            ..
+        },
+        prompt,
+    ) = conversation::build_regeneration(&db, message_id).await?;
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
+
+    let events = StreamEmitter::new(
+        Arc::new(window),
+        request_id.unwrap_or_else(stream::new_request_id),
+    );
+    let response = generate(
+        &events,
+        &generations,
+        provider_impl.send_message(events.clone(), &app, profile, model, request),
+    )
+    .await?;
+
+    let assistant = conversation::save_reply(&db, prompt.topic_id, prompt.id, &response).await?;
+
+    Ok(TopicExchange {
+        user: prompt,
+        assistant,
+        response,
+    })
+}
+
+/// Runs a generation that `cancel_generation` can stop and reports how it
+/// ended on `events`. A cancelled generation returns what was streamed.
+async fn generate(
+    events: &StreamEmitter,
+    generations: &GenerationRegistry,
+    generation: impl Future<Output = Result<StreamResponse, AppError>>,
+) -> Result<StreamResponse, AppError> {
+    events.start()?;
 
-    llm_trait::send_message(&*provider_impl, window, app, model, request).await
+    let (generation, abort_handle) = future::abortable(generation);
+    generations.register(events.request_id(), abort_handle);
+    let result = generation.await;
+    generations.remove(events.request_id());
+
+    match result {
+        Err(Aborted) => {
+            events.stop(StopReason::Cancelled)?;
+            Ok(events.partial_response())
+        }
+        Ok(Ok(response)) => {
+            if let Some(usage) = &response.usage {
+                events.usage(usage)?;
+            }
+            events.stop(response.stop_reason.clone().unwrap_or(StopReason::EndTurn))?;
+            Ok(response)
+        }
+        Ok(Err(e)) => {
+            let _ = events.error(&e);
+            Err(e)
+        }
+    }
+}
+
+#[tauri::command]
+pub fn cancel_generation(generations: State<'_, GenerationRegistry>, request_id: String) -> bool {
+    generations.cancel(&request_id)
 }
 
 #[tauri::command]
 #[allow(dead_code)]
 pub async fn list_models<R: Runtime>(
     app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
     provider: ProviderType,
-) -> Result<Vec<String>, String> {
-    let provider_id = provider.as_str();
+    profile: Option<String>,
+) -> Result<Vec<String>, AppError> {
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
 
-    let provider_impl = llm_trait::get_provider(provider_id)
-        .ok_or(format!("Unsupported provider: {}", provider_id))?;
-
-    llm_trait::list_models(&*provider_impl, app).await
+    provider_impl.list_models(&app, profile).await
 }
 
 #[tauri::command]
 #[allow(dead_code)]
 pub async fn get_model_details<R: Runtime>(
     app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
     provider: ProviderType,
     model: String,
-) -> Result<serde_json::Value, String> {
-    let provider_id = provider.as_str();
-
-    let provider_impl = llm_trait::get_provider(provider_id)
-        .ok_or(format!("Unsupported provider: {}", provider_id))?;
+    profile: Option<String>,
+) -> Result<serde_json::Value, AppError> {
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
 
-    llm_trait::get_model_details(&*provider_impl, app, model).await
+    provider_impl.model_details(&app, profile, model).await
 }
 
 #[tauri::command]
 #[allow(dead_code)]
 pub async fn has_credentials<R: Runtime>(
     app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
     provider: ProviderType,
-) -> Result<bool, String> {
-    let provider_id = provider.as_str();
+    profile: Option<String>,
+) -> Result<bool, AppError> {
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
+
+    provider_impl.has_credentials(&app, profile).await
+}
 
-    let provider_impl = llm_trait::get_provider(provider_id)
-        .ok_or(format!("Unsupported provider: {}", provider_id))?;
+#[tauri::command]
+pub async fn validate_credentials<R: Runtime>(
+    app: AppHandle<R>,
+    registry: State<'_, ProviderRegistry>,
+    provider: ProviderType,
+    profile: Option<String>,
+) -> Result<CredentialValidation, AppError> {
+    let provider_impl = llm_trait::get_provider(&registry, &provider)?;
 
-    llm_trait::has_credentials(&*provider_impl, app).await
+    provider_impl.validate_credentials(&app, profile).await
 }
 
 #[tauri::command]
@@ -97,6 +286,15 @@ pub async fn has_credentials<R: Runtime>(
 pub async fn save_credentials<R: Runtime>(
     app: AppHandle<R>,
     creds: ProviderCredentials,
+    profile: Option<String>,
+) -> Result<(), AppError> {
+    Ok(save_provider_credentials(&app, creds, profile.as_deref()).await?)
+}
+
+async fn save_provider_credentials<R: Runtime>(
+    app: &AppHandle<R>,
+    creds: ProviderCredentials,
+    profile: Option<&str>,
 ) -> Result<(), String> {
     match creds {
         ProviderCredentials::Cloudflare {
@@ -107,19 +305,129 @@ pub async fn save_credentials<R: Runtime>(
                 account_id,
                 api_token,
             };
-            credentials::save_cloudflare_credentials(&app, &credentials).await
+            credentials::save_cloudflare_credentials(app, profile, &credentials).await
         }
         ProviderCredentials::Anthropic { api_key } => {
             let credentials = AnthropicCredentials { api_key };
-            credentials::save_anthropic_credentials(&app, &credentials).await
+            credentials::save_anthropic_credentials(app, profile, &credentials).await
         }
         ProviderCredentials::Mistral { api_key } => {
             let credentials = MistralCredentials { api_key };
-            credentials::save_mistral_credentials(&app, &credentials).await
+            credentials::save_mistral_credentials(app, profile, &credentials).await
+        }
+        ProviderCredentials::OpenAI {
+            api_key,
+            base_url,
+            organization,
+        } => {
+            let credentials = OpenAICredentials {
+                api_key,
+                base_url,
+                organization,
+            };
+            credentials::save_openai_credentials(app, profile, &credentials).await
+        }
+        ProviderCredentials::Ollama { host } => {
+            let credentials = OllamaCredentials { host };
+            credentials::save_ollama_credentials(app, profile, &credentials).await
         }
     }
 }
 
+#[tauri::command]
+pub fn list_credential_profiles<R: Runtime>(
+    app: AppHandle<R>,
+    provider: Option<ProviderType>,
+) -> Result<Vec<CredentialProfile>, AppError> {
+    Ok(credentials::list_profiles(
+        &app,
+        provider.as_ref().map(ProviderType::as_str),
+    )?)
+}
+
+#[tauri::command]
+pub async fn create_credential_profile<R: Runtime>(
+    app: AppHandle<R>,
+    profile: String,
+    creds: ProviderCredentials,
+) -> Result<(), AppError> {
+    let provider_id = creds.provider().as_str();
+    credentials::validate_profile_name(&profile)?;
+    if credentials::profile_exists(&app, provider_id, &profile)? {
+        return Err(format!(
+            "Profile already exists: {}",
+            credentials::profile_key(provider_id, &profile)
+        )
+        .into());
+    }
+
+    Ok(save_provider_credentials(&app, creds, Some(&profile)).await?)
+}
+
+#[tauri::command]
+pub fn rename_credential_profile<R: Runtime>(
+    app: AppHandle<R>,
+    provider: ProviderType,
+    profile: String,
+    new_name: String,
+) -> Result<(), AppError> {
+    Ok(credentials::rename_profile(
+        &app,
+        provider.as_str(),
+        &profile,
+        &new_name,
+    )?)
+}
+
+#[tauri::command]
+pub fn delete_credential_profile<R: Runtime>(
+    app: AppHandle<R>,
+    provider: ProviderType,
+    profile: String,
+) -> Result<(), AppError> {
+    Ok(credentials::delete_profile(
+        &app,
+        provider.as_str(),
+        &profile,
+    )?)
+}
+
+#[tauri::command]
+pub fn set_default_credential_profile<R: Runtime>(
+    app: AppHandle<R>,
+    provider: ProviderType,
+    profile: String,
+) -> Result<(), AppError> {
+    Ok(credentials::set_default_profile(
+        &app,
+        provider.as_str(),
+        &profile,
+    )?)
+}
+
+#[tauri::command]
+pub fn is_credentials_locked(vault: State<'_, CredentialVault>) -> bool {
+    vault.is_locked()
+}
+
+#[tauri::command]
+pub fn unlock_credentials<R: Runtime>(
+    app: AppHandle<R>,
+    vault: State<'_, CredentialVault>,
+    passphrase: String,
+) -> Result<(), AppError> {
+    Ok(vault.unlock(&app, &passphrase)?)
+}
+
+#[tauri::command]
+pub fn set_credentials_passphrase<R: Runtime>(
+    app: AppHandle<R>,
+    vault: State<'_, CredentialVault>,
+    passphrase: Option<String>,
+) -> Result<(), AppError> {
+    Ok(vault.set_passphrase(&app, passphrase.as_deref())?)
+}
+
 #[tauri::command]
 #[allow(dead_code)]
 pub fn get_supported_providers() -> Vec<ProviderType> {
@@ -128,5 +436,56 @@ pub fn get_supported_providers() -> Vec<ProviderType> {
         ProviderType::Anthropic,
         ProviderType::Mistral,
         ProviderType::OpenAI,
+        ProviderType::Ollama,
     ]
 }
+
+#[tauri::command]
+pub fn list_tools(tools: State<'_, ToolRegistry>) -> Vec<AnthropicTool> {
+    tools.definitions()
+}
+
+#[tauri::command]
+pub fn get_network_settings<R: Runtime>(app: AppHandle<R>) -> Result<NetworkSettings, AppError> {
+    Ok(network::load_settings(&app)?)
+}
+
+#[tauri::command]
+pub fn save_network_settings<R: Runtime>(
+    app: AppHandle<R>,
+    settings: NetworkSettings,
+) -> Result<(), AppError> {
+    Ok(network::save_settings(&app, &settings)?)
+}
+
+#[tauri::command]
+pub fn get_gateway_settings<R: Runtime>(app: AppHandle<R>) -> Result<GatewaySettings, AppError> {
+    Ok(gateway::load_settings(&app)?)
+}
+
+#[tauri::command]
+pub async fn save_gateway_settings<R: Runtime>(
+    app: AppHandle<R>,
+    settings: GatewaySettings,
+) -> Result<(), AppError> {
+    Ok(gateway::save_settings(&app, &settings).await?)
+}
+
+#[tauri::command]
+pub fn list_gateway_clients<R: Runtime>(app: AppHandle<R>) -> Result<Vec<GatewayClient>, AppError> {
+    Ok(gateway::list_clients(&app)?)
+}
+
+/// Returns the client's bearer token, which cannot be read back later.
+#[tauri::command]
+pub fn create_gateway_client<R: Runtime>(
+    app: AppHandle<R>,
+    name: String,
+) -> Result<String, AppError> {
+    Ok(gateway::create_client(&app, &name)?)
+}
+
+#[tauri::command]
+pub fn delete_gateway_client<R: Runtime>(app: AppHandle<R>, name: String) -> Result<(), AppError> {
+    Ok(gateway::delete_client(&app, &name)?)
+}
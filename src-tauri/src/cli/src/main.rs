@@ -0,0 +1,267 @@
+//! Command line client for le-hey-eye. It uses the credentials, network
+//! settings and topics the desktop app saved, without starting the app.
+
+use clap::{Args, Parser, Subcommand};
+use sea_orm::{DatabaseConnection, EntityTrait, QueryOrder};
+use std::io::{IsTerminal, Read};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use le_hey_eye_lib::core::app_data::AppData;
+use le_hey_eye_lib::core::headless::Headless;
+use le_hey_eye_lib::core::models::{ChatMessage, ChatRequest, ChatRole, ContentType, ProviderType};
+use le_hey_eye_lib::core::sink::{EventSink, StdoutSink};
+use le_hey_eye_lib::core::stream::{self, StopReason, StreamEmitter};
+use le_hey_eye_lib::db::export::{self, ExportFormat};
+use le_hey_eye_lib::db::topics;
+use le_hey_eye_lib::providers::anthropic::AnthropicSystemPrompt;
+
+/// Unlocks the credentials when the vault is protected by a passphrase.
+const PASSPHRASE_ENV: &str = "LE_HEY_EYE_PASSPHRASE";
+
+#[derive(Parser)]
+#[command(name = "le-hey-eye-cli", version, about)]
+struct Cli {
+    /// Data directory of the app, instead of the one the desktop app uses
+    #[arg(long, global = true)]
+    data_dir: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Send a prompt and stream the answer to stdout. Text piped on stdin is
+    /// sent before the prompt, as context.
+    Send(SendArgs),
+    /// List the models of a provider
+    Models {
+        #[arg(long, short, value_parser = parse_provider)]
+        provider: ProviderType,
+        /// Credential profile, the provider's default one otherwise
+        #[arg(long)]
+        profile: Option<String>,
+    },
+    /// List the topics, most recently used first
+    Topics,
+    /// Write a topic as JSON, Markdown or HTML
+    Export {
+        topic_id: i32,
+        /// json, markdown or html. Only JSON can be imported back
+        #[arg(long, short, default_value = "json", value_parser = parse_format)]
+        format: ExportFormat,
+        /// File to write, stdout otherwise
+        #[arg(long, short)]
+        output: Option<PathBuf>,
+    },
+    /// Create topics from a JSON export, a ChatGPT or a Claude.ai export,
+    /// read from stdin when no file is given
+    Import { file: Option<PathBuf> },
+}
+
+#[derive(Args)]
+struct SendArgs {
+    #[arg(long, short, value_parser = parse_provider)]
+    provider: ProviderType,
+    #[arg(long, short)]
+    model: String,
+    /// Credential profile, the provider's default one otherwise
+    #[arg(long)]
+    profile: Option<String>,
+    #[arg(long, short)]
+    system: Option<String>,
+    #[arg(long, default_value_t = 1024)]
+    max_tokens: u32,
+    #[arg(long)]
+    temperature: Option<f32>,
+    /// Print every stream event as a JSON line instead of the answer
+    #[arg(long)]
+    json: bool,
+    prompt: Vec<String>,
+}
+
+fn parse_provider(provider: &str) -> Result<ProviderType, String> {
+    ProviderType::from_str(provider).ok_or_else(|| format!("Unknown provider: {}", provider))
+}
+
+fn parse_format(format: &str) -> Result<ExportFormat, String> {
+    serde_json::from_value(serde_json::Value::String(format.to_lowercase()))
+        .map_err(|_| format!("Unknown export format: {}", format))
+}
+
+#[tokio::main]
+async fn main() {
+    let cli = Cli::parse();
+
+    if let Err(e) = run(cli).await {
+        eprintln!("Error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+async fn run(cli: Cli) -> Result<(), String> {
+    let data = match cli.data_dir {
+        Some(dir) => AppData::new(dir),
+        None => AppData::locate()?,
+    };
+
+    match cli.command {
+        Command::Send(args) => send(data, args).await,
+        Command::Models { provider, profile } => {
+            let headless = open(data)?;
+            let models = headless
+                .list_models(&provider, profile.as_deref())
+                .await
+                .map_err(|e| e.to_string())?;
+
+            for model in models {
+                println!("{}", model);
+            }
+            Ok(())
+        }
+        Command::Topics => {
+            let db = connect(&data).await?;
+            let topics = entity::topics::Entity::find()
+                .order_by_desc(entity::topics::Column::LastAccessedAt)
+                .all(&db)
+                .await
+                .map_err(|e| e.to_string())?;
+
+            for topic in topics {
+                println!(
+                    "{}\t{}\t{}",
+                    topic.id,
+                    topic.last_accessed_at.to_rfc3339(),
+                    topic.name
+                );
+            }
+            Ok(())
+        }
+        Command::Export {
+            topic_id,
+            format,
+            output,
+        } => {
+            let db = connect(&data).await?;
+            let topic = export::collect_topic(&db, topic_id).await?;
+            let rendered = export::render(&topic, format)?;
+
+            match output {
+                Some(path) => std::fs::write(&path, rendered)
+                    .map_err(|e| format!("Failed to write {}: {}", path.display(), e)),
+                None => {
+                    println!("{}", rendered.trim_end());
+                    Ok(())
+                }
+            }
+        }
+        Command::Import { file } => {
+            // Bytes, exports may be zip archives.
+            let bytes = match file {
+                Some(path) => std::fs::read(&path)
+                    .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?,
+                None => {
+                    let mut input = Vec::new();
+                    std::io::stdin()
+                        .read_to_end(&mut input)
+                        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+                    input
+                }
+            };
+
+            let db = connect(&data).await?;
+            for topic in export::import_bytes(&db, &bytes).await? {
+                println!("Imported topic {}: {}", topic.id, topic.name);
+            }
+            Ok(())
+        }
+    }
+}
+
+async fn send(data: AppData, args: SendArgs) -> Result<(), String> {
+    let mut prompt = args.prompt.join(" ");
+    if !std::io::stdin().is_terminal() {
+        let context = read_stdin()?;
+        if !context.trim().is_empty() {
+            prompt = if prompt.is_empty() {
+                context
+            } else {
+                format!("{}\n\n{}", context.trim_end(), prompt)
+            };
+        }
+    }
+    if prompt.trim().is_empty() {
+        return Err("Nothing to send, pass a prompt or pipe some text".to_string());
+    }
+
+    let headless = open(data)?;
+    let request = ChatRequest {
+        messages: vec![ChatMessage {
+            role: ChatRole::User,
+            content: ContentType::PlainText(prompt),
+        }],
+        system: AnthropicSystemPrompt::Simple(args.system.unwrap_or_default()),
+        max_tokens: Some(args.max_tokens),
+        stream: Some(true),
+        temperature: args.temperature,
+        ..Default::default()
+    };
+
+    let sink: Arc<dyn EventSink> = if args.json {
+        Arc::new(StdoutSink::json_lines())
+    } else {
+        Arc::new(StdoutSink::text())
+    };
+    let events = StreamEmitter::new(sink, stream::new_request_id());
+    events.start()?;
+
+    let result = headless
+        .send_message(
+            &events,
+            &args.provider,
+            args.profile.as_deref(),
+            args.model,
+            request,
+        )
+        .await;
+
+    match result {
+        Ok(response) => {
+            if let Some(usage) = &response.usage {
+                events.usage(usage)?;
+            }
+            events.stop(response.stop_reason.unwrap_or(StopReason::EndTurn))
+        }
+        Err(e) => {
+            // The sink already reported it, on stderr or as a JSON line.
+            let _ = events.error(&e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn open(data: AppData) -> Result<Headless, String> {
+    let passphrase = std::env::var(PASSPHRASE_ENV).ok();
+    Headless::open(data, passphrase.as_deref())
+}
+
+async fn connect(data: &AppData) -> Result<DatabaseConnection, String> {
+    let path = data.database_path();
+    if !path.exists() {
+        return Err(format!(
+            "No database at {}, start the app once first",
+            path.display()
+        ));
+    }
+
+    topics::connect(&path).await.map_err(|e| e.to_string())
+}
+
+fn read_stdin() -> Result<String, String> {
+    let mut input = String::new();
+    std::io::stdin()
+        .read_to_string(&mut input)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(input)
+}
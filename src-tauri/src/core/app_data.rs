@@ -0,0 +1,100 @@
+use serde::Deserialize;
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use crate::core::credentials::{self, CREDENTIALS_STORE, PROFILES_STORE};
+use crate::core::network::{self, NetworkSettings, NETWORK_STORE};
+use crate::core::vault::{self, VAULT_STORE};
+use crate::utils::crypto::{self, CredentialKey};
+
+/// Must match `identifier` in `tauri.conf.json`, Tauri names the app data
+/// directory after it.
+pub const APP_IDENTIFIER: &str = "com.le-hey-eye.app";
+
+/// Overrides the data directory, e.g. to run the CLI against a copy.
+pub const DATA_DIR_ENV: &str = "LE_HEY_EYE_DATA_DIR";
+
+pub const DATABASE_FILE: &str = "topics.db";
+
+/// Read access to what the app keeps in its data directory, for processes
+/// running without a Tauri app such as the CLI. Stores are read from disk
+/// as the store plugin wrote them, and nothing is migrated: the app must
+/// have been started once.
+#[derive(Debug, Clone)]
+pub struct AppData {
+    dir: PathBuf,
+}
+
+impl AppData {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// The directory of the desktop app, unless `LE_HEY_EYE_DATA_DIR` is set.
+    pub fn locate() -> Result<Self, String> {
+        if let Some(dir) = std::env::var_os(DATA_DIR_ENV).filter(|dir| !dir.is_empty()) {
+            return Ok(Self::new(dir));
+        }
+
+        dirs::data_dir()
+            .map(|dir| Self::new(dir.join(APP_IDENTIFIER)))
+            .ok_or_else(|| "Could not determine the app data directory".to_string())
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn database_path(&self) -> PathBuf {
+        self.dir.join(DATABASE_FILE)
+    }
+
+    /// Entries of a store, empty when the app never saved it.
+    fn store(&self, name: &str) -> Result<Map<String, Value>, String> {
+        let path = self.dir.join(name);
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Map::new()),
+            Err(e) => return Err(format!("Failed to read {}: {}", path.display(), e)),
+        };
+
+        serde_json::from_slice(&bytes).map_err(|e| format!("Invalid store {}: {}", name, e))
+    }
+
+    pub fn credentials_key(&self, passphrase: Option<&str>) -> Result<CredentialKey, String> {
+        vault::key_from_store(&self.store(VAULT_STORE)?, passphrase)
+    }
+
+    /// Decrypts the credentials of a profile, the provider's default one
+    /// when `profile` is `None`.
+    pub fn credentials<T: for<'de> Deserialize<'de>>(
+        &self,
+        key: &CredentialKey,
+        provider_id: &str,
+        profile: Option<&str>,
+    ) -> Result<T, String> {
+        let profile = match profile {
+            Some(profile) => profile.to_string(),
+            None => credentials::default_profile_in(&self.store(PROFILES_STORE)?, provider_id),
+        };
+        let credentials_key = credentials::profile_key(provider_id, &profile);
+
+        let encrypted_value = self
+            .store(CREDENTIALS_STORE)?
+            .remove(&credentials_key)
+            .ok_or_else(|| format!("No credentials found for key: {}", credentials_key))?;
+
+        let decrypted_value = crypto::decrypt_value(key, &credentials_key, encrypted_value)?;
+
+        serde_json::from_value(decrypted_value)
+            .map_err(|e| format!("Failed to deserialize credentials: {}", e))
+    }
+
+    pub fn network_settings(&self) -> Result<NetworkSettings, String> {
+        match self.store(NETWORK_STORE)?.remove(network::SETTINGS_KEY) {
+            Some(value) => serde_json::from_value(value)
+                .map_err(|e| format!("Invalid network settings: {}", e)),
+            None => Ok(NetworkSettings::default()),
+        }
+    }
+}
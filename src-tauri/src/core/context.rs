@@ -0,0 +1,76 @@
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::collections::HashMap;
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::core::credentials;
+use crate::core::endpoints;
+use crate::core::network;
+use crate::core::retry::{self, RetryPolicy};
+use crate::core::tools::ToolRegistry;
+
+/// What providers need from where they run: the desktop app resolves it from
+/// its managed state and stores, `Headless` from the app data directory.
+#[async_trait]
+pub trait ProviderContext: Send + Sync {
+    /// Decrypted credentials of a profile, the provider's default one when
+    /// `profile` is `None`.
+    async fn raw_credentials(
+        &self,
+        provider_id: &str,
+        profile: Option<&str>,
+    ) -> Result<Value, String>;
+
+    /// Endpoint parameters holding the base URL used for the provider.
+    fn endpoint_params(&self, provider_id: &str) -> Result<HashMap<String, String>, String>;
+
+    fn client(&self) -> Result<reqwest::Client, String>;
+
+    fn retry_policy(&self) -> RetryPolicy;
+
+    fn tools(&self) -> ToolRegistry;
+}
+
+impl dyn ProviderContext + '_ {
+    /// The credentials of a profile, deserialized for its provider.
+    pub async fn credentials<T: DeserializeOwned>(
+        &self,
+        provider_id: &str,
+        profile: Option<&str>,
+    ) -> Result<T, String> {
+        let value = self.raw_credentials(provider_id, profile).await?;
+
+        serde_json::from_value(value)
+            .map_err(|e| format!("Failed to deserialize credentials: {}", e))
+    }
+}
+
+#[async_trait]
+impl<R: Runtime> ProviderContext for AppHandle<R> {
+    async fn raw_credentials(
+        &self,
+        provider_id: &str,
+        profile: Option<&str>,
+    ) -> Result<Value, String> {
+        credentials::get_profile_credentials(self, provider_id, profile).await
+    }
+
+    fn endpoint_params(&self, provider_id: &str) -> Result<HashMap<String, String>, String> {
+        endpoints::endpoint_params(self, provider_id)
+    }
+
+    fn client(&self) -> Result<reqwest::Client, String> {
+        network::client(self)
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        retry::policy(self)
+    }
+
+    fn tools(&self) -> ToolRegistry {
+        self.try_state::<ToolRegistry>()
+            .map(|registry| registry.inner().clone())
+            .unwrap_or_default()
+    }
+}
@@ -1,6 +1,10 @@
-use crate::utils::crypto;
+use crate::core::models::ProviderType;
+use crate::core::vault::CredentialVault;
+use crate::utils::crypto::{self, CredentialKey};
 use serde::{Deserialize, Serialize};
+use tauri::Manager;
 use tauri_plugin_store::StoreExt;
+use ts_rs::TS;
 
 pub const CREDENTIALS_STORE: &str = "credentials.json";
 
@@ -20,6 +24,18 @@ pub struct MistralCredentials {
     pub api_key: String,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OpenAICredentials {
+    pub api_key: String,
+    pub base_url: Option<String>,
+    pub organization: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct OllamaCredentials {
+    pub host: String,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProviderCredentials {
     pub provider_id: String,
@@ -29,16 +45,288 @@ pub struct ProviderCredentials {
 pub const CLOUDFLARE_CREDENTIALS_KEY: &str = "CLOUDFLARE_CREDENTIALS";
 pub const ANTHROPIC_CREDENTIALS_KEY: &str = "ANTHROPIC_CREDENTIALS";
 pub const MISTRAL_CREDENTIALS_KEY: &str = "MISTRAL_CREDENTIALS";
+pub const OPENAI_CREDENTIALS_KEY: &str = "OPENAI_CREDENTIALS";
+pub const OLLAMA_CREDENTIALS_KEY: &str = "OLLAMA_CREDENTIALS";
+
+/// Holds the default profile of each provider, outside of the encrypted store.
+pub const PROFILES_STORE: &str = "credential_profiles.json";
+pub const DEFAULT_PROFILE: &str = "default";
 
+const DEFAULT_PROFILES_KEY: &str = "defaults";
+const PROVIDER_IDS: [&str; 5] = ["cloudflare", "anthropic", "mistral", "openai", "ollama"];
+
+/// Store key used by the single credentials blob of each provider, before
+/// profiles existed.
 pub fn get_credentials_key(provider_id: &str) -> &'static str {
     match provider_id {
         "cloudflare" => CLOUDFLARE_CREDENTIALS_KEY,
         "anthropic" => ANTHROPIC_CREDENTIALS_KEY,
         "mistral" => MISTRAL_CREDENTIALS_KEY,
+        "openai" => OPENAI_CREDENTIALS_KEY,
+        "ollama" => OLLAMA_CREDENTIALS_KEY,
         _ => panic!("Unknown provider: {}", provider_id),
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../shared/types/llm/core.ts",
+    rename_all = "camelCase"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct CredentialProfile {
+    pub provider: ProviderType,
+    pub name: String,
+    pub is_default: bool,
+}
+
+/// Profiles are stored as `<provider>/<name>`, e.g. `anthropic/work`.
+pub fn profile_key(provider_id: &str, profile: &str) -> String {
+    format!("{}/{}", provider_id, profile)
+}
+
+pub fn validate_profile_name(profile: &str) -> Result<(), String> {
+    if profile.trim().is_empty() {
+        return Err("Profile name cannot be empty".to_string());
+    }
+    if profile.contains('/') {
+        return Err(format!(
+            "Invalid profile name '{}': '/' is not allowed",
+            profile
+        ));
+    }
+    Ok(())
+}
+
+fn default_profiles<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> Result<serde_json::Map<String, serde_json::Value>, String> {
+    let store = app.store(PROFILES_STORE).map_err(|e| e.to_string())?;
+
+    Ok(match store.get(DEFAULT_PROFILES_KEY) {
+        Some(serde_json::Value::Object(defaults)) => defaults,
+        _ => serde_json::Map::new(),
+    })
+}
+
+fn save_default_profiles<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    defaults: serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    let store = app.store(PROFILES_STORE).map_err(|e| e.to_string())?;
+
+    store.set(DEFAULT_PROFILES_KEY, serde_json::Value::Object(defaults));
+    store.save().map_err(|e| e.to_string())
+}
+
+pub fn default_profile<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+) -> Result<String, String> {
+    Ok(default_profiles(app)?
+        .get(provider_id)
+        .and_then(|profile| profile.as_str())
+        .unwrap_or(DEFAULT_PROFILE)
+        .to_string())
+}
+
+/// Same as `default_profile`, reading the entries of the profiles store
+/// loaded from disk.
+pub fn default_profile_in(
+    profiles_store: &serde_json::Map<String, serde_json::Value>,
+    provider_id: &str,
+) -> String {
+    profiles_store
+        .get(DEFAULT_PROFILES_KEY)
+        .and_then(|defaults| defaults.get(provider_id))
+        .and_then(|profile| profile.as_str())
+        .unwrap_or(DEFAULT_PROFILE)
+        .to_string()
+}
+
+/// Picks the requested profile, falling back to the provider's default one.
+pub fn resolve_profile<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: Option<&str>,
+) -> Result<String, String> {
+    match profile {
+        Some(profile) => Ok(profile.to_string()),
+        None => default_profile(app, provider_id),
+    }
+}
+
+pub fn profile_exists<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: &str,
+) -> Result<bool, String> {
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+
+    Ok(store.has(profile_key(provider_id, profile)))
+}
+
+pub fn list_profiles<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: Option<&str>,
+) -> Result<Vec<CredentialProfile>, String> {
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+    let defaults = default_profiles(app)?;
+
+    let mut profiles: Vec<CredentialProfile> = store
+        .keys()
+        .into_iter()
+        .filter_map(|key| {
+            let (provider, name) = key.split_once('/')?;
+            if provider_id.is_some_and(|provider_id| provider_id != provider) {
+                return None;
+            }
+            let default = defaults
+                .get(provider)
+                .and_then(|profile| profile.as_str())
+                .unwrap_or(DEFAULT_PROFILE);
+
+            Some(CredentialProfile {
+                provider: ProviderType::from_str(provider)?,
+                name: name.to_string(),
+                is_default: name == default,
+            })
+        })
+        .collect();
+
+    profiles.sort_by(|a, b| (a.provider.as_str(), &a.name).cmp(&(b.provider.as_str(), &b.name)));
+
+    Ok(profiles)
+}
+
+pub fn set_default_profile<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: &str,
+) -> Result<(), String> {
+    if !profile_exists(app, provider_id, profile)? {
+        return Err(format!(
+            "No credentials found for profile: {}",
+            profile_key(provider_id, profile)
+        ));
+    }
+
+    let mut defaults = default_profiles(app)?;
+    defaults.insert(provider_id.to_string(), profile.into());
+    save_default_profiles(app, defaults)
+}
+
+/// Renames a profile. The encryption context is the store key, so the
+/// credentials are re-encrypted under the new name.
+pub fn rename_profile<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    from: &str,
+    to: &str,
+) -> Result<(), String> {
+    validate_profile_name(to)?;
+
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+    let (from_key, to_key) = (profile_key(provider_id, from), profile_key(provider_id, to));
+
+    let encrypted_value = store
+        .get(&from_key)
+        .ok_or_else(|| format!("No credentials found for profile: {}", from_key))?;
+    if store.has(&to_key) {
+        return Err(format!("Profile already exists: {}", to_key));
+    }
+
+    let key = vault_key(app)?;
+    let value = crypto::decrypt_value(&key, &from_key, encrypted_value)?;
+    store.set(to_key.clone(), crypto::encrypt_value(&key, &to_key, value)?);
+    store.delete(&from_key);
+    store.save().map_err(|e| e.to_string())?;
+
+    if default_profile(app, provider_id)? == from {
+        let mut defaults = default_profiles(app)?;
+        defaults.insert(provider_id.to_string(), to.into());
+        save_default_profiles(app, defaults)?;
+    }
+
+    Ok(())
+}
+
+pub fn delete_profile<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: &str,
+) -> Result<(), String> {
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+    let key = profile_key(provider_id, profile);
+
+    if !store.delete(&key) {
+        return Err(format!("No credentials found for profile: {}", key));
+    }
+    store.save().map_err(|e| e.to_string())?;
+
+    let mut defaults = default_profiles(app)?;
+    if defaults
+        .get(provider_id)
+        .and_then(|default| default.as_str())
+        == Some(profile)
+    {
+        defaults.remove(provider_id);
+        save_default_profiles(app, defaults)?;
+    }
+
+    Ok(())
+}
+
+/// Moves the single blob each provider had before profiles into its
+/// `default` profile.
+pub fn migrate_to_profiles<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    key: &CredentialKey,
+) -> Result<usize, String> {
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+
+    let migrated = PROVIDER_IDS
+        .iter()
+        .filter_map(|provider_id| {
+            let legacy_key = get_credentials_key(provider_id);
+            store
+                .get(legacy_key)
+                .map(|value| (provider_id, legacy_key, value))
+        })
+        .map(|(provider_id, legacy_key, value)| {
+            let new_key = profile_key(provider_id, DEFAULT_PROFILE);
+            let value = crypto::decrypt_value(key, legacy_key, value)?;
+            Ok((
+                legacy_key,
+                new_key.clone(),
+                crypto::encrypt_value(key, &new_key, value)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if migrated.is_empty() {
+        return Ok(0);
+    }
+
+    let count = migrated.len();
+    for (legacy_key, new_key, value) in migrated {
+        if !store.has(&new_key) {
+            store.set(new_key, value);
+        }
+        store.delete(legacy_key);
+    }
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(count)
+}
+
+fn vault_key<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<CredentialKey, String> {
+    app.try_state::<CredentialVault>()
+        .ok_or("Credential vault is not initialized")?
+        .key()
+}
+
 pub async fn save_credentials<T: Serialize, R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
     credentials_key: &str,
@@ -47,7 +335,7 @@ pub async fn save_credentials<T: Serialize, R: tauri::Runtime>(
     let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
     let json_value = serde_json::to_value(credentials).map_err(|e| e.to_string())?;
 
-    let encrypted_value = crypto::encrypt_value(json_value)?;
+    let encrypted_value = crypto::encrypt_value(&vault_key(app)?, credentials_key, json_value)?;
 
     store.set(credentials_key.to_string(), encrypted_value);
 
@@ -64,49 +352,106 @@ pub async fn get_credentials<T: for<'de> Deserialize<'de>, R: tauri::Runtime>(
         .get(credentials_key)
         .ok_or_else(|| format!("No credentials found for key: {}", credentials_key))?;
 
-    let decrypted_value = crypto::decrypt_value(encrypted_value)?;
+    let decrypted_value =
+        crypto::decrypt_value(&vault_key(app)?, credentials_key, encrypted_value)?;
 
     serde_json::from_value::<T>(decrypted_value)
         .map_err(|e| format!("Failed to deserialize credentials: {}", e))
 }
 
+pub async fn save_profile_credentials<T: Serialize, R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: Option<&str>,
+    credentials: &T,
+) -> Result<(), String> {
+    let profile = resolve_profile(app, provider_id, profile)?;
+    validate_profile_name(&profile)?;
+
+    save_credentials(app, &profile_key(provider_id, &profile), credentials).await
+}
+
+pub async fn get_profile_credentials<T: for<'de> Deserialize<'de>, R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    provider_id: &str,
+    profile: Option<&str>,
+) -> Result<T, String> {
+    let profile = resolve_profile(app, provider_id, profile)?;
+
+    get_credentials(app, &profile_key(provider_id, &profile)).await
+}
+
 pub async fn get_cloudflare_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
 ) -> Result<CloudflareCredentials, String> {
-    get_credentials(app, CLOUDFLARE_CREDENTIALS_KEY).await
+    get_profile_credentials(app, "cloudflare", profile).await
 }
 
 pub async fn save_cloudflare_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
     credentials: &CloudflareCredentials,
 ) -> Result<(), String> {
-    save_credentials(app, CLOUDFLARE_CREDENTIALS_KEY, credentials).await
+    save_profile_credentials(app, "cloudflare", profile, credentials).await
 }
 
 pub async fn save_anthropic_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
     credentials: &AnthropicCredentials,
 ) -> Result<(), String> {
-    save_credentials(app, ANTHROPIC_CREDENTIALS_KEY, credentials).await
+    save_profile_credentials(app, "anthropic", profile, credentials).await
 }
 
 pub async fn get_anthropic_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
 ) -> Result<AnthropicCredentials, String> {
-    get_credentials(app, ANTHROPIC_CREDENTIALS_KEY).await
+    get_profile_credentials(app, "anthropic", profile).await
 }
 
-#[allow(dead_code)]
 pub async fn save_mistral_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
     credentials: &MistralCredentials,
 ) -> Result<(), String> {
-    save_credentials(app, MISTRAL_CREDENTIALS_KEY, credentials).await
+    save_profile_credentials(app, "mistral", profile, credentials).await
 }
 
-#[allow(dead_code)]
 pub async fn get_mistral_credentials<R: tauri::Runtime>(
     app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
 ) -> Result<MistralCredentials, String> {
-    get_credentials(app, MISTRAL_CREDENTIALS_KEY).await
+    get_profile_credentials(app, "mistral", profile).await
+}
+
+pub async fn save_openai_credentials<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
+    credentials: &OpenAICredentials,
+) -> Result<(), String> {
+    save_profile_credentials(app, "openai", profile, credentials).await
+}
+
+pub async fn get_openai_credentials<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
+) -> Result<OpenAICredentials, String> {
+    get_profile_credentials(app, "openai", profile).await
+}
+
+pub async fn save_ollama_credentials<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
+    credentials: &OllamaCredentials,
+) -> Result<(), String> {
+    save_profile_credentials(app, "ollama", profile, credentials).await
+}
+
+pub async fn get_ollama_credentials<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    profile: Option<&str>,
+) -> Result<OllamaCredentials, String> {
+    get_profile_credentials(app, "ollama", profile).await
 }
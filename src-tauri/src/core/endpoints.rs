@@ -1,42 +1,161 @@
 use std::collections::HashMap;
+use tauri::{AppHandle, Manager, Runtime};
 
 use super::models::{ChatRequest, ProviderType};
 
+/// URL templates of a provider. `{api_base}` is replaced by the base URL
+/// override of the provider when there is one, by `api_base` otherwise.
 pub struct ProviderEndpoints {
+    pub api_base: &'static str,
     pub api_url: &'static str,
     pub models_url: Option<&'static str>,
 }
 
+pub const CLOUDFLARE_API_BASE: &str = "https://api.cloudflare.com/client/v4";
+
 pub const CLOUDFLARE: ProviderEndpoints = ProviderEndpoints {
-    api_url: "https://api.cloudflare.com/client/v4/accounts/{account_id}/ai/run/{model}",
-    models_url: Some("https://api.cloudflare.com/client/v4/accounts/{account_id}/ai/models"),
+    api_base: CLOUDFLARE_API_BASE,
+    api_url: "{api_base}/accounts/{account_id}/ai/run/{model}",
+    models_url: Some("{api_base}/accounts/{account_id}/ai/models"),
 };
 
 pub const ANTHROPIC: ProviderEndpoints = ProviderEndpoints {
-    api_url: "https://api.anthropic.com/v1/messages",
-    models_url: Some("https://api.anthropic.com/v1/models"),
+    api_base: "https://api.anthropic.com",
+    api_url: "{api_base}/v1/messages",
+    models_url: Some("{api_base}/v1/models"),
 };
 
 pub const MISTRAL: ProviderEndpoints = ProviderEndpoints {
-    api_url: "https://api.mistral.ai/v1/chat/completions",
-    models_url: Some("https://api.mistral.ai/v1/models"),
+    api_base: "https://api.mistral.ai",
+    api_url: "{api_base}/v1/chat/completions",
+    models_url: Some("{api_base}/v1/models"),
+};
+
+// OpenAI and Ollama take their base URL from the credentials.
+pub const OPENAI: ProviderEndpoints = ProviderEndpoints {
+    api_base: OPENAI_DEFAULT_BASE_URL,
+    api_url: "{base_url}/chat/completions",
+    models_url: Some("{base_url}/models"),
+};
+
+pub const OPENAI_DEFAULT_BASE_URL: &str = "https://api.openai.com/v1";
+
+pub const OLLAMA: ProviderEndpoints = ProviderEndpoints {
+    api_base: OLLAMA_DEFAULT_HOST,
+    api_url: "{host}/api/chat",
+    models_url: Some("{host}/api/tags"),
 };
 
+pub const OLLAMA_DEFAULT_HOST: &str = "http://localhost:11434";
+
+/// Base URLs replacing the default ones, e.g. to point a provider at a
+/// local mock server or at an internal gateway.
+#[derive(Debug, Clone, Default)]
+pub struct EndpointOverrides {
+    base_urls: HashMap<String, String>,
+}
+
+impl EndpointOverrides {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Reads `LE_HEY_EYE_<PROVIDER>_BASE_URL` for each provider.
+    pub fn from_env() -> Self {
+        ["anthropic", "cloudflare", "mistral"].into_iter().fold(
+            Self::new(),
+            |overrides, provider| {
+                let variable = format!("LE_HEY_EYE_{}_BASE_URL", provider.to_uppercase());
+                match std::env::var(variable) {
+                    Ok(base_url) if !base_url.trim().is_empty() => {
+                        overrides.with_base_url(provider, base_url.trim())
+                    }
+                    _ => overrides,
+                }
+            },
+        )
+    }
+
+    pub fn with_base_url(mut self, provider: &str, base_url: &str) -> Self {
+        self.base_urls.insert(
+            provider.to_string(),
+            base_url.trim_end_matches('/').to_string(),
+        );
+        self
+    }
+
+    pub fn base_url(&self, provider: &str) -> Option<&str> {
+        self.base_urls.get(provider).map(String::as_str)
+    }
+
+    /// Base URL used for the provider, its override or the default.
+    pub fn api_base(&self, provider: &str) -> Result<String, String> {
+        match self.base_url(provider) {
+            Some(base_url) => Ok(base_url.to_string()),
+            None => Ok(get_provider_endpoints(provider)?.api_base.to_string()),
+        }
+    }
+
+    /// Endpoint parameters holding the base URL used for the provider.
+    pub fn endpoint_params(&self, provider: &str) -> Result<HashMap<String, String>, String> {
+        Ok(HashMap::from([(
+            "api_base".to_string(),
+            self.api_base(provider)?,
+        )]))
+    }
+}
+
+/// Base URL the app uses for the provider, its override or the default.
+pub fn api_base<R: Runtime>(app: &AppHandle<R>, provider: &str) -> Result<String, String> {
+    match app.try_state::<EndpointOverrides>() {
+        Some(overrides) => overrides.api_base(provider),
+        None => EndpointOverrides::new().api_base(provider),
+    }
+}
+
+/// Endpoint parameters holding the base URL the app uses for the provider.
+pub fn endpoint_params<R: Runtime>(
+    app: &AppHandle<R>,
+    provider: &str,
+) -> Result<HashMap<String, String>, String> {
+    match app.try_state::<EndpointOverrides>() {
+        Some(overrides) => overrides.endpoint_params(provider),
+        None => EndpointOverrides::new().endpoint_params(provider),
+    }
+}
+
 pub fn get_provider_endpoints(provider_id: &str) -> Result<&'static ProviderEndpoints, String> {
     match provider_id {
         "cloudflare" => Ok(&CLOUDFLARE),
         "anthropic" => Ok(&ANTHROPIC),
         "mistral" => Ok(&MISTRAL),
+        "openai" => Ok(&OPENAI),
+        "ollama" => Ok(&OLLAMA),
         _ => Err(format!("Unknown provider: {}", provider_id)),
     }
 }
 
+/// Base URL of the provider, for requests outside its chat and models endpoints.
+pub fn get_api_base(
+    provider: &str,
+    params: Option<&HashMap<String, String>>,
+) -> Result<String, String> {
+    let endpoints = get_provider_endpoints(provider)?;
+    Ok(with_default_base(
+        format_endpoint("{api_base}", params),
+        endpoints,
+    ))
+}
+
 pub fn get_api_url(
     provider: &str,
     params: Option<&HashMap<String, String>>,
 ) -> Result<String, String> {
     let endpoints = get_provider_endpoints(provider)?;
-    Ok(format_endpoint(endpoints.api_url, params))
+    Ok(with_default_base(
+        format_endpoint(endpoints.api_url, params),
+        endpoints,
+    ))
 }
 
 pub fn get_models_url(
@@ -45,7 +164,7 @@ pub fn get_models_url(
 ) -> Result<String, String> {
     let endpoints = get_provider_endpoints(provider)?;
     match &endpoints.models_url {
-        Some(url) => Ok(format_endpoint(url, params)),
+        Some(url) => Ok(with_default_base(format_endpoint(url, params), endpoints)),
         None => Err(format!(
             "Provider {} doesn't have a models URL configured",
             provider
@@ -62,6 +181,10 @@ pub fn get_model_schema_url(
     Ok(format!("{}/schema?model={}", models_url, model))
 }
 
+fn with_default_base(url: String, endpoints: &ProviderEndpoints) -> String {
+    url.replace("{api_base}", endpoints.api_base)
+}
+
 pub fn format_endpoint(url: &str, params: Option<&HashMap<String, String>>) -> String {
     let mut result = url.to_string();
     if let Some(params) = params {
@@ -87,7 +210,7 @@ pub fn prepare_request_for_provider(
             params.insert("model".to_string(), model.to_string());
             (request, Some(params))
         }
-        "mistral" => {
+        "mistral" | "openai" | "ollama" => {
             request.model = Some(model.to_string());
             (request, None)
         }
@@ -0,0 +1,211 @@
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, thiserror::Error, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AppError {
+    /// The provider refused the request, either with an HTTP error status or
+    /// with an error event in the middle of a stream (no status then).
+    #[error("{provider} API error: {message}")]
+    Api {
+        provider: String,
+        #[ts(optional)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        status: Option<u16>,
+        #[ts(optional)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        error_type: Option<String>,
+        /// Seconds to wait before retrying, from the `retry-after` header.
+        #[ts(optional)]
+        #[serde(skip_serializing_if = "Option::is_none")]
+        retry_after: Option<u64>,
+        message: String,
+    },
+    /// The provider could not be reached or the connection dropped.
+    #[error("{provider} network error: {message}")]
+    Network { provider: String, message: String },
+    #[error("{message}")]
+    Internal { message: String },
+}
+
+impl From<String> for AppError {
+    fn from(message: String) -> Self {
+        AppError::Internal { message }
+    }
+}
+
+impl From<&str> for AppError {
+    fn from(message: &str) -> Self {
+        AppError::Internal {
+            message: message.to_string(),
+        }
+    }
+}
+
+impl AppError {
+    pub fn network(provider: &str, error: impl std::fmt::Display) -> Self {
+        AppError::Network {
+            provider: provider.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Seconds the provider asked to wait before retrying, if it said so.
+    pub fn retry_after(&self) -> Option<u64> {
+        match self {
+            AppError::Api { retry_after, .. } => *retry_after,
+            _ => None,
+        }
+    }
+
+    /// Builds the error of a failed HTTP response, reading the error JSON of
+    /// the provider when it has one.
+    pub fn from_response(
+        provider: &str,
+        status: StatusCode,
+        headers: &HeaderMap,
+        body: &str,
+    ) -> Self {
+        let (error_type, message) = match serde_json::from_str::<Value>(body) {
+            Ok(json) => parse_error_body(&json),
+            Err(_) => (None, None),
+        };
+
+        let message = message
+            .or_else(|| Some(body.trim().to_string()).filter(|body| !body.is_empty()))
+            .unwrap_or_else(|| {
+                status
+                    .canonical_reason()
+                    .unwrap_or("Unknown error")
+                    .to_string()
+            });
+
+        AppError::Api {
+            provider: provider.to_string(),
+            status: Some(status.as_u16()),
+            error_type,
+            retry_after: retry_after(headers),
+            message,
+        }
+    }
+
+    /// Builds the error of an `error` event received mid-stream.
+    pub fn from_stream_error(provider: &str, error: &Value) -> Self {
+        let (error_type, message) = parse_error_body(error);
+
+        AppError::Api {
+            provider: provider.to_string(),
+            status: None,
+            error_type,
+            retry_after: None,
+            message: message.unwrap_or_else(|| error.to_string()),
+        }
+    }
+}
+
+fn retry_after(headers: &HeaderMap) -> Option<u64> {
+    headers
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.trim().parse::<f64>().ok())
+        .map(|seconds| seconds.ceil() as u64)
+}
+
+/// Reads the error type and message out of the error JSON of the providers:
+/// - Anthropic: `{ "type": "error", "error": { "type", "message" } }`
+/// - OpenAI: `{ "error": { "type", "code", "message" } }`
+/// - Cloudflare: `{ "success": false, "errors": [{ "code", "message" }] }`
+/// - Mistral: `{ "object": "error", "type", "message" }`
+/// - Ollama: `{ "error": "message" }`
+fn parse_error_body(json: &Value) -> (Option<String>, Option<String>) {
+    let as_string = |value: Option<&Value>| value.and_then(Value::as_str).map(String::from);
+
+    if let Some(errors) = json.get("errors").and_then(Value::as_array) {
+        if let Some(first) = errors.first() {
+            let code = first.get("code").map(|code| match code {
+                Value::String(code) => code.clone(),
+                code => code.to_string(),
+            });
+            let message = errors
+                .iter()
+                .filter_map(|error| error.get("message").and_then(Value::as_str))
+                .collect::<Vec<_>>()
+                .join(", ");
+            return (code, Some(message).filter(|message| !message.is_empty()));
+        }
+    }
+
+    match json.get("error") {
+        Some(Value::String(message)) => (None, Some(message.clone())),
+        Some(error @ Value::Object(_)) => (
+            as_string(error.get("type")).or_else(|| as_string(error.get("code"))),
+            as_string(error.get("message")),
+        ),
+        _ => (
+            as_string(json.get("type")).filter(|error_type| error_type != "error"),
+            as_string(json.get("message")).or_else(|| as_string(json.get("detail"))),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::{HeaderValue, RETRY_AFTER};
+
+    #[test]
+    fn parses_anthropic_errors() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("12"));
+
+        let error = AppError::from_response(
+            "anthropic",
+            StatusCode::from_u16(529).unwrap(),
+            &headers,
+            r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#,
+        );
+
+        assert_eq!(
+            error,
+            AppError::Api {
+                provider: "anthropic".to_string(),
+                status: Some(529),
+                error_type: Some("overloaded_error".to_string()),
+                retry_after: Some(12),
+                message: "Overloaded".to_string(),
+            }
+        );
+    }
+
+    #[test]
+    fn parses_cloudflare_errors() {
+        let error = AppError::from_response(
+            "cloudflare",
+            StatusCode::FORBIDDEN,
+            &HeaderMap::new(),
+            r#"{"success":false,"errors":[{"code":10000,"message":"Authentication error"}],"messages":[],"result":null}"#,
+        );
+
+        assert!(matches!(
+            error,
+            AppError::Api { status: Some(403), error_type: Some(ref code), ref message, .. }
+                if code == "10000" && message == "Authentication error"
+        ));
+    }
+
+    #[test]
+    fn falls_back_to_the_raw_body() {
+        let error = AppError::from_response(
+            "ollama",
+            StatusCode::BAD_GATEWAY,
+            &HeaderMap::new(),
+            "upstream unavailable",
+        );
+
+        assert_eq!(error.to_string(), "ollama API error: upstream unavailable");
+    }
+}
@@ -0,0 +1,105 @@
+use async_trait::async_trait;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::core::app_data::AppData;
+use crate::core::context::ProviderContext;
+use crate::core::endpoints::EndpointOverrides;
+use crate::core::error::AppError;
+use crate::core::llm_trait::{self, ProviderRegistry};
+use crate::core::models::{ChatRequest, ProviderType, StreamResponse};
+use crate::core::retry::RetryPolicy;
+use crate::core::stream::StreamEmitter;
+use crate::core::tools::ToolRegistry;
+use crate::utils::crypto::CredentialKey;
+
+/// Runs the providers without a Tauri app, with the credentials, network
+/// settings and tools the desktop app uses. Calls go through the same
+/// `ProviderRegistry`, `Headless` standing in for the app as their context.
+pub struct Headless {
+    data: AppData,
+    key: CredentialKey,
+    client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    endpoints: EndpointOverrides,
+    tools: ToolRegistry,
+    providers: ProviderRegistry,
+}
+
+impl Headless {
+    pub fn open(data: AppData, passphrase: Option<&str>) -> Result<Self, String> {
+        let key = data.credentials_key(passphrase)?;
+        let network_settings = data.network_settings()?;
+        let client = network_settings.build_client()?;
+
+        Ok(Self {
+            data,
+            key,
+            client,
+            retry_policy: network_settings.retry_policy(),
+            endpoints: EndpointOverrides::from_env(),
+            tools: ToolRegistry::with_defaults(),
+            providers: ProviderRegistry::with_defaults(),
+        })
+    }
+
+    pub fn data(&self) -> &AppData {
+        &self.data
+    }
+
+    pub async fn send_message(
+        &self,
+        events: &StreamEmitter,
+        provider: &ProviderType,
+        profile: Option<&str>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        llm_trait::get_provider(&self.providers, provider)?
+            .send_message(
+                events.clone(),
+                self,
+                profile.map(str::to_string),
+                model,
+                request,
+            )
+            .await
+    }
+
+    pub async fn list_models(
+        &self,
+        provider: &ProviderType,
+        profile: Option<&str>,
+    ) -> Result<Vec<String>, AppError> {
+        llm_trait::get_provider(&self.providers, provider)?
+            .list_models(self, profile.map(str::to_string))
+            .await
+    }
+}
+
+#[async_trait]
+impl ProviderContext for Headless {
+    async fn raw_credentials(
+        &self,
+        provider_id: &str,
+        profile: Option<&str>,
+    ) -> Result<Value, String> {
+        self.data.credentials(&self.key, provider_id, profile)
+    }
+
+    fn endpoint_params(&self, provider_id: &str) -> Result<HashMap<String, String>, String> {
+        self.endpoints.endpoint_params(provider_id)
+    }
+
+    fn client(&self) -> Result<reqwest::Client, String> {
+        Ok(self.client.clone())
+    }
+
+    fn retry_policy(&self) -> RetryPolicy {
+        self.retry_policy.clone()
+    }
+
+    fn tools(&self) -> ToolRegistry {
+        self.tools.clone()
+    }
+}
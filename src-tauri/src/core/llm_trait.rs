@@ -1,12 +1,59 @@
 use crate::{
+    core::context::ProviderContext,
+    core::error::AppError,
     core::models::{ChatRequest, ProviderType, StreamResponse},
+    core::stream::StreamEmitter,
+    core::validation::CredentialValidation,
     providers::anthropic::{AnthropicChatRequest, AnthropicResponse},
 };
+use async_trait::async_trait;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tauri::{AppHandle, Runtime, Window};
 
+#[async_trait]
 pub trait LLMProvider: Send + Sync {
     fn provider_id(&self) -> &'static str;
+
+    async fn send_message(
+        &self,
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError>;
+
+    async fn list_models(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<Vec<String>, AppError>;
+
+    async fn model_details(
+        &self,
+        _context: &dyn ProviderContext,
+        _profile: Option<String>,
+        _model: String,
+    ) -> Result<serde_json::Value, AppError> {
+        Err(format!(
+            "Model details are not supported by provider: {}",
+            self.provider_id()
+        )
+        .into())
+    }
+
+    async fn has_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError>;
+
+    /// Makes a cheap authenticated call to check the credentials actually work.
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError>;
 }
 
 pub trait AnthropicAdapter {
@@ -19,115 +66,48 @@ pub trait AnthropicAdapter {
 //     fn adapt_response(&self, response: CloudflareResponse) -> StreamResponse;
 // }
 
-#[allow(dead_code)]
-pub fn get_provider(provider_id: &str) -> Option<Box<dyn LLMProvider>> {
-    match ProviderType::from_str(provider_id) {
-        Some(ProviderType::Cloudflare) => {
-            let provider = crate::providers::cloudflare::CloudflareProvider {};
-            struct CloudflareProviderWrapper(crate::providers::cloudflare::CloudflareProvider);
-            impl LLMProvider for CloudflareProviderWrapper {
-                fn provider_id(&self) -> &'static str {
-                    "cloudflare"
-                }
-            }
-            Some(Box::new(CloudflareProviderWrapper(provider)))
-        }
-        Some(ProviderType::Anthropic) => {
-            let provider = crate::providers::anthropic::AnthropicProvider {};
-            struct AnthropicProviderWrapper(crate::providers::anthropic::AnthropicProvider);
-            impl LLMProvider for AnthropicProviderWrapper {
-                fn provider_id(&self) -> &'static str {
-                    "anthropic"
-                }
-            }
-            Some(Box::new(AnthropicProviderWrapper(provider)))
-        }
-        Some(ProviderType::Mistral) => None,
-        Some(ProviderType::OpenAI) => None,
-        None => None,
-    }
+pub struct ProviderRegistry {
+    providers: HashMap<&'static str, Arc<dyn LLMProvider>>,
 }
 
-#[allow(dead_code)]
-pub async fn send_message<R: Runtime>(
-    provider: &dyn LLMProvider,
-    window: Window<R>,
-    app: AppHandle<R>,
-    model: String,
-    request: ChatRequest,
-) -> Result<StreamResponse, String> {
-    let app_arc = Arc::new(app);
-
-    match provider.provider_id() {
-        "cloudflare" => {
-            let provider = crate::providers::cloudflare::CloudflareProvider {};
-            let handle = provider.send_message_impl(window, app_arc, model, request);
-            handle.await.unwrap()
-        }
-        "anthropic" => {
-            let provider = crate::providers::anthropic::AnthropicProvider {};
-            let handle = provider.send_message_impl(window, app_arc, model, request);
-            handle.await.unwrap()
+impl ProviderRegistry {
+    pub fn new() -> Self {
+        Self {
+            providers: HashMap::new(),
         }
-        _ => Err(format!("Unsupported provider: {}", provider.provider_id())),
     }
-}
-
-#[allow(dead_code)]
-pub async fn list_models<R: Runtime>(
-    provider: &dyn LLMProvider,
-    app: AppHandle<R>,
-) -> Result<Vec<String>, String> {
-    let app_arc = Arc::new(app);
 
-    match provider.provider_id() {
-        "anthropic" => {
-            let provider = crate::providers::anthropic::AnthropicProvider {};
-            let handle = provider.list_models_impl(app_arc);
-            handle.await.unwrap()
-        }
-        "cloudflare" => {
-            let provider = crate::providers::cloudflare::CloudflareProvider {};
-            let handle = provider.list_models_impl(app_arc);
-            handle.await.unwrap()
-        }
-        _ => Err(format!("Unsupported provider: {}", provider.provider_id())),
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(crate::providers::cloudflare::CloudflareProvider {});
+        registry.register(crate::providers::anthropic::AnthropicProvider {});
+        registry.register(crate::providers::mistral::MistralProvider {});
+        registry.register(crate::providers::openai::OpenAIProvider {});
+        registry.register(crate::providers::ollama::OllamaProvider {});
+        registry
     }
-}
 
-#[allow(dead_code)]
-pub async fn get_model_details<R: Runtime>(
-    provider: &dyn LLMProvider,
-    app: AppHandle<R>,
-    model: String,
-) -> Result<serde_json::Value, String> {
-    let app_arc = Arc::new(app);
+    pub fn register<P: LLMProvider + 'static>(&mut self, provider: P) {
+        self.providers
+            .insert(provider.provider_id(), Arc::new(provider));
+    }
 
-    // Create a new provider instance
-    match provider.provider_id() {
-        "cloudflare" => {
-            let provider = crate::providers::cloudflare::CloudflareProvider {};
-            let handle = provider.get_model_details_impl(app_arc, model);
-            handle.await.unwrap()
-        }
-        _ => Err(format!("Unsupported provider: {}", provider.provider_id())),
+    pub fn get(&self, provider_id: &str) -> Option<Arc<dyn LLMProvider>> {
+        self.providers.get(provider_id).cloned()
     }
 }
 
-#[allow(dead_code)]
-pub async fn has_credentials<R: Runtime>(
-    provider: &dyn LLMProvider,
-    app: AppHandle<R>,
-) -> Result<bool, String> {
-    let app_arc = Arc::new(app);
-
-    // Create a new provider instance
-    match provider.provider_id() {
-        "cloudflare" => {
-            let provider = crate::providers::cloudflare::CloudflareProvider {};
-            let handle = provider.has_credentials_impl(app_arc);
-            handle.await.unwrap()
-        }
-        _ => Err(format!("Unsupported provider: {}", provider.provider_id())),
+impl Default for ProviderRegistry {
+    fn default() -> Self {
+        Self::new()
     }
 }
+
+pub fn get_provider(
+    registry: &ProviderRegistry,
+    provider: &ProviderType,
+) -> Result<Arc<dyn LLMProvider>, AppError> {
+    registry
+        .get(provider.as_str())
+        .ok_or_else(|| format!("Unsupported provider: {}", provider.as_str()).into())
+}
@@ -1,4 +1,16 @@
+pub mod app_data;
+pub mod context;
 pub mod credentials;
 pub mod endpoints;
+pub mod error;
+pub mod headless;
 pub mod llm_trait;
 pub mod models;
+pub mod network;
+pub mod retry;
+pub mod sink;
+pub mod sse;
+pub mod stream;
+pub mod tools;
+pub mod validation;
+pub mod vault;
@@ -1,6 +1,7 @@
 use serde::{Deserialize, Serialize};
 use ts_rs::TS;
 
+use crate::core::stream::StopReason;
 use crate::providers::anthropic::{
     AnthropicSystemPrompt, AnthropicThinkingConfig, AnthropicTool, AnthropicToolChoice,
     AnthropicToolChoiceType,
@@ -13,6 +14,7 @@ pub enum ProviderType {
     Cloudflare,
     Mistral,
     OpenAI,
+    Ollama,
 }
 
 impl ProviderType {
@@ -22,6 +24,7 @@ impl ProviderType {
             ProviderType::Cloudflare => "cloudflare",
             ProviderType::Mistral => "mistral",
             ProviderType::OpenAI => "openai",
+            ProviderType::Ollama => "ollama",
         }
     }
 
@@ -31,6 +34,7 @@ impl ProviderType {
             "cloudflare" => Some(ProviderType::Cloudflare),
             "mistral" => Some(ProviderType::Mistral),
             "openai" => Some(ProviderType::OpenAI),
+            "ollama" => Some(ProviderType::Ollama),
             _ => None,
         }
     }
@@ -326,7 +330,7 @@ pub struct Image {
     pub data: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
 #[ts(export, export_to = "../../shared/types/llm/core.ts")]
 pub struct ChatRequest {
     pub messages: Vec<ChatMessage>,
@@ -398,6 +402,23 @@ pub enum ContentItem {
     Text { text: String },
     #[serde(rename = "image")]
     Image { source: ImageSource },
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+    #[serde(rename = "tool_use")]
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    #[serde(rename = "tool_result")]
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -426,6 +447,11 @@ pub struct StreamResponse {
     pub usage: Option<TokenUsage>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub thinking: Option<String>,
+    /// Full assistant content, thinking signatures and tool calls included.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<Vec<ContentItem>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stop_reason: Option<StopReason>,
 }
 
 #[derive(Debug, Serialize, Deserialize, TS)]
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use ts_rs::TS;
+
+use crate::core::retry::{AppRetryPolicy, RetryPolicy};
+
+pub const NETWORK_STORE: &str = "network.json";
+
+pub const SETTINGS_KEY: &str = "settings";
+
+/// How the app reaches the providers, persisted in the network store.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(default)]
+pub struct NetworkSettings {
+    pub connect_timeout_secs: u64,
+    /// Longest silence allowed between two reads, so a stalled stream fails
+    /// instead of hanging. Long thinking pauses need some slack here.
+    pub read_timeout_secs: u64,
+    /// `http://`, `https://`, `socks5://` or `socks5h://` proxy used for
+    /// every request.
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_url: Option<String>,
+    /// PEM certificates trusted on top of the system roots, e.g. the CA of
+    /// a corporate TLS proxy.
+    pub root_certificates: Vec<String>,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+    /// Attempts per provider request, including the first one. 1 disables
+    /// retries.
+    pub max_attempts: u32,
+    pub retry_base_delay_ms: u64,
+    /// Longest wait between two attempts. A provider asking to wait longer
+    /// fails the request instead.
+    pub retry_max_delay_ms: u64,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            connect_timeout_secs: 10,
+            read_timeout_secs: 120,
+            proxy_url: None,
+            root_certificates: Vec::new(),
+            user_agent: None,
+            max_attempts: 4,
+            retry_base_delay_ms: 500,
+            retry_max_delay_ms: 30_000,
+        }
+    }
+}
+
+impl NetworkSettings {
+    pub fn retry_policy(&self) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts: self.max_attempts.max(1),
+            base_delay: Duration::from_millis(self.retry_base_delay_ms),
+            max_delay: Duration::from_millis(self.retry_max_delay_ms),
+        }
+    }
+
+    pub fn build_client(&self) -> Result<reqwest::Client, String> {
+        let user_agent = self
+            .user_agent
+            .clone()
+            .unwrap_or_else(|| format!("le-hey-eye/{}", env!("CARGO_PKG_VERSION")));
+
+        let mut builder = reqwest::Client::builder()
+            .connect_timeout(Duration::from_secs(self.connect_timeout_secs))
+            .read_timeout(Duration::from_secs(self.read_timeout_secs))
+            .user_agent(user_agent);
+
+        if let Some(proxy_url) = self
+            .proxy_url
+            .as_deref()
+            .filter(|url| !url.trim().is_empty())
+        {
+            let proxy = reqwest::Proxy::all(proxy_url.trim())
+                .map_err(|e| format!("Invalid proxy URL: {}", e))?;
+            builder = builder.proxy(proxy);
+        }
+
+        for pem in &self.root_certificates {
+            let certificates = reqwest::Certificate::from_pem_bundle(pem.as_bytes())
+                .map_err(|e| format!("Invalid root certificate: {}", e))?;
+            if certificates.is_empty() {
+                return Err("Invalid root certificate: no PEM certificate found".to_string());
+            }
+            for certificate in certificates {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        builder
+            .build()
+            .map_err(|e| format!("Failed to build HTTP client: {}", e))
+    }
+}
+
+/// The HTTP client shared by every provider call, rebuilt when the network
+/// settings change. Cloning a `reqwest::Client` only clones a handle, so
+/// connections are pooled across requests.
+pub struct HttpClient {
+    client: RwLock<reqwest::Client>,
+}
+
+impl HttpClient {
+    pub fn new(settings: &NetworkSettings) -> Result<Self, String> {
+        Ok(Self {
+            client: RwLock::new(settings.build_client()?),
+        })
+    }
+
+    pub fn get(&self) -> reqwest::Client {
+        self.client.read().unwrap().clone()
+    }
+}
+
+/// Returns the client managed by the app, built from the network settings.
+pub fn client<R: Runtime>(app: &AppHandle<R>) -> Result<reqwest::Client, String> {
+    app.try_state::<HttpClient>()
+        .map(|client| client.get())
+        .ok_or_else(|| "HTTP client is not initialized".to_string())
+}
+
+pub fn load_settings<R: Runtime>(app: &AppHandle<R>) -> Result<NetworkSettings, String> {
+    let store = app.store(NETWORK_STORE).map_err(|e| e.to_string())?;
+
+    store
+        .get(SETTINGS_KEY)
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Invalid network settings: {}", e))
+        })
+        .unwrap_or_else(|| Ok(NetworkSettings::default()))
+}
+
+/// Persists the settings once a client could be built from them, then
+/// swaps the managed client and retry policy.
+pub fn save_settings<R: Runtime>(
+    app: &AppHandle<R>,
+    settings: &NetworkSettings,
+) -> Result<(), String> {
+    let client = settings.build_client()?;
+
+    let store = app.store(NETWORK_STORE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())?;
+
+    match app.try_state::<HttpClient>() {
+        Some(http_client) => *http_client.client.write().unwrap() = client,
+        None => {
+            app.manage(HttpClient {
+                client: RwLock::new(client),
+            });
+        }
+    }
+    match app.try_state::<AppRetryPolicy>() {
+        Some(policy) => policy.set(settings.retry_policy()),
+        None => {
+            app.manage(AppRetryPolicy::new(settings.retry_policy()));
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_build_clients_and_reject_bad_values() {
+        assert!(NetworkSettings::default().build_client().is_ok());
+
+        let proxied = NetworkSettings {
+            proxy_url: Some("socks5h://127.0.0.1:1080".to_string()),
+            ..NetworkSettings::default()
+        };
+        assert!(proxied.build_client().is_ok());
+
+        let bad_certificate = NetworkSettings {
+            root_certificates: vec!["not a certificate".to_string()],
+            ..NetworkSettings::default()
+        };
+        assert!(bad_certificate.build_client().is_err());
+
+        let settings: NetworkSettings =
+            serde_json::from_value(serde_json::json!({ "connect_timeout_secs": 3 })).unwrap();
+        assert_eq!(settings.connect_timeout_secs, 3);
+        assert_eq!(settings.read_timeout_secs, 120);
+    }
+
+    #[test]
+    fn settings_configure_retries() {
+        assert_eq!(
+            NetworkSettings::default().retry_policy(),
+            RetryPolicy::default()
+        );
+
+        let settings: NetworkSettings = serde_json::from_value(serde_json::json!({
+            "max_attempts": 0,
+            "retry_max_delay_ms": 5000
+        }))
+        .unwrap();
+        let policy = settings.retry_policy();
+        assert_eq!(policy.max_attempts, 1);
+        assert_eq!(policy.base_delay, Duration::from_millis(500));
+        assert_eq!(policy.max_delay, Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,226 @@
+use rand::Rng;
+use reqwest::{RequestBuilder, Response};
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use std::time::Duration;
+use tauri::{AppHandle, Manager, Runtime};
+use ts_rs::TS;
+
+use crate::core::error::AppError;
+
+/// Statuses worth another try: rate limits, timeouts, overloaded or
+/// temporarily unavailable upstreams, and Anthropic's 529 "overloaded".
+const RETRYABLE_STATUSES: [u16; 7] = [408, 429, 500, 502, 503, 504, 529];
+
+/// How provider requests are retried, shared by all providers. The app
+/// manages one; tests build their own with millisecond delays.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first one. 1 disables retries.
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn is_retryable(&self, error: &AppError) -> bool {
+        match error {
+            AppError::Api {
+                status: Some(status),
+                ..
+            } => RETRYABLE_STATUSES.contains(status),
+            AppError::Network { .. } => true,
+            _ => false,
+        }
+    }
+
+    /// Delay before the attempt following `attempt` (1-based). The server's
+    /// `retry-after` wins when it sent one, otherwise the delay doubles with
+    /// each attempt, half of it randomized so clients do not retry in step.
+    /// `None` when the server asks to wait longer than `max_delay`: retrying
+    /// sooner would only be refused again.
+    pub fn delay(&self, attempt: u32, error: &AppError) -> Option<Duration> {
+        if let Some(seconds) = error.retry_after() {
+            return Some(Duration::from_secs(seconds)).filter(|delay| *delay <= self.max_delay);
+        }
+
+        let backoff = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = backoff / 2;
+
+        Some(half + rand::thread_rng().gen_range(Duration::ZERO..=half))
+    }
+}
+
+/// The policy managed by the app, replaced when the network settings are
+/// saved.
+#[derive(Debug, Default)]
+pub struct AppRetryPolicy {
+    policy: RwLock<RetryPolicy>,
+}
+
+impl AppRetryPolicy {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            policy: RwLock::new(policy),
+        }
+    }
+
+    pub fn get(&self) -> RetryPolicy {
+        self.policy.read().unwrap().clone()
+    }
+
+    pub fn set(&self, policy: RetryPolicy) {
+        *self.policy.write().unwrap() = policy;
+    }
+}
+
+/// Returns the policy managed by the app, or the default one.
+pub fn policy<R: Runtime>(app: &AppHandle<R>) -> RetryPolicy {
+    app.try_state::<AppRetryPolicy>()
+        .map(|policy| policy.get())
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+pub struct RetryAttempt {
+    /// The attempt that just failed, starting at 1.
+    pub attempt: u32,
+    pub max_attempts: u32,
+    pub delay_ms: u64,
+    pub error: AppError,
+}
+
+/// A policy plus the callback told about each retry, passed down to the
+/// functions sending provider requests.
+pub struct Retry<'a> {
+    policy: RetryPolicy,
+    on_retry: Box<dyn Fn(&RetryAttempt) + Send + Sync + 'a>,
+}
+
+impl<'a> Retry<'a> {
+    pub fn new(policy: RetryPolicy, on_retry: impl Fn(&RetryAttempt) + Send + Sync + 'a) -> Self {
+        Self {
+            policy,
+            on_retry: Box::new(on_retry),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn never() -> Self {
+        let policy = RetryPolicy {
+            max_attempts: 1,
+            ..RetryPolicy::default()
+        };
+
+        Self::new(policy, |_| {})
+    }
+
+    /// Sends the request built by `request` until the provider answers with
+    /// a success status. Only the request itself is retried: once this
+    /// returns, the caller owns the response and nothing was streamed yet.
+    pub async fn send<F>(&self, provider: &str, request: F) -> Result<Response, AppError>
+    where
+        F: Fn() -> RequestBuilder,
+    {
+        let mut attempt = 1;
+
+        loop {
+            let error = match request().send().await {
+                Ok(response) if response.status().is_success() => return Ok(response),
+                Ok(response) => {
+                    let status = response.status();
+                    let headers = response.headers().clone();
+                    let body = response
+                        .text()
+                        .await
+                        .map_err(|e| AppError::network(provider, e))?;
+                    AppError::from_response(provider, status, &headers, &body)
+                }
+                Err(e) => AppError::network(provider, e),
+            };
+
+            if attempt >= self.policy.max_attempts || !self.policy.is_retryable(&error) {
+                return Err(error);
+            }
+            let Some(delay) = self.policy.delay(attempt, &error) else {
+                return Err(error);
+            };
+
+            (self.on_retry)(&RetryAttempt {
+                attempt,
+                max_attempts: self.policy.max_attempts,
+                delay_ms: delay.as_millis() as u64,
+                error,
+            });
+
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(status: u16, retry_after: Option<u64>) -> AppError {
+        AppError::Api {
+            provider: "anthropic".to_string(),
+            status: Some(status),
+            error_type: None,
+            retry_after,
+            message: "Overloaded".to_string(),
+        }
+    }
+
+    #[test]
+    fn only_transient_errors_are_retried() {
+        let policy = RetryPolicy::default();
+
+        assert!(policy.is_retryable(&api_error(529, None)));
+        assert!(policy.is_retryable(&api_error(429, None)));
+        assert!(policy.is_retryable(&AppError::network("anthropic", "connection refused")));
+        assert!(!policy.is_retryable(&api_error(401, None)));
+        assert!(!policy.is_retryable(&AppError::from("No credentials")));
+    }
+
+    #[test]
+    fn delays_back_off_and_honor_retry_after() {
+        let policy = RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(300),
+        };
+
+        let first = policy.delay(1, &api_error(529, None)).unwrap();
+        assert!(first >= Duration::from_millis(50) && first <= Duration::from_millis(100));
+
+        let capped = policy.delay(4, &api_error(529, None)).unwrap();
+        assert!(capped >= Duration::from_millis(150) && capped <= Duration::from_millis(300));
+
+        let patient = RetryPolicy {
+            max_delay: Duration::from_secs(30),
+            ..policy.clone()
+        };
+        assert_eq!(
+            patient.delay(1, &api_error(429, Some(7))),
+            Some(Duration::from_secs(7))
+        );
+        // Waiting less than the server asked would only be refused again.
+        assert_eq!(patient.delay(1, &api_error(429, Some(120))), None);
+    }
+}
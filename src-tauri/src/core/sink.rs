@@ -0,0 +1,140 @@
+use std::io::Write;
+use std::sync::Mutex;
+use tauri::{Emitter, Runtime, Window};
+use tokio::sync::mpsc;
+
+use crate::core::stream::{StreamEvent, STREAM_EVENT};
+
+/// Where the events of a generation go. The app sends them to the window
+/// that started the generation; tests, the CLI and other headless callers
+/// use one of the sinks below instead, so providers never need a running
+/// Tauri app.
+pub trait EventSink: Send + Sync {
+    fn emit(&self, event: StreamEvent) -> Result<(), String>;
+}
+
+impl<R: Runtime> EventSink for Window<R> {
+    fn emit(&self, event: StreamEvent) -> Result<(), String> {
+        Emitter::emit(self, STREAM_EVENT, event).map_err(|e| e.to_string())
+    }
+}
+
+/// Forwards events to a channel, for callers consuming them as a stream.
+pub struct ChannelSink {
+    sender: mpsc::UnboundedSender<StreamEvent>,
+}
+
+impl ChannelSink {
+    pub fn new() -> (Self, mpsc::UnboundedReceiver<StreamEvent>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl EventSink for ChannelSink {
+    fn emit(&self, event: StreamEvent) -> Result<(), String> {
+        self.sender
+            .send(event)
+            .map_err(|_| "Stream event receiver was dropped".to_string())
+    }
+}
+
+/// Keeps every event, for tests asserting what a generation emitted.
+#[derive(Default)]
+pub struct CollectorSink {
+    events: Mutex<Vec<StreamEvent>>,
+}
+
+impl CollectorSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<StreamEvent> {
+        self.events.lock().unwrap().clone()
+    }
+}
+
+impl EventSink for CollectorSink {
+    fn emit(&self, event: StreamEvent) -> Result<(), String> {
+        self.events.lock().unwrap().push(event);
+        Ok(())
+    }
+}
+
+/// Writes the generation to the terminal: the answer on stdout as it
+/// streams and everything else on stderr, so the output can be piped. With
+/// `json_lines`, every event is printed on stdout as one JSON object.
+#[derive(Debug, Default)]
+pub struct StdoutSink {
+    json_lines: bool,
+}
+
+impl StdoutSink {
+    pub fn text() -> Self {
+        Self { json_lines: false }
+    }
+
+    pub fn json_lines() -> Self {
+        Self { json_lines: true }
+    }
+}
+
+impl EventSink for StdoutSink {
+    fn emit(&self, event: StreamEvent) -> Result<(), String> {
+        if self.json_lines {
+            let line = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+            println!("{}", line);
+            return Ok(());
+        }
+
+        match &event {
+            StreamEvent::TextDelta { text, .. } => {
+                let mut stdout = std::io::stdout().lock();
+                stdout
+                    .write_all(text.as_bytes())
+                    .and_then(|_| stdout.flush())
+                    .map_err(|e| e.to_string())?;
+            }
+            StreamEvent::ThinkingDelta { thinking, .. } => eprint!("{}", thinking),
+            StreamEvent::Retrying { retry, .. } => eprintln!(
+                "Retrying in {} ms ({}/{}): {}",
+                retry.delay_ms, retry.attempt, retry.max_attempts, retry.error
+            ),
+            StreamEvent::ToolCall { tool_call, .. } => {
+                eprintln!("[tool {}] {}", tool_call.name, tool_call.output)
+            }
+            StreamEvent::Stop { .. } => println!(),
+            StreamEvent::Error { error, .. } => eprintln!("Error: {}", error),
+            StreamEvent::Start { .. } | StreamEvent::Usage { .. } => {}
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(text: &str) -> StreamEvent {
+        StreamEvent::TextDelta {
+            request_id: "req-1".to_string(),
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn channel_sink_fails_once_the_receiver_is_gone() {
+        let (sink, mut receiver) = ChannelSink::new();
+
+        sink.emit(text("Hello")).unwrap();
+        assert!(matches!(
+            receiver.try_recv(),
+            Ok(StreamEvent::TextDelta { text, .. }) if text == "Hello"
+        ));
+
+        drop(receiver);
+        assert!(sink.emit(text("lost")).is_err());
+    }
+}
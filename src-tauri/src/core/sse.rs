@@ -0,0 +1,251 @@
+use futures_util::{future, stream, Stream, StreamExt};
+
+/// One event of a `text/event-stream` response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SseEvent {
+    /// The `event:` field, `message` when the server did not send one.
+    pub event: String,
+    /// The `data:` lines of the event, joined with `\n`.
+    pub data: String,
+    /// The last `id:` received, which carries over to the following events.
+    pub id: Option<String>,
+}
+
+/// Incremental parser following the event stream interpretation of the
+/// HTML spec. Lines are split on raw bytes and only decoded once complete,
+/// so a UTF-8 character split across two chunks is decoded correctly.
+#[derive(Debug, Default)]
+pub struct SseParser {
+    line: Vec<u8>,
+    /// The previous chunk ended on `\r`, a `\n` starting the next one
+    /// belongs to the same line ending.
+    skip_line_feed: bool,
+    started: bool,
+    event: String,
+    data: String,
+    has_data: bool,
+    last_event_id: Option<String>,
+}
+
+impl SseParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds the next chunk of the body, returning the events it completed.
+    pub fn feed(&mut self, chunk: &[u8]) -> Vec<SseEvent> {
+        let mut events = Vec::new();
+
+        for &byte in chunk {
+            if std::mem::take(&mut self.skip_line_feed) && byte == b'\n' {
+                continue;
+            }
+
+            match byte {
+                b'\r' | b'\n' => {
+                    self.skip_line_feed = byte == b'\r';
+                    let line = std::mem::take(&mut self.line);
+                    if let Some(event) = self.process_line(&line) {
+                        events.push(event);
+                    }
+                }
+                _ => self.line.push(byte),
+            }
+        }
+
+        events
+    }
+
+    fn process_line(&mut self, line: &[u8]) -> Option<SseEvent> {
+        let mut line = String::from_utf8_lossy(line);
+        if !std::mem::replace(&mut self.started, true) {
+            if let Some(stripped) = line.strip_prefix('\u{feff}') {
+                line = stripped.to_string().into();
+            }
+        }
+
+        if line.is_empty() {
+            return self.dispatch();
+        }
+
+        if line.starts_with(':') {
+            return None;
+        }
+
+        let (field, value) = match line.split_once(':') {
+            Some((field, value)) => (field, value.strip_prefix(' ').unwrap_or(value)),
+            None => (&*line, ""),
+        };
+
+        match field {
+            "event" => self.event = value.to_string(),
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "id" if !value.contains('\0') => {
+                self.last_event_id = Some(value.to_string()).filter(|id| !id.is_empty());
+            }
+            // `retry` only matters to clients that reconnect, and unknown
+            // fields are ignored.
+            _ => {}
+        }
+
+        None
+    }
+
+    fn dispatch(&mut self) -> Option<SseEvent> {
+        let event = std::mem::take(&mut self.event);
+        if !std::mem::take(&mut self.has_data) {
+            return None;
+        }
+
+        Some(SseEvent {
+            event: if event.is_empty() {
+                "message".to_string()
+            } else {
+                event
+            },
+            data: std::mem::take(&mut self.data),
+            id: self.last_event_id.clone(),
+        })
+    }
+}
+
+/// Turns a body stream, e.g. `reqwest::Response::bytes_stream()`, into the
+/// events it carries. An event left incomplete when the body ends is
+/// dropped, as the spec requires.
+pub fn sse_stream<S, B, E>(bytes: S) -> impl Stream<Item = Result<SseEvent, E>>
+where
+    S: Stream<Item = Result<B, E>>,
+    B: AsRef<[u8]>,
+{
+    bytes
+        .scan(SseParser::new(), |parser, chunk| {
+            future::ready(Some(chunk.map(|chunk| parser.feed(chunk.as_ref()))))
+        })
+        .flat_map(|events| {
+            stream::iter(match events {
+                Ok(events) => events.into_iter().map(Ok).collect(),
+                Err(e) => vec![Err(e)],
+            })
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+
+    fn parse(chunks: &[&[u8]]) -> Vec<SseEvent> {
+        let mut parser = SseParser::new();
+        chunks.iter().flat_map(|chunk| parser.feed(chunk)).collect()
+    }
+
+    fn event(event: &str, data: &str, id: Option<&str>) -> SseEvent {
+        SseEvent {
+            event: event.to_string(),
+            data: data.to_string(),
+            id: id.map(String::from),
+        }
+    }
+
+    const TRANSCRIPT: &str = concat!(
+        "\u{feff}: keep-alive comment\r\n",
+        "event: message_start\r\n",
+        "id: 1\r\n",
+        "data: {\"text\":\"héllo 👋\"}\r\n",
+        "\r\n",
+        "data:first\n",
+        "data:  second\n",
+        "\n",
+        "event: ping\r",
+        "\r",
+        "id\n",
+        "data\n",
+        "retry: 1000\n",
+        "\n",
+        "data: incomplete",
+    );
+
+    fn transcript_events() -> Vec<SseEvent> {
+        vec![
+            event("message_start", "{\"text\":\"héllo 👋\"}", Some("1")),
+            event("message", "first\n second", Some("1")),
+            event("message", "", None),
+        ]
+    }
+
+    #[test]
+    fn parses_fields_per_spec() {
+        assert_eq!(parse(&[TRANSCRIPT.as_bytes()]), transcript_events());
+    }
+
+    #[test]
+    fn keeps_utf8_split_across_chunks() {
+        let bytes = "data: 👋\n\n".as_bytes();
+        let (head, tail) = bytes.split_at(8);
+
+        assert_eq!(parse(&[head, tail]), vec![event("message", "👋", None)]);
+    }
+
+    #[tokio::test]
+    async fn streams_events_and_errors() {
+        let chunks: Vec<Result<&[u8], &str>> = vec![
+            Ok(&b"data: a\n"[..]),
+            Ok(&b"\ndata: b\n\n"[..]),
+            Err("connection reset"),
+        ];
+
+        let events = sse_stream(stream::iter(chunks)).collect::<Vec<_>>().await;
+
+        assert_eq!(
+            events,
+            vec![
+                Ok(event("message", "a", None)),
+                Ok(event("message", "b", None)),
+                Err("connection reset"),
+            ]
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn chunk_boundaries_do_not_change_events(
+            cuts in proptest::collection::vec(0..TRANSCRIPT.len(), 0..16)
+        ) {
+            let bytes = TRANSCRIPT.as_bytes();
+            let mut cuts = cuts;
+            cuts.sort_unstable();
+            cuts.dedup();
+
+            let mut chunks = Vec::new();
+            let mut start = 0;
+            for cut in cuts {
+                chunks.push(&bytes[start..cut]);
+                start = cut;
+            }
+            chunks.push(&bytes[start..]);
+
+            prop_assert_eq!(parse(&chunks), transcript_events());
+        }
+
+        #[test]
+        fn data_round_trips_through_any_chunking(
+            payloads in proptest::collection::vec("[^\r\n]{0,12}", 1..6),
+            chunk_size in 1usize..9,
+        ) {
+            let body: String = payloads
+                .iter()
+                .map(|payload| format!("data: {}\n\n", payload))
+                .collect();
+            let chunks: Vec<&[u8]> = body.as_bytes().chunks(chunk_size).collect();
+
+            let data: Vec<String> = parse(&chunks).into_iter().map(|event| event.data).collect();
+            prop_assert_eq!(data, payloads);
+        }
+    }
+}
@@ -0,0 +1,259 @@
+use futures_util::future::AbortHandle;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use ts_rs::TS;
+
+use crate::core::error::AppError;
+use crate::core::models::{StreamResponse, TokenUsage};
+use crate::core::retry::RetryAttempt;
+use crate::core::sink::EventSink;
+use crate::core::tools::ToolCallEvent;
+
+pub const STREAM_EVENT: &str = "stream-event";
+
+static REQUEST_COUNTER: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum StopReason {
+    EndTurn,
+    MaxTokens,
+    StopSequence,
+    ToolUse,
+    Cancelled,
+}
+
+impl StopReason {
+    /// Maps the finish reasons reported by the providers' APIs.
+    pub fn from_finish_reason(reason: &str) -> Self {
+        match reason {
+            "length" | "max_tokens" | "model_length" => StopReason::MaxTokens,
+            "stop_sequence" => StopReason::StopSequence,
+            "tool_calls" | "tool_use" => StopReason::ToolUse,
+            _ => StopReason::EndTurn,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum StreamEvent {
+    Start {
+        request_id: String,
+    },
+    /// The request failed before anything was streamed and is sent again.
+    Retrying {
+        request_id: String,
+        retry: RetryAttempt,
+    },
+    TextDelta {
+        request_id: String,
+        text: String,
+    },
+    ThinkingDelta {
+        request_id: String,
+        thinking: String,
+    },
+    ToolCall {
+        request_id: String,
+        tool_call: ToolCallEvent,
+    },
+    Usage {
+        request_id: String,
+        usage: TokenUsage,
+    },
+    Stop {
+        request_id: String,
+        reason: StopReason,
+    },
+    Error {
+        request_id: String,
+        error: AppError,
+    },
+}
+
+pub fn new_request_id() -> String {
+    format!(
+        "req-{}-{}",
+        chrono::Utc::now().timestamp_millis(),
+        REQUEST_COUNTER.fetch_add(1, Ordering::Relaxed)
+    )
+}
+
+#[derive(Debug, Default)]
+struct PartialResponse {
+    text: String,
+    thinking: Option<String>,
+    usage: Option<TokenUsage>,
+}
+
+/// Sends the `StreamEvent`s of one generation to its sink, all tagged with
+/// the same request id, and keeps what was streamed so far so a cancelled
+/// generation can still return its partial answer.
+#[derive(Clone)]
+pub struct StreamEmitter {
+    sink: Arc<dyn EventSink>,
+    request_id: String,
+    partial: Arc<Mutex<PartialResponse>>,
+}
+
+impl StreamEmitter {
+    pub fn new(sink: Arc<dyn EventSink>, request_id: String) -> Self {
+        Self {
+            sink,
+            request_id,
+            partial: Arc::new(Mutex::new(PartialResponse::default())),
+        }
+    }
+
+    pub fn request_id(&self) -> &str {
+        &self.request_id
+    }
+
+    pub fn partial_response(&self) -> StreamResponse {
+        let partial = self.partial.lock().unwrap();
+
+        StreamResponse {
+            response: partial.text.clone(),
+            usage: partial.usage.clone(),
+            thinking: partial.thinking.clone(),
+            content: None,
+            stop_reason: Some(StopReason::Cancelled),
+        }
+    }
+
+    fn emit(&self, event: StreamEvent) -> Result<(), String> {
+        self.sink.emit(event)
+    }
+
+    pub fn start(&self) -> Result<(), String> {
+        self.emit(StreamEvent::Start {
+            request_id: self.request_id.clone(),
+        })
+    }
+
+    pub fn retrying(&self, retry: &RetryAttempt) -> Result<(), String> {
+        self.emit(StreamEvent::Retrying {
+            request_id: self.request_id.clone(),
+            retry: retry.clone(),
+        })
+    }
+
+    pub fn text_delta(&self, text: &str) -> Result<(), String> {
+        self.partial.lock().unwrap().text.push_str(text);
+        self.emit(StreamEvent::TextDelta {
+            request_id: self.request_id.clone(),
+            text: text.to_string(),
+        })
+    }
+
+    pub fn thinking_delta(&self, thinking: &str) -> Result<(), String> {
+        self.partial
+            .lock()
+            .unwrap()
+            .thinking
+            .get_or_insert_with(String::new)
+            .push_str(thinking);
+        self.emit(StreamEvent::ThinkingDelta {
+            request_id: self.request_id.clone(),
+            thinking: thinking.to_string(),
+        })
+    }
+
+    pub fn tool_call(&self, tool_call: &ToolCallEvent) -> Result<(), String> {
+        self.emit(StreamEvent::ToolCall {
+            request_id: self.request_id.clone(),
+            tool_call: tool_call.clone(),
+        })
+    }
+
+    pub fn usage(&self, usage: &TokenUsage) -> Result<(), String> {
+        self.partial.lock().unwrap().usage = Some(usage.clone());
+        self.emit(StreamEvent::Usage {
+            request_id: self.request_id.clone(),
+            usage: usage.clone(),
+        })
+    }
+
+    pub fn stop(&self, reason: StopReason) -> Result<(), String> {
+        self.emit(StreamEvent::Stop {
+            request_id: self.request_id.clone(),
+            reason,
+        })
+    }
+
+    pub fn error(&self, error: &AppError) -> Result<(), String> {
+        self.emit(StreamEvent::Error {
+            request_id: self.request_id.clone(),
+            error: error.clone(),
+        })
+    }
+}
+
+/// Abort handles of the generations in flight, keyed by request id.
+#[derive(Default)]
+pub struct GenerationRegistry {
+    handles: Mutex<HashMap<String, AbortHandle>>,
+}
+
+impl GenerationRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&self, request_id: &str, handle: AbortHandle) {
+        self.handles
+            .lock()
+            .unwrap()
+            .insert(request_id.to_string(), handle);
+    }
+
+    pub fn remove(&self, request_id: &str) {
+        self.handles.lock().unwrap().remove(request_id);
+    }
+
+    /// Aborts the generation, returning false if it already finished.
+    pub fn cancel(&self, request_id: &str) -> bool {
+        match self.handles.lock().unwrap().remove(request_id) {
+            Some(handle) => {
+                handle.abort();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::future::{self, Aborted};
+
+    #[tokio::test]
+    async fn cancel_aborts_registered_generation() {
+        let generations = GenerationRegistry::new();
+        let (generation, handle) = future::abortable(future::pending::<()>());
+        generations.register("req-1", handle);
+
+        assert!(generations.cancel("req-1"));
+        assert_eq!(generation.await, Err(Aborted));
+        assert!(!generations.cancel("req-1"));
+    }
+
+    #[test]
+    fn finish_reasons_map_to_stop_reasons() {
+        assert_eq!(StopReason::from_finish_reason("stop"), StopReason::EndTurn);
+        assert_eq!(
+            StopReason::from_finish_reason("length"),
+            StopReason::MaxTokens
+        );
+        assert_eq!(
+            StopReason::from_finish_reason("tool_calls"),
+            StopReason::ToolUse
+        );
+    }
+}
@@ -0,0 +1,100 @@
+use crate::providers::anthropic::{AnthropicTool, PropertySchema};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Arc;
+use ts_rs::TS;
+
+pub const MAX_TOOL_ITERATIONS: u32 = 8;
+
+#[async_trait]
+pub trait ToolExecutor: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn definition(&self) -> AnthropicTool;
+
+    async fn execute(&self, input: Value) -> Result<String, String>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+pub struct ToolCallEvent {
+    pub iteration: u32,
+    pub tool_use_id: String,
+    pub name: String,
+    pub input: Value,
+    pub output: String,
+    pub is_error: bool,
+}
+
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    executors: HashMap<&'static str, Arc<dyn ToolExecutor>>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut registry = Self::new();
+        registry.register(CurrentDateTimeTool {});
+        registry
+    }
+
+    pub fn register<T: ToolExecutor + 'static>(&mut self, executor: T) {
+        self.executors.insert(executor.name(), Arc::new(executor));
+    }
+
+    pub fn get(&self, name: &str) -> Option<Arc<dyn ToolExecutor>> {
+        self.executors.get(name).cloned()
+    }
+
+    pub fn definitions(&self) -> Vec<AnthropicTool> {
+        self.executors
+            .values()
+            .map(|executor| executor.definition())
+            .collect()
+    }
+
+    pub async fn execute(&self, name: &str, input: Value) -> Result<String, String> {
+        match self.get(name) {
+            Some(executor) => executor.execute(input).await,
+            None => Err(format!("No executor registered for tool: {}", name)),
+        }
+    }
+}
+
+pub struct CurrentDateTimeTool {}
+
+#[async_trait]
+impl ToolExecutor for CurrentDateTimeTool {
+    fn name(&self) -> &'static str {
+        "current_datetime"
+    }
+
+    fn definition(&self) -> AnthropicTool {
+        let mut properties = HashMap::new();
+        properties.insert(
+            "timezone".to_string(),
+            PropertySchema::string_enum("Timezone of the returned date", vec!["utc", "local"]),
+        );
+
+        AnthropicTool::new(
+            self.name(),
+            "Returns the current date and time in RFC 3339 format",
+            properties,
+            None,
+        )
+    }
+
+    async fn execute(&self, input: Value) -> Result<String, String> {
+        match input.get("timezone").and_then(|tz| tz.as_str()) {
+            Some("local") => Ok(chrono::Local::now().to_rfc3339()),
+            Some("utc") | None => Ok(chrono::Utc::now().to_rfc3339()),
+            Some(other) => Err(format!("Unsupported timezone: {}", other)),
+        }
+    }
+}
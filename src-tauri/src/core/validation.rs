@@ -0,0 +1,126 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(rename_all = "snake_case")]
+pub enum CredentialStatus {
+    Valid,
+    MissingCredentials,
+    InvalidKey,
+    WrongAccountId,
+    RateLimited,
+    NetworkError,
+    Unexpected,
+}
+
+/// What the probe learned about the account behind the credentials.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+pub struct AccountMetadata {
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub organization_id: Option<String>,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account_name: Option<String>,
+    pub models: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+pub struct CredentialValidation {
+    pub status: CredentialStatus,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub account: Option<AccountMetadata>,
+}
+
+impl CredentialValidation {
+    pub fn valid(account: AccountMetadata) -> Self {
+        Self {
+            status: CredentialStatus::Valid,
+            message: None,
+            account: Some(account),
+        }
+    }
+
+    pub fn failed(status: CredentialStatus, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: Some(message.into()),
+            account: None,
+        }
+    }
+
+    pub fn missing(message: impl Into<String>) -> Self {
+        Self::failed(CredentialStatus::MissingCredentials, message)
+    }
+
+    pub fn from_request_error(error: &reqwest::Error) -> Self {
+        Self::failed(CredentialStatus::NetworkError, error.to_string())
+    }
+
+    /// Maps the status of a failed probe; providers with richer error
+    /// bodies refine this themselves.
+    pub fn from_http_status(status: StatusCode, body: &str) -> Self {
+        let kind = match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => CredentialStatus::InvalidKey,
+            StatusCode::TOO_MANY_REQUESTS => CredentialStatus::RateLimited,
+            _ => CredentialStatus::Unexpected,
+        };
+
+        Self::failed(kind, format!("{}: {}", status, body))
+    }
+}
+
+/// Sends a model listing request, the cheapest authenticated call most
+/// providers have, and reads the `{ "data": [{ "id": ... }] }` it returns.
+pub async fn probe_models(
+    request: reqwest::RequestBuilder,
+    organization_header: Option<&str>,
+) -> CredentialValidation {
+    let response = match request.send().await {
+        Ok(response) => response,
+        Err(e) => return CredentialValidation::from_request_error(&e),
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        return CredentialValidation::from_http_status(status, &body);
+    }
+
+    let organization_id = organization_header.and_then(|name| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    });
+
+    match response.json::<serde_json::Value>().await {
+        Ok(listing) => CredentialValidation::valid(AccountMetadata {
+            organization_id,
+            account_name: None,
+            models: model_ids(&listing),
+        }),
+        Err(e) => CredentialValidation::from_request_error(&e),
+    }
+}
+
+fn model_ids(listing: &serde_json::Value) -> Vec<String> {
+    listing
+        .get("data")
+        .and_then(|data| data.as_array())
+        .map(|data| {
+            data.iter()
+                .filter_map(|model| model.get("id").and_then(|id| id.as_str()).map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
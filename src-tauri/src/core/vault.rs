@@ -0,0 +1,304 @@
+use serde::{Deserialize, Serialize};
+use std::sync::RwLock;
+use tauri_plugin_store::StoreExt;
+
+use crate::core::credentials::{self, CREDENTIALS_STORE};
+use crate::utils::crypto::{self, CredentialKey, SALT_LEN, SECRET_LEN};
+
+pub const VAULT_STORE: &str = "vault.json";
+
+const VAULT_KEY: &str = "vault";
+const VERIFIER_CONTEXT: &str = "vault.verifier";
+const VERIFIER_PLAINTEXT: &str = "le-hey-eye";
+
+/// Key material persisted next to the credentials store. The install secret
+/// is generated once per install; the passphrase itself is never stored,
+/// only a verifier envelope sealed with the derived key.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultRecord {
+    version: u32,
+    install_secret: String,
+    salt: String,
+    passphrase_protected: bool,
+    verifier: String,
+}
+
+impl VaultRecord {
+    fn derive_key(&self, passphrase: Option<&str>) -> Result<CredentialKey, String> {
+        let install_secret = crypto::decode(&self.install_secret)?;
+        let salt = crypto::decode(&self.salt)?;
+
+        CredentialKey::derive(&install_secret, &salt, passphrase)
+    }
+
+    fn create(
+        install_secret: &[u8],
+        passphrase: Option<&str>,
+    ) -> Result<(Self, CredentialKey), String> {
+        let salt = crypto::random_bytes(SALT_LEN);
+        let key = CredentialKey::derive(install_secret, &salt, passphrase)?;
+        let record = Self {
+            version: 1,
+            install_secret: crypto::encode(install_secret),
+            salt: crypto::encode(&salt),
+            passphrase_protected: passphrase.is_some(),
+            verifier: key.encrypt_string(VERIFIER_CONTEXT, VERIFIER_PLAINTEXT)?,
+        };
+
+        Ok((record, key))
+    }
+}
+
+/// Holds the credentials key once it is available. Installs protected by a
+/// passphrase start locked until `unlock` is called.
+#[derive(Default)]
+pub struct CredentialVault {
+    key: RwLock<Option<CredentialKey>>,
+}
+
+impl CredentialVault {
+    pub fn load<R: tauri::Runtime>(app: &tauri::AppHandle<R>) -> Result<Self, String> {
+        let vault = Self::default();
+
+        let record = match read_record(app)? {
+            Some(record) => record,
+            None => {
+                let (record, key) = VaultRecord::create(&crypto::random_bytes(SECRET_LEN), None)?;
+                write_record(app, &record)?;
+                *vault.key.write().unwrap() = Some(key);
+                return Ok(vault);
+            }
+        };
+
+        if !record.passphrase_protected {
+            let key = record.derive_key(None)?;
+            key.decrypt_string(VERIFIER_CONTEXT, &record.verifier)?;
+            *vault.key.write().unwrap() = Some(key);
+        }
+
+        Ok(vault)
+    }
+
+    pub fn is_locked(&self) -> bool {
+        self.key.read().unwrap().is_none()
+    }
+
+    pub fn key(&self) -> Result<CredentialKey, String> {
+        self.key
+            .read()
+            .unwrap()
+            .clone()
+            .ok_or_else(|| "Credentials are locked, unlock them with your passphrase".to_string())
+    }
+
+    pub fn unlock<R: tauri::Runtime>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        passphrase: &str,
+    ) -> Result<(), String> {
+        let record = read_record(app)?.ok_or("Credential vault is not initialized")?;
+        let key = record.derive_key(Some(passphrase))?;
+        key.decrypt_string(VERIFIER_CONTEXT, &record.verifier)
+            .map_err(|_| "Wrong passphrase".to_string())?;
+
+        migrate_legacy_credentials(app, &key)?;
+        credentials::migrate_to_profiles(app, &key)?;
+        *self.key.write().unwrap() = Some(key);
+
+        Ok(())
+    }
+
+    /// Sets, changes or (with `None`) removes the passphrase, re-encrypting
+    /// every stored credential with the new key.
+    pub fn set_passphrase<R: tauri::Runtime>(
+        &self,
+        app: &tauri::AppHandle<R>,
+        passphrase: Option<&str>,
+    ) -> Result<(), String> {
+        let old_key = self.key()?;
+        let record = read_record(app)?.ok_or("Credential vault is not initialized")?;
+        let install_secret = crypto::decode(&record.install_secret)?;
+        let (record, new_key) = VaultRecord::create(&install_secret, passphrase)?;
+
+        let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+        let entries = store.entries();
+        let reencrypted = entries
+            .iter()
+            .map(|(name, value)| {
+                let value = crypto::decrypt_value(&old_key, name, value.clone())?;
+                Ok((name.clone(), crypto::encrypt_value(&new_key, name, value)?))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+
+        let save_credentials = |entries: &[(String, serde_json::Value)]| {
+            for (name, value) in entries {
+                store.set(name.clone(), value.clone());
+            }
+            store.save().map_err(|e| e.to_string())
+        };
+        rotate(&entries, &reencrypted, save_credentials, || {
+            write_record(app, &record)
+        })?;
+
+        *self.key.write().unwrap() = Some(new_key);
+
+        Ok(())
+    }
+}
+
+/// Saves the re-encrypted credentials, then the record of their new key. A
+/// record that cannot be saved puts the previous credentials back, so the
+/// stores never disagree on the key.
+fn rotate(
+    previous: &[(String, serde_json::Value)],
+    reencrypted: &[(String, serde_json::Value)],
+    mut save_credentials: impl FnMut(&[(String, serde_json::Value)]) -> Result<(), String>,
+    save_record: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    save_credentials(reencrypted)?;
+
+    if let Err(e) = save_record() {
+        save_credentials(previous).map_err(|restore_error| {
+            format!(
+                "{}, and the previous credentials could not be restored: {}",
+                e, restore_error
+            )
+        })?;
+        return Err(e);
+    }
+
+    Ok(())
+}
+
+/// Derives the credentials key from the entries of the vault store, for
+/// processes reading the stores without a Tauri app.
+pub fn key_from_store(
+    entries: &serde_json::Map<String, serde_json::Value>,
+    passphrase: Option<&str>,
+) -> Result<CredentialKey, String> {
+    let record: VaultRecord = entries
+        .get(VAULT_KEY)
+        .cloned()
+        .map(serde_json::from_value)
+        .ok_or("Credential vault is not initialized, start the app once first")?
+        .map_err(|e| format!("Corrupted credential vault: {}", e))?;
+
+    if record.passphrase_protected && passphrase.is_none() {
+        return Err("Credentials are locked, a passphrase is required".to_string());
+    }
+
+    let key = record.derive_key(passphrase.filter(|_| record.passphrase_protected))?;
+    key.decrypt_string(VERIFIER_CONTEXT, &record.verifier)
+        .map_err(|_| "Wrong passphrase".to_string())?;
+
+    Ok(key)
+}
+
+fn read_record<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+) -> Result<Option<VaultRecord>, String> {
+    let store = app.store(VAULT_STORE).map_err(|e| e.to_string())?;
+
+    store
+        .get(VAULT_KEY)
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Corrupted credential vault: {}", e))
+        })
+        .transpose()
+}
+
+fn write_record<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    record: &VaultRecord,
+) -> Result<(), String> {
+    let store = app.store(VAULT_STORE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(record).map_err(|e| e.to_string())?;
+
+    store.set(VAULT_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+/// Re-encrypts the entries still using the legacy fixed key. Any entry that
+/// cannot be decrypted aborts the migration before anything is written.
+pub fn migrate_legacy_credentials<R: tauri::Runtime>(
+    app: &tauri::AppHandle<R>,
+    key: &CredentialKey,
+) -> Result<usize, String> {
+    let store = app.store(CREDENTIALS_STORE).map_err(|e| e.to_string())?;
+
+    let migrated = store
+        .entries()
+        .into_iter()
+        .filter(|(_, value)| crypto::has_legacy_strings(value))
+        .map(|(name, value)| {
+            Ok((
+                name.clone(),
+                crypto::migrate_legacy_value(key, &name, value)?,
+            ))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    if migrated.is_empty() {
+        return Ok(0);
+    }
+
+    let count = migrated.len();
+    for (name, value) in migrated {
+        store.set(name, value);
+    }
+    store.save().map_err(|e| e.to_string())?;
+
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::cell::Cell;
+
+    #[test]
+    fn rotation_restores_credentials_when_the_record_fails() {
+        let previous = vec![("anthropic".to_string(), json!("old"))];
+        let reencrypted = vec![("anthropic".to_string(), json!("new"))];
+        let mut saved = Vec::new();
+
+        let result = rotate(
+            &previous,
+            &reencrypted,
+            |entries| {
+                saved.push(entries.to_vec());
+                Ok(())
+            },
+            || Err("disk full".to_string()),
+        );
+
+        assert_eq!(result, Err("disk full".to_string()));
+        assert_eq!(saved, vec![reencrypted.clone(), previous.clone()]);
+    }
+
+    #[test]
+    fn rotation_saves_the_record_last() {
+        let reencrypted = vec![("anthropic".to_string(), json!("new"))];
+        let saved = Cell::new(0);
+
+        let result = rotate(
+            &[],
+            &reencrypted,
+            |_| {
+                saved.set(saved.get() + 1);
+                Ok(())
+            },
+            || {
+                assert_eq!(
+                    saved.get(),
+                    1,
+                    "credentials must be saved before the record"
+                );
+                Ok(())
+            },
+        );
+
+        assert!(result.is_ok());
+    }
+}
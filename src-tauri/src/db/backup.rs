@@ -0,0 +1,385 @@
+use chrono::Utc;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use sea_orm::{ConnectionTrait, Database, DatabaseConnection, DbBackend, Statement};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tauri::{command, AppHandle, State};
+use ts_rs::TS;
+
+use crate::core::error::AppError;
+use crate::db::topics;
+use migration::{Migrator, MigratorTrait};
+
+const BACKUP_DIR: &str = "backups";
+const PRE_MIGRATION_LABEL: &str = "-pre-migration";
+const BEFORE_RESTORE_LABEL: &str = "-before-restore";
+/// Automatic backups kept before migrations, older ones are deleted.
+const KEPT_PRE_MIGRATION_BACKUPS: usize = 5;
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/db/backup.ts")]
+pub struct IntegrityReport {
+    pub ok: bool,
+    /// What `PRAGMA integrity_check` and `PRAGMA foreign_key_check` found.
+    pub problems: Vec<String>,
+}
+
+pub fn backup_dir(db_path: &Path) -> PathBuf {
+    db_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(BACKUP_DIR)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_os_string();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+/// A restore waiting for the next `connect` to swap it in.
+fn staged_path(db_path: &Path) -> PathBuf {
+    with_suffix(db_path, ".restore")
+}
+
+/// A name in `dir` that is not taken yet, compressed or not. `VACUUM INTO`
+/// refuses to overwrite a file.
+fn backup_path(dir: &Path, label: &str) -> PathBuf {
+    let stamp = Utc::now().format("%Y%m%d-%H%M%S");
+    let taken = |path: &Path| path.exists() || with_suffix(path, ".gz").exists();
+
+    let mut path = dir.join(format!("topics-{}{}.db", stamp, label));
+    let mut attempt = 1;
+    while taken(&path) {
+        path = dir.join(format!("topics-{}{}-{}.db", stamp, label, attempt));
+        attempt += 1;
+    }
+    path
+}
+
+fn gzip(from: &Path, to: &Path) -> Result<(), String> {
+    let mut input =
+        File::open(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    let output =
+        File::create(to).map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+    let mut encoder = GzEncoder::new(BufWriter::new(output), Compression::default());
+
+    io::copy(&mut input, &mut encoder)
+        .and_then(|_| encoder.finish())
+        .and_then(|mut output| output.flush())
+        .map_err(|e| format!("Failed to compress {}: {}", from.display(), e))
+}
+
+/// Copies a backup, compressed or not, to `to`.
+fn extract(from: &Path, to: &Path) -> Result<(), String> {
+    let file = File::open(from).map_err(|e| format!("Failed to read {}: {}", from.display(), e))?;
+    let mut input = BufReader::new(file);
+    let mut output =
+        File::create(to).map_err(|e| format!("Failed to write {}: {}", to.display(), e))?;
+
+    let gzipped = input
+        .fill_buf()
+        .map(|buffer| buffer.starts_with(&GZIP_MAGIC))
+        .unwrap_or(false);
+    let copied = if gzipped {
+        io::copy(&mut GzDecoder::new(input), &mut output)
+    } else {
+        io::copy(&mut input, &mut output)
+    };
+
+    copied
+        .and_then(|_| output.sync_all())
+        .map_err(|e| format!("Failed to read {}: {}", from.display(), e))
+}
+
+/// Writes a snapshot of the live database to `dir` with `VACUUM INTO`,
+/// which does not block the app meanwhile, gzipped if asked.
+pub async fn backup_to(
+    db: &DatabaseConnection,
+    dir: &Path,
+    label: &str,
+    compress: bool,
+) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    let path = backup_path(dir, label);
+
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "VACUUM INTO ?",
+        [path.to_string_lossy().to_string().into()],
+    ))
+    .await
+    .map_err(|e| format!("Backup failed: {}", e))?;
+
+    if !compress {
+        return Ok(path);
+    }
+
+    let compressed = with_suffix(&path, ".gz");
+    let result = gzip(&path, &compressed);
+    fs::remove_file(&path).map_err(|e| e.to_string())?;
+    result.map(|_| compressed)
+}
+
+pub async fn integrity_report<C: ConnectionTrait>(db: &C) -> Result<IntegrityReport, String> {
+    let mut problems = Vec::new();
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "PRAGMA integrity_check",
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let message: String = row.try_get_by_index(0).map_err(|e| e.to_string())?;
+        if message != "ok" {
+            problems.push(message);
+        }
+    }
+
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "PRAGMA foreign_key_check",
+        ))
+        .await
+        .map_err(|e| e.to_string())?;
+    for row in rows {
+        let table: String = row.try_get_by_index(0).map_err(|e| e.to_string())?;
+        let row_id: Option<i64> = row.try_get_by_index(1).map_err(|e| e.to_string())?;
+        let parent: String = row.try_get_by_index(2).map_err(|e| e.to_string())?;
+        problems.push(match row_id {
+            Some(row_id) => format!(
+                "{} row {} references a missing {} row",
+                table, row_id, parent
+            ),
+            None => format!("{} references a missing {} row", table, parent),
+        });
+    }
+
+    Ok(IntegrityReport {
+        ok: problems.is_empty(),
+        problems,
+    })
+}
+
+/// Older schemas are fine, the migrations bring them up to date. Newer ones
+/// come from a later version of the app and cannot be read.
+async fn check_restorable<C: ConnectionTrait>(db: &C) -> Result<(), String> {
+    let rows = db
+        .query_all(Statement::from_string(
+            DbBackend::Sqlite,
+            "SELECT version FROM seaql_migrations",
+        ))
+        .await
+        .map_err(|_| "The file is not a le-hey-eye database".to_string())?;
+
+    let known: HashSet<String> = Migrator::migrations()
+        .iter()
+        .map(|migration| migration.name().to_string())
+        .collect();
+    for row in rows {
+        let version: String = row.try_get_by_index(0).map_err(|e| e.to_string())?;
+        if !known.contains(&version) {
+            return Err(format!(
+                "The backup comes from a newer version of the app (migration {})",
+                version
+            ));
+        }
+    }
+
+    let report = integrity_report(db).await?;
+    if !report.ok {
+        return Err(format!(
+            "The backup is damaged: {}",
+            report.problems.join("; ")
+        ));
+    }
+
+    Ok(())
+}
+
+/// Checks a backup and stages it to replace the database at `db_path` the
+/// next time it is opened.
+pub async fn stage_restore(backup: &Path, db_path: &Path) -> Result<(), String> {
+    let candidate = with_suffix(db_path, ".restore-tmp");
+
+    let result = async {
+        extract(backup, &candidate)?;
+
+        let url = format!("sqlite:{}?mode=ro", candidate.to_string_lossy());
+        let db = Database::connect(&url)
+            .await
+            .map_err(|e| format!("Failed to open the backup: {}", e))?;
+        let checked = check_restorable(&db).await;
+        db.close().await.map_err(|e| e.to_string())?;
+        checked?;
+
+        fs::rename(&candidate, staged_path(db_path))
+            .map_err(|e| format!("Failed to stage the backup: {}", e))
+    }
+    .await;
+
+    if result.is_err() {
+        let _ = fs::remove_file(&candidate);
+    }
+    result
+}
+
+/// Swaps a staged restore in before the database is opened. The journals
+/// belong to the replaced database and go with it.
+pub fn apply_staged_restore(db_path: &Path) -> Result<bool, String> {
+    let staged = staged_path(db_path);
+    if !staged.exists() {
+        return Ok(false);
+    }
+
+    for suffix in ["-journal", "-wal", "-shm"] {
+        let journal = with_suffix(db_path, suffix);
+        if journal.exists() {
+            fs::remove_file(&journal)
+                .map_err(|e| format!("Failed to remove {}: {}", journal.display(), e))?;
+        }
+    }
+    fs::rename(&staged, db_path).map_err(|e| format!("Failed to restore the backup: {}", e))?;
+
+    Ok(true)
+}
+
+fn prune_pre_migration_backups(dir: &Path) -> Result<(), String> {
+    let mut backups: Vec<PathBuf> = fs::read_dir(dir)
+        .map_err(|e| e.to_string())?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().contains(PRE_MIGRATION_LABEL))
+        })
+        .collect();
+    // Timestamps sort by name.
+    backups.sort();
+
+    let excess = backups.len().saturating_sub(KEPT_PRE_MIGRATION_BACKUPS);
+    for path in &backups[..excess] {
+        fs::remove_file(path).map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+    }
+    Ok(())
+}
+
+/// Backs the database up when migrations are about to change it. A new
+/// database has nothing to lose and is skipped.
+pub async fn backup_before_migrations(
+    db: &DatabaseConnection,
+    db_path: &Path,
+) -> Result<(), String> {
+    let pending = Migrator::get_pending_migrations(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    if pending.is_empty() {
+        return Ok(());
+    }
+    let applied = Migrator::get_applied_migrations(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    if applied.is_empty() {
+        return Ok(());
+    }
+
+    let dir = backup_dir(db_path);
+    let path = backup_to(db, &dir, PRE_MIGRATION_LABEL, true).await?;
+    eprintln!(
+        "Backed up the database to {} before migrating",
+        path.display()
+    );
+
+    prune_pre_migration_backups(&dir)
+}
+
+/// Returns the path of the backup.
+#[command]
+pub async fn backup_database(
+    app: AppHandle,
+    db: State<'_, DatabaseConnection>,
+    compress: Option<bool>,
+) -> Result<String, AppError> {
+    let dir = backup_dir(&topics::get_db_path(&app));
+    let path = backup_to(&db, &dir, "", compress.unwrap_or(false)).await?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// Replaces the database with a backup, after saving the current one.
+/// The app restarts so everything reconnects to the restored file.
+#[command]
+pub async fn restore_database(
+    app: AppHandle,
+    db: State<'_, DatabaseConnection>,
+    path: String,
+) -> Result<(), AppError> {
+    let db_path = topics::get_db_path(&app);
+    backup_to(&db, &backup_dir(&db_path), BEFORE_RESTORE_LABEL, true).await?;
+    stage_restore(Path::new(&path), &db_path).await?;
+
+    app.restart()
+}
+
+#[command]
+pub async fn check_database(
+    db: State<'_, DatabaseConnection>,
+) -> Result<IntegrityReport, AppError> {
+    Ok(integrity_report(&*db).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use entity::topics as topic;
+    use sea_orm::{ActiveModelTrait, EntityTrait, NotSet, Set};
+
+    #[tokio::test]
+    async fn restores_a_compressed_backup() {
+        let dir = std::env::temp_dir().join(format!("le-hey-eye-backup-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("topics.db");
+
+        let db = topics::connect(&db_path).await.unwrap();
+        let now = Utc::now().fixed_offset();
+        topic::ActiveModel {
+            id: NotSet,
+            name: Set("Kept".to_string()),
+            created_at: Set(now),
+            last_accessed_at: Set(now),
+            active_leaf_id: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        assert!(integrity_report(&db).await.unwrap().ok);
+
+        let backup = backup_to(&db, &backup_dir(&db_path), "", true)
+            .await
+            .unwrap();
+        topic::Entity::delete_many().exec(&db).await.unwrap();
+        db.close().await.unwrap();
+
+        let not_a_database = dir.join("notes.txt");
+        fs::write(&not_a_database, "hello").unwrap();
+        assert!(stage_restore(&not_a_database, &db_path).await.is_err());
+
+        stage_restore(&backup, &db_path).await.unwrap();
+        let db = topics::connect(&db_path).await.unwrap();
+        let restored = topic::Entity::find().all(&db).await.unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].name, "Kept");
+        assert!(!staged_path(&db_path).exists());
+
+        db.close().await.unwrap();
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
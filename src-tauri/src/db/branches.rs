@@ -0,0 +1,471 @@
+use chrono::Utc;
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, NotSet,
+    QueryFilter, QueryOrder, Set, TransactionTrait,
+};
+use std::collections::{HashMap, HashSet};
+use tauri::{command, State};
+
+use crate::core::error::AppError;
+use crate::core::models::ContentType;
+use crate::db::message_blocks::{self, StoredMessage};
+use entity::message_blocks::Entity as MessageBlocks;
+use entity::messages::{
+    ActiveModel as MessagesActiveModel, Column as MessagesColumn, Entity as Messages,
+    Model as MessagesModel, RoleType,
+};
+use entity::topics::{Column as TopicsColumn, Entity as Topics};
+
+// Messages form a tree per topic through `parent_id`. The topic keeps the
+// leaf of the active branch; walking up from it gives what is shown and sent
+// to the model. New messages are appended under that leaf.
+
+async fn topic_messages<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+) -> Result<Vec<MessagesModel>, String> {
+    Messages::find()
+        .filter(MessagesColumn::TopicId.eq(topic_id))
+        .order_by_asc(MessagesColumn::Id)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+async fn find_message<C: ConnectionTrait>(
+    db: &C,
+    message_id: i32,
+) -> Result<MessagesModel, String> {
+    Messages::find_by_id(message_id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Message {} not found", message_id))
+}
+
+/// The last message of the active branch. Falls back to the latest message
+/// when the topic has no valid leaf, e.g. after it was deleted.
+async fn active_leaf<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+    messages: &[MessagesModel],
+) -> Result<Option<i32>, String> {
+    let topic = Topics::find_by_id(topic_id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Topic {} not found", topic_id))?;
+
+    Ok(topic
+        .active_leaf_id
+        .filter(|leaf| messages.iter().any(|message| message.id == *leaf))
+        .or_else(|| messages.iter().map(|message| message.id).max()))
+}
+
+pub async fn set_active_leaf<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+    leaf: Option<i32>,
+) -> Result<(), String> {
+    Topics::update_many()
+        .col_expr(TopicsColumn::ActiveLeafId, leaf.into())
+        .filter(TopicsColumn::Id.eq(topic_id))
+        .exec(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    Ok(())
+}
+
+/// Parent of the next message appended to the topic.
+pub async fn append_parent<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+) -> Result<Option<i32>, String> {
+    let messages = topic_messages(db, topic_id).await?;
+    active_leaf(db, topic_id, &messages).await
+}
+
+/// Ids from the root to `leaf`.
+fn path_to(messages: &[MessagesModel], leaf: i32) -> Vec<i32> {
+    let parents: HashMap<i32, Option<i32>> = messages
+        .iter()
+        .map(|message| (message.id, message.parent_id))
+        .collect();
+
+    let mut path = Vec::new();
+    let mut current = Some(leaf);
+    while let Some(id) = current {
+        // A cycle would only come from a corrupted database, stop there.
+        if path.contains(&id) {
+            break;
+        }
+        path.push(id);
+        current = parents.get(&id).copied().flatten();
+    }
+    path.reverse();
+    path
+}
+
+/// Follows the most recent child down from `message_id`.
+fn latest_leaf_under(messages: &[MessagesModel], message_id: i32) -> i32 {
+    let mut leaf = message_id;
+    while let Some(child) = messages
+        .iter()
+        .filter(|message| message.parent_id == Some(leaf))
+        .map(|message| message.id)
+        .max()
+    {
+        leaf = child;
+    }
+    leaf
+}
+
+async fn with_blocks<C: ConnectionTrait>(
+    db: &C,
+    ids: &[i32],
+) -> Result<Vec<StoredMessage>, String> {
+    let mut messages: HashMap<i32, StoredMessage> = Messages::find()
+        .filter(MessagesColumn::Id.is_in(ids.to_vec()))
+        .find_with_related(MessageBlocks)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|(message, blocks)| (message.id, StoredMessage::from_model(message, blocks)))
+        .collect();
+
+    Ok(ids.iter().filter_map(|id| messages.remove(id)).collect())
+}
+
+/// Messages of the active branch, oldest first.
+pub async fn active_path<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+) -> Result<Vec<StoredMessage>, String> {
+    let messages = topic_messages(db, topic_id).await?;
+    let Some(leaf) = active_leaf(db, topic_id, &messages).await? else {
+        return Ok(Vec::new());
+    };
+
+    with_blocks(db, &path_to(&messages, leaf)).await
+}
+
+/// Before `message_ids` are deleted, moves their children under the closest
+/// surviving ancestor and the active leaf up to it, so the branches below
+/// stay reachable.
+pub async fn detach<C: ConnectionTrait>(db: &C, message_ids: &[i32]) -> Result<(), String> {
+    let deleted: HashMap<i32, MessagesModel> = Messages::find()
+        .filter(MessagesColumn::Id.is_in(message_ids.to_vec()))
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|message| (message.id, message))
+        .collect();
+
+    let surviving_ancestor = |mut id: Option<i32>| {
+        let mut seen = HashSet::new();
+        while let Some(message) = id.and_then(|id| deleted.get(&id)) {
+            if !seen.insert(message.id) {
+                return None;
+            }
+            id = message.parent_id;
+        }
+        id
+    };
+
+    for message in deleted.values() {
+        let parent = surviving_ancestor(message.parent_id);
+
+        Messages::update_many()
+            .col_expr(MessagesColumn::ParentId, parent.into())
+            .filter(MessagesColumn::ParentId.eq(message.id))
+            .filter(MessagesColumn::Id.is_not_in(message_ids.to_vec()))
+            .exec(db)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        let topic = Topics::find_by_id(message.topic_id)
+            .one(db)
+            .await
+            .map_err(|e| e.to_string())?;
+        if topic.and_then(|topic| topic.active_leaf_id) == Some(message.id) {
+            set_active_leaf(db, message.topic_id, parent).await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Adds an edited copy of a message next to it and makes it the active
+/// branch. The original and everything after it stay as a sibling branch.
+pub async fn fork(
+    db: &DatabaseConnection,
+    message_id: i32,
+    content: &ContentType,
+) -> Result<StoredMessage, String> {
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+    let original = find_message(&transaction, message_id).await?;
+    let timestamp = Utc::now().fixed_offset();
+
+    let forked = MessagesActiveModel {
+        id: NotSet,
+        topic_id: Set(original.topic_id),
+        role: Set(original.role),
+        content: Set(message_blocks::flatten_text(content)),
+        created_at: Set(timestamp),
+        updated_at: Set(Some(timestamp)),
+        tokens_used: Set(0),
+        parent_id: Set(original.parent_id),
+    }
+    .insert(&transaction)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let mut blocks = Vec::new();
+    for block in message_blocks::blocks_from_content(forked.id, content) {
+        blocks.push(
+            block
+                .insert(&transaction)
+                .await
+                .map_err(|e| e.to_string())?,
+        );
+    }
+
+    set_active_leaf(&transaction, forked.topic_id, Some(forked.id)).await?;
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(StoredMessage::from_model(forked, blocks))
+}
+
+/// Makes the branch going through `message_id` the active one, down to its
+/// most recent leaf, and returns it.
+pub async fn switch<C: ConnectionTrait>(
+    db: &C,
+    message_id: i32,
+) -> Result<Vec<StoredMessage>, String> {
+    let message = find_message(db, message_id).await?;
+    let messages = topic_messages(db, message.topic_id).await?;
+
+    let leaf = latest_leaf_under(&messages, message.id);
+    set_active_leaf(db, message.topic_id, Some(leaf)).await?;
+
+    with_blocks(db, &path_to(&messages, leaf)).await
+}
+
+/// The messages to send again to regenerate an assistant reply, from the
+/// root to its prompt. Nothing changes until the new reply is saved next
+/// to the old one.
+pub async fn regeneration_path<C: ConnectionTrait>(
+    db: &C,
+    message_id: i32,
+) -> Result<Vec<StoredMessage>, String> {
+    let message = find_message(db, message_id).await?;
+    if message.role != RoleType::Assistant {
+        return Err("Only assistant replies can be regenerated".to_string());
+    }
+    let prompt = message
+        .parent_id
+        .ok_or("This reply does not answer any message")?;
+
+    let messages = topic_messages(db, message.topic_id).await?;
+    with_blocks(db, &path_to(&messages, prompt)).await
+}
+
+#[command]
+pub async fn fork_message(
+    db: State<'_, DatabaseConnection>,
+    message_id: i32,
+    content: ContentType,
+) -> Result<StoredMessage, AppError> {
+    Ok(fork(&db, message_id, &content).await?)
+}
+
+/// The branches at a message: itself and the other children of its parent,
+/// oldest first.
+#[command]
+pub async fn list_sibling_branches(
+    db: State<'_, DatabaseConnection>,
+    message_id: i32,
+) -> Result<Vec<StoredMessage>, AppError> {
+    let message = find_message(&*db, message_id).await?;
+
+    let parent_filter = match message.parent_id {
+        Some(parent_id) => MessagesColumn::ParentId.eq(parent_id),
+        None => MessagesColumn::ParentId.is_null(),
+    };
+    let ids: Vec<i32> = Messages::find()
+        .filter(MessagesColumn::TopicId.eq(message.topic_id))
+        .filter(parent_filter)
+        .order_by_asc(MessagesColumn::Id)
+        .all(&*db)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|message| message.id)
+        .collect();
+
+    Ok(with_blocks(&*db, &ids).await?)
+}
+
+#[command]
+pub async fn switch_branch(
+    db: State<'_, DatabaseConnection>,
+    message_id: i32,
+) -> Result<Vec<StoredMessage>, AppError> {
+    Ok(switch(&*db, message_id).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::topics::insert_message;
+    use entity::topics as topic;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+
+    fn message(id: i32, parent_id: Option<i32>) -> MessagesModel {
+        let now = Utc::now().fixed_offset();
+        MessagesModel {
+            id,
+            topic_id: 1,
+            role: RoleType::User,
+            content: String::new(),
+            created_at: now,
+            tokens_used: 0,
+            updated_at: None,
+            parent_id,
+        }
+    }
+
+    #[test]
+    fn walks_branches() {
+        // 1 ─ 2 ─ 3
+        //   └ 4 ─ 5
+        //       └ 6
+        let messages = vec![
+            message(1, None),
+            message(2, Some(1)),
+            message(3, Some(2)),
+            message(4, Some(1)),
+            message(5, Some(4)),
+            message(6, Some(4)),
+        ];
+
+        assert_eq!(path_to(&messages, 3), vec![1, 2, 3]);
+        assert_eq!(path_to(&messages, 5), vec![1, 4, 5]);
+        assert_eq!(latest_leaf_under(&messages, 2), 3);
+        assert_eq!(latest_leaf_under(&messages, 4), 6);
+        assert_eq!(latest_leaf_under(&messages, 1), 6);
+    }
+
+    async fn setup() -> (DatabaseConnection, i32) {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let now = Utc::now().fixed_offset();
+        let topic = topic::ActiveModel {
+            id: NotSet,
+            name: Set("Branches".to_string()),
+            created_at: Set(now),
+            last_accessed_at: Set(now),
+            active_leaf_id: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        (db, topic.id)
+    }
+
+    async fn append(db: &DatabaseConnection, topic_id: i32, role: RoleType, text: &str) -> i32 {
+        let parent_id = append_parent(db, topic_id).await.unwrap();
+        let message = insert_message(
+            db,
+            topic_id,
+            role,
+            &ContentType::PlainText(text.to_string()),
+            0,
+            parent_id,
+        )
+        .await
+        .unwrap();
+        set_active_leaf(db, topic_id, Some(message.id))
+            .await
+            .unwrap();
+        message.id
+    }
+
+    async fn active_ids(db: &DatabaseConnection, topic_id: i32) -> Vec<i32> {
+        active_path(db, topic_id)
+            .await
+            .unwrap()
+            .iter()
+            .map(|message| message.id)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn forks_and_switches_branches() {
+        let (db, topic_id) = setup().await;
+        let question = append(&db, topic_id, RoleType::User, "Tea?").await;
+        let answer = append(&db, topic_id, RoleType::Assistant, "Green.").await;
+        assert_eq!(active_ids(&db, topic_id).await, vec![question, answer]);
+
+        let edited = fork(
+            &db,
+            question,
+            &ContentType::PlainText("Coffee?".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(edited.parent_id, None);
+        assert_eq!(active_ids(&db, topic_id).await, vec![edited.id]);
+
+        let reply = append(&db, topic_id, RoleType::Assistant, "Black.").await;
+        assert_eq!(active_ids(&db, topic_id).await, vec![edited.id, reply]);
+
+        let original = switch(&db, question).await.unwrap();
+        assert_eq!(
+            original
+                .iter()
+                .map(|message| message.id)
+                .collect::<Vec<_>>(),
+            vec![question, answer]
+        );
+        assert_eq!(active_ids(&db, topic_id).await, vec![question, answer]);
+    }
+
+    #[tokio::test]
+    async fn regeneration_keeps_the_active_branch() {
+        let (db, topic_id) = setup().await;
+        let question = append(&db, topic_id, RoleType::User, "Tea?").await;
+        let answer = append(&db, topic_id, RoleType::Assistant, "Green.").await;
+
+        let path = regeneration_path(&db, answer).await.unwrap();
+        assert_eq!(
+            path.iter().map(|message| message.id).collect::<Vec<_>>(),
+            vec![question]
+        );
+        assert_eq!(active_ids(&db, topic_id).await, vec![question, answer]);
+
+        assert!(regeneration_path(&db, question).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn detached_messages_keep_their_children_reachable() {
+        let (db, topic_id) = setup().await;
+        let question = append(&db, topic_id, RoleType::User, "Tea?").await;
+        let answer = append(&db, topic_id, RoleType::Assistant, "Green.").await;
+        let follow_up = append(&db, topic_id, RoleType::User, "Why?").await;
+
+        detach(&db, &[answer]).await.unwrap();
+        Messages::delete_by_id(answer).exec(&db).await.unwrap();
+        assert_eq!(active_ids(&db, topic_id).await, vec![question, follow_up]);
+
+        detach(&db, &[follow_up]).await.unwrap();
+        Messages::delete_by_id(follow_up).exec(&db).await.unwrap();
+        assert_eq!(active_ids(&db, topic_id).await, vec![question]);
+    }
+}
@@ -0,0 +1,377 @@
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DatabaseConnection, EntityTrait, QueryFilter, QueryOrder,
+    TransactionTrait,
+};
+use serde::Serialize;
+use ts_rs::TS;
+
+use crate::core::models::{
+    ChatMessage, ChatRequest, ChatRole, ContentType, ProviderType, StreamResponse,
+};
+use crate::db::message_blocks::{self, StoredMessage};
+use crate::db::{branches, topics};
+use crate::providers::anthropic::{
+    AnthropicSystemPrompt, AnthropicThinkingConfig, AnthropicThinkingType,
+};
+use entity::anthropic_models_settings::{
+    Column as AnthropicSettingsColumn, Entity as AnthropicSettings,
+};
+use entity::messages::RoleType;
+use entity::models_settings::Model as SettingsModel;
+use entity::thinking::{Column as ThinkingColumn, Entity as Thinking};
+
+/// A request built from a topic's settings and active branch.
+pub struct TopicRequest {
+    pub provider: ProviderType,
+    pub model: String,
+    pub profile: Option<String>,
+    pub request: ChatRequest,
+    /// Leaf of the branch the request was built from, the new messages go
+    /// under it even if another branch is activated meanwhile.
+    pub parent_id: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, TS)]
+#[ts(export, export_to = "../../shared/types/db/messages.ts")]
+pub struct TopicExchange {
+    pub user: StoredMessage,
+    pub assistant: StoredMessage,
+    pub response: StreamResponse,
+}
+
+/// Extended thinking as configured for an Anthropic topic. The budget of the
+/// `thinking` row wins over the one of `anthropic_models_settings`.
+async fn thinking_config<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+) -> Result<Option<AnthropicThinkingConfig>, String> {
+    let thinking = Thinking::find()
+        .filter(ThinkingColumn::TopicId.eq(topic_id))
+        .order_by_desc(ThinkingColumn::Id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?;
+    let Some(thinking) = thinking.filter(|thinking| thinking.enabled) else {
+        return Ok(None);
+    };
+
+    let anthropic = AnthropicSettings::find()
+        .filter(AnthropicSettingsColumn::TopicId.eq(topic_id))
+        .order_by_desc(AnthropicSettingsColumn::Id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let budget_tokens = Some(thinking.budget_tokens)
+        .filter(|budget| *budget > 0)
+        .or(anthropic.map(|settings| settings.budget_tokens))
+        .and_then(|budget| u32::try_from(budget).ok())
+        .filter(|budget| *budget > 0)
+        .ok_or("Thinking is enabled without a token budget")?;
+
+    Ok(Some(AnthropicThinkingConfig {
+        thinking_type: AnthropicThinkingType::Enabled,
+        budget_tokens,
+    }))
+}
+
+fn chat_request(
+    settings: &SettingsModel,
+    history: Vec<StoredMessage>,
+    user_content: ContentType,
+    thinking: Option<AnthropicThinkingConfig>,
+) -> ChatRequest {
+    let mut system: Vec<String> = Some(settings.system.clone())
+        .filter(|system| !system.trim().is_empty())
+        .into_iter()
+        .collect();
+    let mut messages = Vec::with_capacity(history.len() + 1);
+
+    for message in history {
+        let role = match message.role {
+            // Providers take instructions apart from the conversation.
+            RoleType::System => {
+                system.push(message_blocks::flatten_text(&message.content));
+                continue;
+            }
+            RoleType::User => ChatRole::User,
+            RoleType::Assistant => ChatRole::Assistant,
+        };
+        messages.push(ChatMessage {
+            role,
+            content: message.content,
+        });
+    }
+    messages.push(ChatMessage {
+        role: ChatRole::User,
+        content: user_content,
+    });
+
+    ChatRequest {
+        messages,
+        system: AnthropicSystemPrompt::Simple(system.join("\n\n")),
+        max_tokens: u32::try_from(settings.max_tokens)
+            .ok()
+            .filter(|tokens| *tokens > 0),
+        model: Some(settings.model_name.clone()),
+        stream: Some(settings.stream),
+        temperature: settings.temperature,
+        thinking,
+        // Older saves stored 0 for unset values, which no provider accepts.
+        top_p: settings.top_p.filter(|top_p| *top_p > 0.0),
+        top_k: settings
+            .top_k
+            .filter(|top_k| *top_k > 0.0)
+            .map(|top_k| top_k.round() as i32),
+        ..Default::default()
+    }
+}
+
+/// The request sending `user_content` after `history`, with the settings
+/// the topic was last saved with.
+async fn topic_request(
+    db: &DatabaseConnection,
+    topic_id: i32,
+    history: Vec<StoredMessage>,
+    user_content: ContentType,
+    parent_id: Option<i32>,
+) -> Result<TopicRequest, String> {
+    let settings = topics::latest_settings(db, topic_id)
+        .await?
+        .ok_or_else(|| format!("Topic {} has no model settings", topic_id))?;
+    let provider = ProviderType::from_str(&settings.provider)
+        .ok_or_else(|| format!("Unknown provider: {}", settings.provider))?;
+
+    let thinking = match provider {
+        ProviderType::Anthropic => thinking_config(db, topic_id).await?,
+        _ => None,
+    };
+
+    Ok(TopicRequest {
+        request: chat_request(&settings, history, user_content, thinking),
+        model: settings.model_name,
+        profile: settings.credential_profile,
+        provider,
+        parent_id,
+    })
+}
+
+/// The request for `user_content` following the active branch of a topic.
+pub async fn build_request(
+    db: &DatabaseConnection,
+    topic_id: i32,
+    user_content: ContentType,
+) -> Result<TopicRequest, String> {
+    let history = branches::active_path(db, topic_id).await?;
+    let parent_id = history.last().map(|message| message.id);
+
+    topic_request(db, topic_id, history, user_content, parent_id).await
+}
+
+/// The request sending the prompt of an assistant reply again, with the
+/// prompt itself. `parent_id` is the prompt, for `save_reply`.
+pub async fn build_regeneration(
+    db: &DatabaseConnection,
+    message_id: i32,
+) -> Result<(TopicRequest, StoredMessage), String> {
+    let mut history = branches::regeneration_path(db, message_id).await?;
+    let prompt = history
+        .pop()
+        .ok_or_else(|| format!("Message {} has no prompt", message_id))?;
+
+    let request = topic_request(
+        db,
+        prompt.topic_id,
+        history,
+        prompt.content.clone(),
+        Some(prompt.id),
+    )
+    .await?;
+
+    Ok((request, prompt))
+}
+
+fn token_count(count: Option<u32>) -> i32 {
+    count.map_or(0, |count| i32::try_from(count).unwrap_or(i32::MAX))
+}
+
+fn reply_content(response: &StreamResponse) -> ContentType {
+    match &response.content {
+        Some(items) if !items.is_empty() => ContentType::StructuredContent(items.clone()),
+        _ => ContentType::PlainText(response.response.clone()),
+    }
+}
+
+/// Stores the prompt under `parent_id` and the reply under the prompt, and
+/// makes the reply the active leaf, in one transaction. The prompt is
+/// charged the prompt tokens, the reply the completion tokens.
+pub async fn save_exchange(
+    db: &DatabaseConnection,
+    topic_id: i32,
+    parent_id: Option<i32>,
+    user_content: &ContentType,
+    response: &StreamResponse,
+) -> Result<(StoredMessage, StoredMessage), String> {
+    let usage = response.usage.as_ref();
+
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+
+    let user = topics::insert_message(
+        &transaction,
+        topic_id,
+        RoleType::User,
+        user_content,
+        token_count(usage.map(|usage| usage.prompt_tokens)),
+        parent_id,
+    )
+    .await?;
+    let assistant = topics::insert_message(
+        &transaction,
+        topic_id,
+        RoleType::Assistant,
+        &reply_content(response),
+        token_count(usage.map(|usage| usage.completion_tokens)),
+        Some(user.id),
+    )
+    .await?;
+    branches::set_active_leaf(&transaction, topic_id, Some(assistant.id)).await?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok((user, assistant))
+}
+
+/// Stores a regenerated reply under its existing prompt, next to the
+/// previous replies, and makes it the active leaf.
+pub async fn save_reply(
+    db: &DatabaseConnection,
+    topic_id: i32,
+    prompt_id: i32,
+    response: &StreamResponse,
+) -> Result<StoredMessage, String> {
+    let usage = response.usage.as_ref();
+
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+
+    let assistant = topics::insert_message(
+        &transaction,
+        topic_id,
+        RoleType::Assistant,
+        &reply_content(response),
+        token_count(usage.map(|usage| usage.completion_tokens)),
+        Some(prompt_id),
+    )
+    .await?;
+    branches::set_active_leaf(&transaction, topic_id, Some(assistant.id)).await?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(assistant)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use entity::{models_settings, thinking, topics as topic};
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, Database, NotSet, Set};
+
+    #[tokio::test]
+    async fn builds_requests_from_topic_settings_and_saves_replies() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let now = Utc::now().fixed_offset();
+        let topic = topic::ActiveModel {
+            id: NotSet,
+            name: Set("Stars".to_string()),
+            created_at: Set(now),
+            last_accessed_at: Set(now),
+            active_leaf_id: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        models_settings::ActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            provider: Set("anthropic".to_string()),
+            system: Set("Be brief.".to_string()),
+            model_name: Set("claude-3-7-sonnet-20250219".to_string()),
+            stream: Set(true),
+            max_tokens: Set(2048),
+            temperature: Set(Some(1.0)),
+            top_k: Set(Some(0.0)),
+            top_p: Set(Some(0.0)),
+            credential_profile: Set(Some("work".to_string())),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        thinking::ActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            budget_tokens: Set(1024),
+            enabled: Set(true),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let question = ContentType::PlainText("How far is Vega?".to_string());
+        let first = build_request(&db, topic.id, question.clone())
+            .await
+            .unwrap();
+        assert_eq!(first.model, "claude-3-7-sonnet-20250219");
+        assert_eq!(first.profile.as_deref(), Some("work"));
+        assert_eq!(first.parent_id, None);
+
+        let request = serde_json::to_value(&first.request).unwrap();
+        assert_eq!(request["system"], "Be brief.");
+        assert_eq!(request["max_tokens"], 2048);
+        assert_eq!(request["thinking"]["budget_tokens"], 1024);
+        assert!(request.get("top_k").is_none());
+        assert!(request.get("top_p").is_none());
+
+        let response = StreamResponse {
+            response: "25 light-years.".to_string(),
+            usage: None,
+            thinking: None,
+            content: None,
+            stop_reason: None,
+        };
+        let (user, assistant) = save_exchange(&db, topic.id, first.parent_id, &question, &response)
+            .await
+            .unwrap();
+        assert_eq!(assistant.parent_id, Some(user.id));
+
+        let next = build_request(
+            &db,
+            topic.id,
+            ContentType::PlainText("And Sirius?".to_string()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(next.parent_id, Some(assistant.id));
+        assert_eq!(next.request.messages.len(), 3);
+
+        // Building the regeneration leaves the active branch alone.
+        let (regeneration, prompt) = build_regeneration(&db, assistant.id).await.unwrap();
+        assert_eq!(prompt.id, user.id);
+        assert_eq!(regeneration.parent_id, Some(user.id));
+        assert_eq!(regeneration.request.messages.len(), 1);
+        let unchanged = build_request(&db, topic.id, question.clone())
+            .await
+            .unwrap();
+        assert_eq!(unchanged.parent_id, Some(assistant.id));
+
+        let regenerated = save_reply(&db, topic.id, prompt.id, &response)
+            .await
+            .unwrap();
+        assert_eq!(regenerated.parent_id, Some(user.id));
+        let after = build_request(&db, topic.id, question).await.unwrap();
+        assert_eq!(after.parent_id, Some(regenerated.id));
+        assert_eq!(after.request.messages.len(), 3);
+    }
+}
@@ -0,0 +1,243 @@
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::core::models::ContentType;
+use entity::messages::RoleType;
+
+use super::{ExportedMessage, ExportedSettings, TopicExport, EXPORT_VERSION};
+
+// ChatGPT exports conversations as trees of nodes in `mapping`, edits and
+// regenerations being siblings, like our branches. Only the text of user and
+// assistant messages is kept: system prompts, tool calls and attachments
+// have no equivalent here.
+
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    title: Option<String>,
+    create_time: Option<f64>,
+    update_time: Option<f64>,
+    mapping: HashMap<String, Node>,
+    current_node: Option<String>,
+    default_model_slug: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Node {
+    message: Option<Message>,
+    parent: Option<String>,
+    #[serde(default)]
+    children: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    author: Author,
+    content: Content,
+    create_time: Option<f64>,
+    update_time: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Author {
+    role: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Content {
+    content_type: String,
+    #[serde(default)]
+    parts: Vec<Value>,
+}
+
+fn date(timestamp: Option<f64>) -> Option<String> {
+    let timestamp = timestamp?;
+    DateTime::<Utc>::from_timestamp(timestamp.trunc() as i64, (timestamp.fract() * 1e9) as u32)
+        .map(|date| date.to_rfc3339())
+}
+
+/// The message to keep for a node, if any.
+fn convert(message: &Message, fallback_date: &str) -> Option<ExportedMessage> {
+    let role = match message.author.role.as_str() {
+        "user" => RoleType::User,
+        "assistant" => RoleType::Assistant,
+        _ => return None,
+    };
+    if !matches!(
+        message.content.content_type.as_str(),
+        "text" | "multimodal_text"
+    ) {
+        return None;
+    }
+
+    // Images are objects among the parts, only the strings are text.
+    let text = message
+        .content
+        .parts
+        .iter()
+        .filter_map(Value::as_str)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if text.trim().is_empty() {
+        return None;
+    }
+
+    Some(ExportedMessage {
+        role,
+        content: ContentType::PlainText(text),
+        created_at: date(message.create_time).unwrap_or_else(|| fallback_date.to_string()),
+        updated_at: date(message.update_time),
+        tokens_used: 0,
+        parent: None,
+    })
+}
+
+fn convert_conversation(conversation: Conversation) -> TopicExport {
+    let created_at = date(conversation.create_time).unwrap_or_else(|| Utc::now().to_rfc3339());
+    let mapping = &conversation.mapping;
+
+    let mut roots: Vec<&String> = mapping
+        .iter()
+        .filter(|(_, node)| {
+            node.parent
+                .as_ref()
+                .is_none_or(|parent| !mapping.contains_key(parent))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    roots.sort();
+
+    // Depth first, so parents are listed before their children. Skipped
+    // nodes pass their closest kept ancestor down.
+    let mut messages = Vec::new();
+    let mut indexes: HashMap<&str, usize> = HashMap::new();
+    let mut stack: Vec<(&String, Option<usize>)> =
+        roots.into_iter().rev().map(|id| (id, None)).collect();
+
+    while let Some((id, parent)) = stack.pop() {
+        let Some(node) = mapping.get(id) else {
+            continue;
+        };
+        if indexes.contains_key(id.as_str()) {
+            continue;
+        }
+
+        let kept = node
+            .message
+            .as_ref()
+            .and_then(|message| convert(message, &created_at))
+            .map(|message| {
+                messages.push(ExportedMessage { parent, ..message });
+                messages.len() - 1
+            });
+        if let Some(index) = kept {
+            indexes.insert(id, index);
+        }
+
+        let parent = kept.or(parent);
+        stack.extend(node.children.iter().rev().map(|child| (child, parent)));
+    }
+
+    // The current node may be a skipped one, e.g. a tool call.
+    let mut active_leaf = None;
+    let mut current = conversation.current_node.as_deref();
+    while let Some(id) = current {
+        if let Some(index) = indexes.get(id) {
+            active_leaf = Some(*index);
+            break;
+        }
+        current = mapping.get(id).and_then(|node| node.parent.as_deref());
+    }
+
+    let settings = conversation
+        .default_model_slug
+        .map(|model_name| ExportedSettings {
+            provider: "openai".to_string(),
+            system: String::new(),
+            model_name,
+            stream: true,
+            max_tokens: 4096,
+            temperature: None,
+            top_k: None,
+            top_p: None,
+            credential_profile: None,
+        });
+
+    TopicExport {
+        version: EXPORT_VERSION,
+        name: conversation
+            .title
+            .filter(|title| !title.trim().is_empty())
+            .unwrap_or_else(|| "Imported conversation".to_string()),
+        last_accessed_at: date(conversation.update_time).unwrap_or_else(|| created_at.clone()),
+        created_at,
+        settings: settings.into_iter().collect(),
+        anthropic_settings: Vec::new(),
+        thinking: Vec::new(),
+        messages,
+        active_leaf,
+    }
+}
+
+pub fn parse(conversations: Vec<Value>) -> Result<Vec<TopicExport>, String> {
+    conversations
+        .into_iter()
+        .map(|conversation| {
+            serde_json::from_value(conversation)
+                .map(convert_conversation)
+                .map_err(|e| format!("Invalid ChatGPT conversation: {}", e))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn node(role: &str, text: &str, parent: Option<&str>, children: &[&str]) -> Value {
+        json!({
+            "message": {
+                "author": { "role": role },
+                "content": { "content_type": "text", "parts": [text] },
+                "create_time": 1714000000.5,
+            },
+            "parent": parent,
+            "children": children,
+        })
+    }
+
+    #[test]
+    fn keeps_the_tree_and_the_current_branch() {
+        let conversation = json!({
+            "title": "Tomatoes",
+            "create_time": 1714000000.0,
+            "update_time": 1714000100.0,
+            "current_node": "answer-2",
+            "default_model_slug": "gpt-4o",
+            "mapping": {
+                "root": { "message": null, "parent": null, "children": ["system"] },
+                "system": node("system", "", Some("root"), &["question"]),
+                "question": node("user", "When to plant?", Some("system"), &["answer-1", "answer-2"]),
+                "answer-1": node("assistant", "In May.", Some("question"), &[]),
+                "answer-2": node("assistant", "After the frost.", Some("question"), &[]),
+            },
+        });
+
+        let exports = parse(vec![conversation]).unwrap();
+        let export = &exports[0];
+
+        assert_eq!(export.name, "Tomatoes");
+        assert_eq!(export.settings[0].model_name, "gpt-4o");
+        assert_eq!(export.messages.len(), 3);
+        assert_eq!(export.messages[0].parent, None);
+        assert_eq!(export.messages[1].parent, Some(0));
+        assert_eq!(export.messages[2].parent, Some(0));
+        assert_eq!(export.active_leaf, Some(2));
+        assert_eq!(
+            export.messages[0].created_at,
+            "2024-04-24T23:06:40.500+00:00"
+        );
+    }
+}
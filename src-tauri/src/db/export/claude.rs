@@ -0,0 +1,127 @@
+use chrono::Utc;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::HashMap;
+
+use crate::core::models::ContentType;
+use entity::messages::RoleType;
+
+use super::{ExportedMessage, TopicExport, EXPORT_VERSION};
+
+// Claude.ai exports list the messages of each conversation in order. Newer
+// exports link them with `parent_message_uuid`, older ones are a single
+// branch. Only text is kept: thinking comes without the signature the API
+// needs to accept it back, and attachments are not part of the export.
+
+#[derive(Debug, Deserialize)]
+struct Conversation {
+    name: Option<String>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    #[serde(default)]
+    chat_messages: Vec<Message>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Message {
+    uuid: Option<String>,
+    sender: String,
+    #[serde(default)]
+    text: String,
+    #[serde(default)]
+    content: Vec<ContentItem>,
+    created_at: Option<String>,
+    updated_at: Option<String>,
+    parent_message_uuid: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ContentItem {
+    #[serde(rename = "type")]
+    item_type: String,
+    text: Option<String>,
+}
+
+impl Message {
+    fn text(&self) -> String {
+        if self.content.is_empty() {
+            return self.text.clone();
+        }
+
+        self.content
+            .iter()
+            .filter(|item| item.item_type == "text")
+            .filter_map(|item| item.text.as_deref())
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+}
+
+fn convert_conversation(conversation: Conversation) -> TopicExport {
+    let created_at = conversation
+        .created_at
+        .unwrap_or_else(|| Utc::now().to_rfc3339());
+
+    let mut messages: Vec<ExportedMessage> = Vec::new();
+    let mut indexes: HashMap<String, usize> = HashMap::new();
+
+    for message in conversation.chat_messages {
+        let role = match message.sender.as_str() {
+            "human" => RoleType::User,
+            "assistant" => RoleType::Assistant,
+            _ => continue,
+        };
+        let text = message.text();
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        // A parent that was skipped or comes later falls back to the
+        // previous message.
+        let parent = match message.parent_message_uuid.as_deref() {
+            Some(parent) => indexes.get(parent).copied(),
+            None => None,
+        }
+        .or_else(|| messages.len().checked_sub(1));
+
+        if let Some(uuid) = message.uuid {
+            indexes.insert(uuid, messages.len());
+        }
+        messages.push(ExportedMessage {
+            role,
+            content: ContentType::PlainText(text),
+            created_at: message.created_at.unwrap_or_else(|| created_at.clone()),
+            updated_at: message.updated_at,
+            tokens_used: 0,
+            parent,
+        });
+    }
+
+    TopicExport {
+        version: EXPORT_VERSION,
+        name: conversation
+            .name
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| "Imported conversation".to_string()),
+        last_accessed_at: conversation
+            .updated_at
+            .unwrap_or_else(|| created_at.clone()),
+        created_at,
+        settings: Vec::new(),
+        anthropic_settings: Vec::new(),
+        thinking: Vec::new(),
+        active_leaf: messages.len().checked_sub(1),
+        messages,
+    }
+}
+
+pub fn parse(conversations: Vec<Value>) -> Result<Vec<TopicExport>, String> {
+    conversations
+        .into_iter()
+        .map(|conversation| {
+            serde_json::from_value(conversation)
+                .map(convert_conversation)
+                .map_err(|e| format!("Invalid Claude.ai conversation: {}", e))
+        })
+        .collect()
+}
@@ -0,0 +1,128 @@
+use crate::core::models::{ContentItem, ContentType};
+use entity::messages::RoleType;
+
+use super::{role_label, TopicExport};
+
+const STYLE: &str = "\
+body { font-family: system-ui, sans-serif; max-width: 48rem; margin: 2rem auto; padding: 0 1rem; color: #1f2328; }
+header p { color: #59636e; }
+section { border-radius: 0.5rem; padding: 0.75rem 1rem; margin: 1rem 0; }
+section.user { background: #eef4ff; }
+section.assistant { background: #f6f8fa; }
+section.system { background: #fff8e6; }
+h2 { font-size: 0.85rem; text-transform: uppercase; color: #59636e; margin: 0 0 0.5rem; }
+.text { white-space: pre-wrap; }
+pre { white-space: pre-wrap; background: #ffffff; padding: 0.5rem; border-radius: 0.25rem; overflow-x: auto; }
+details { margin: 0.5rem 0; color: #59636e; }
+img { max-width: 100%; }
+";
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn role_class(role: &RoleType) -> &'static str {
+    match role {
+        RoleType::System => "system",
+        RoleType::User => "user",
+        RoleType::Assistant => "assistant",
+    }
+}
+
+fn push_item(out: &mut String, item: &ContentItem) {
+    match item {
+        ContentItem::Text { text } => {
+            out.push_str(&format!("<div class=\"text\">{}</div>\n", escape(text)))
+        }
+        ContentItem::Thinking { thinking, .. } => out.push_str(&format!(
+            "<details><summary>Thinking</summary><pre>{}</pre></details>\n",
+            escape(thinking)
+        )),
+        ContentItem::RedactedThinking { .. } => {
+            out.push_str("<details><summary>Redacted thinking</summary></details>\n")
+        }
+        ContentItem::Image { source } => out.push_str(&format!(
+            "<img src=\"data:{};base64,{}\" alt=\"\">\n",
+            escape(&source.media_type),
+            escape(&source.data)
+        )),
+        ContentItem::ToolUse { name, input, .. } => out.push_str(&format!(
+            "<details><summary>Tool call: {}</summary><pre>{}</pre></details>\n",
+            escape(name),
+            escape(&serde_json::to_string_pretty(input).unwrap_or_default())
+        )),
+        ContentItem::ToolResult {
+            content, is_error, ..
+        } => {
+            let label = if *is_error == Some(true) {
+                "Tool error"
+            } else {
+                "Tool result"
+            };
+            out.push_str(&format!(
+                "<details><summary>{}</summary><pre>{}</pre></details>\n",
+                label,
+                escape(content)
+            ))
+        }
+    }
+}
+
+/// The active branch as a page with no external resources.
+pub fn render(export: &TopicExport) -> String {
+    let title = escape(&export.name);
+    let mut out = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n\
+         <meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">\n\
+         <title>{}</title>\n<style>\n{}</style>\n</head>\n<body>\n<header>\n<h1>{}</h1>\n",
+        title, STYLE, title
+    );
+
+    if let Some(settings) = export.latest_settings() {
+        out.push_str(&format!(
+            "<p>{} · {}</p>\n",
+            escape(&settings.provider),
+            escape(&settings.model_name)
+        ));
+        if !settings.system.trim().is_empty() {
+            out.push_str(&format!(
+                "<section class=\"system\">\n<h2>System</h2>\n<div class=\"text\">{}</div>\n</section>\n",
+                escape(&settings.system)
+            ));
+        }
+    }
+    out.push_str("</header>\n<main>\n");
+
+    for message in export.active_branch() {
+        out.push_str(&format!(
+            "<section class=\"{}\">\n<h2>{}</h2>\n",
+            role_class(&message.role),
+            role_label(&message.role)
+        ));
+        match &message.content {
+            ContentType::PlainText(text) => {
+                out.push_str(&format!("<div class=\"text\">{}</div>\n", escape(text)))
+            }
+            ContentType::StructuredContent(items) => {
+                for item in items {
+                    push_item(&mut out, item);
+                }
+            }
+        }
+        out.push_str("</section>\n");
+    }
+
+    out.push_str("</main>\n</body>\n</html>\n");
+    out
+}
@@ -0,0 +1,88 @@
+use crate::core::models::{ContentItem, ContentType};
+
+use super::{role_label, TopicExport};
+
+/// A fence longer than any backtick run of `text`, so it cannot be closed
+/// early by the content.
+fn fence(text: &str) -> String {
+    let longest = text.split(|c| c != '`').map(str::len).max().unwrap_or(0);
+    "`".repeat(longest.max(2) + 1)
+}
+
+fn push_fenced(out: &mut String, info: &str, text: &str) {
+    let fence = fence(text);
+    out.push_str(&format!(
+        "{}{}\n{}\n{}\n\n",
+        fence,
+        info,
+        text.trim_end(),
+        fence
+    ));
+}
+
+fn push_item(out: &mut String, item: &ContentItem) {
+    match item {
+        ContentItem::Text { text } => {
+            out.push_str(text.trim_end());
+            out.push_str("\n\n");
+        }
+        ContentItem::Thinking { thinking, .. } => push_fenced(out, "thinking", thinking),
+        ContentItem::RedactedThinking { .. } => out.push_str("> _Redacted thinking_\n\n"),
+        ContentItem::Image { source } => out.push_str(&format!(
+            "![image](data:{};base64,{})\n\n",
+            source.media_type, source.data
+        )),
+        ContentItem::ToolUse { name, input, .. } => {
+            out.push_str(&format!("**Tool call:** `{}`\n\n", name));
+            let input = serde_json::to_string_pretty(input).unwrap_or_default();
+            push_fenced(out, "json", &input);
+        }
+        ContentItem::ToolResult {
+            content, is_error, ..
+        } => {
+            let label = if *is_error == Some(true) {
+                "Tool error"
+            } else {
+                "Tool result"
+            };
+            out.push_str(&format!("**{}:**\n\n", label));
+            push_fenced(out, "", content);
+        }
+    }
+}
+
+/// The active branch, one section per message.
+pub fn render(export: &TopicExport) -> String {
+    let mut out = format!("# {}\n\n", export.name);
+
+    if let Some(settings) = export.latest_settings() {
+        out.push_str(&format!(
+            "_{} · {}_\n\n",
+            settings.provider, settings.model_name
+        ));
+        if !settings.system.trim().is_empty() {
+            out.push_str("## System\n\n");
+            out.push_str(settings.system.trim_end());
+            out.push_str("\n\n");
+        }
+    }
+
+    for message in export.active_branch() {
+        out.push_str(&format!("## {}\n\n", role_label(&message.role)));
+        match &message.content {
+            ContentType::PlainText(text) => {
+                out.push_str(text.trim_end());
+                out.push_str("\n\n");
+            }
+            ContentType::StructuredContent(items) => {
+                for item in items {
+                    push_item(&mut out, item);
+                }
+            }
+        }
+    }
+
+    out.truncate(out.trim_end().len());
+    out.push('\n');
+    out
+}
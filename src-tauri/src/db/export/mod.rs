@@ -0,0 +1,738 @@
+mod chatgpt;
+mod claude;
+mod html;
+mod markdown;
+
+use chrono::{DateTime, FixedOffset, Utc};
+use sea_orm::{
+    ActiveModelTrait, ColumnTrait, DatabaseConnection, EntityTrait, NotSet, QueryFilter,
+    QueryOrder, Set, TransactionTrait,
+};
+use serde::{Deserialize, Serialize};
+use std::io::{Cursor, Read};
+use tauri::{command, State};
+use ts_rs::TS;
+
+use crate::core::error::AppError;
+use crate::core::models::ContentType;
+use crate::db::branches;
+use crate::db::message_blocks::{self, StoredMessage};
+use entity::anthropic_models_settings::{
+    ActiveModel as AnthropicSettingsActiveModel, Column as AnthropicSettingsColumn,
+    Entity as AnthropicSettings, Model as AnthropicSettingsModel,
+};
+use entity::message_blocks::Entity as MessageBlocks;
+use entity::messages::{
+    ActiveModel as MessagesActiveModel, Column as MessagesColumn, Entity as Messages, RoleType,
+};
+use entity::models_settings::{
+    ActiveModel as SettingsActiveModel, Column as SettingsColumn, Entity as Settings,
+    Model as SettingsModel,
+};
+use entity::thinking::{
+    ActiveModel as ThinkingActiveModel, Column as ThinkingColumn, Entity as Thinking,
+    Model as ThinkingModel,
+};
+use entity::topics::{ActiveModel as TopicActiveModel, Entity as Topics, Model as TopicModel};
+
+/// Bumped whenever a change of `TopicExport` breaks reading older files, or
+/// would make older builds drop part of newer ones. Version 1 had no
+/// branches, its messages import as a single one. Version 2 had no Anthropic
+/// settings.
+pub const EXPORT_VERSION: u32 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/db/export.ts")]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    /// The active branch, for reading or pasting elsewhere.
+    Markdown,
+    /// Every branch, settings and content block. The only format imported
+    /// back.
+    Json,
+    /// The active branch as a standalone page.
+    Html,
+}
+
+impl ExportFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ExportFormat::Markdown => "md",
+            ExportFormat::Json => "json",
+            ExportFormat::Html => "html",
+        }
+    }
+}
+
+/// A topic with its settings and messages, as written by `collect_topic`.
+/// Ids are left out: importing always creates a new topic.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TopicExport {
+    pub version: u32,
+    pub name: String,
+    pub created_at: String,
+    pub last_accessed_at: String,
+    pub settings: Vec<ExportedSettings>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub anthropic_settings: Vec<ExportedAnthropicSettings>,
+    /// Extended thinking of Anthropic topics, the last entry applies.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub thinking: Vec<ExportedThinking>,
+    /// Parents always come before their children.
+    pub messages: Vec<ExportedMessage>,
+    /// Index in `messages` of the last message of the active branch.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub active_leaf: Option<usize>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedSettings {
+    pub provider: String,
+    pub system: String,
+    pub model_name: String,
+    pub stream: bool,
+    pub max_tokens: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub credential_profile: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedAnthropicSettings {
+    pub budget_tokens: i32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedThinking {
+    pub budget_tokens: i32,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportedMessage {
+    pub role: RoleType,
+    pub content: ContentType,
+    pub created_at: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<String>,
+    pub tokens_used: i32,
+    /// Index in `messages` of the parent, none for the first message.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent: Option<usize>,
+}
+
+impl From<SettingsModel> for ExportedSettings {
+    fn from(settings: SettingsModel) -> Self {
+        Self {
+            provider: settings.provider,
+            system: settings.system,
+            model_name: settings.model_name,
+            stream: settings.stream,
+            max_tokens: settings.max_tokens,
+            temperature: settings.temperature,
+            top_k: settings.top_k,
+            top_p: settings.top_p,
+            credential_profile: settings.credential_profile,
+        }
+    }
+}
+
+impl From<AnthropicSettingsModel> for ExportedAnthropicSettings {
+    fn from(settings: AnthropicSettingsModel) -> Self {
+        Self {
+            budget_tokens: settings.budget_tokens,
+        }
+    }
+}
+
+impl From<ThinkingModel> for ExportedThinking {
+    fn from(thinking: ThinkingModel) -> Self {
+        Self {
+            budget_tokens: thinking.budget_tokens,
+            enabled: thinking.enabled,
+        }
+    }
+}
+
+impl From<StoredMessage> for ExportedMessage {
+    fn from(message: StoredMessage) -> Self {
+        Self {
+            role: message.role,
+            content: message.content,
+            created_at: message.created_at,
+            updated_at: message.updated_at,
+            tokens_used: message.tokens_used,
+            parent: None,
+        }
+    }
+}
+
+impl TopicExport {
+    /// Messages of the active branch, oldest first.
+    pub fn active_branch(&self) -> Vec<&ExportedMessage> {
+        let mut branch = Vec::new();
+        let mut current = self
+            .active_leaf
+            .or_else(|| self.messages.len().checked_sub(1));
+
+        while let Some(index) = current {
+            let Some(message) = self.messages.get(index) else {
+                break;
+            };
+            branch.push(message);
+            // Parents come first, anything else would loop.
+            current = message.parent.filter(|parent| *parent < index);
+        }
+
+        branch.reverse();
+        branch
+    }
+
+    /// The settings the topic was last used with.
+    pub fn latest_settings(&self) -> Option<&ExportedSettings> {
+        self.settings.last()
+    }
+}
+
+pub fn role_label(role: &RoleType) -> &'static str {
+    match role {
+        RoleType::System => "System",
+        RoleType::User => "User",
+        RoleType::Assistant => "Assistant",
+    }
+}
+
+pub fn render(export: &TopicExport, format: ExportFormat) -> Result<String, String> {
+    match format {
+        ExportFormat::Markdown => Ok(markdown::render(export)),
+        ExportFormat::Json => serde_json::to_string_pretty(export).map_err(|e| e.to_string()),
+        ExportFormat::Html => Ok(html::render(export)),
+    }
+}
+
+/// Reads a file written by `export_topic` in JSON, a ChatGPT
+/// `conversations.json`, a Claude.ai `conversations.json`, or either
+/// export archive as downloaded.
+pub fn parse_import(bytes: &[u8]) -> Result<Vec<TopicExport>, String> {
+    if bytes.starts_with(b"PK\x03\x04") {
+        return parse_import(&read_conversations_entry(bytes)?);
+    }
+
+    let value: serde_json::Value =
+        serde_json::from_slice(bytes).map_err(|e| format!("Invalid JSON: {}", e))?;
+
+    let conversations = match value {
+        serde_json::Value::Object(_) => {
+            let export = serde_json::from_value(value)
+                .map_err(|e| format!("Invalid topic export: {}", e))?;
+            return Ok(vec![export]);
+        }
+        serde_json::Value::Array(conversations) => conversations,
+        _ => return Err("Unrecognized import file".to_string()),
+    };
+
+    let exports = match conversations.first() {
+        None => Vec::new(),
+        Some(first) if first.get("mapping").is_some() => chatgpt::parse(conversations)?,
+        Some(first) if first.get("chat_messages").is_some() => claude::parse(conversations)?,
+        Some(_) => return Err("Unrecognized conversations file".to_string()),
+    };
+
+    Ok(exports
+        .into_iter()
+        .filter(|export| !export.messages.is_empty())
+        .collect())
+}
+
+fn read_conversations_entry(bytes: &[u8]) -> Result<Vec<u8>, String> {
+    let mut archive =
+        zip::ZipArchive::new(Cursor::new(bytes)).map_err(|e| format!("Invalid archive: {}", e))?;
+
+    let name = archive
+        .file_names()
+        .filter(|name| name.rsplit('/').next() == Some("conversations.json"))
+        .min_by_key(|name| name.len())
+        .map(str::to_string)
+        .ok_or("The archive has no conversations.json")?;
+
+    let mut entry = archive.by_name(&name).map_err(|e| e.to_string())?;
+    let mut contents = Vec::new();
+    entry
+        .read_to_end(&mut contents)
+        .map_err(|e| format!("Failed to read {}: {}", name, e))?;
+
+    Ok(contents)
+}
+
+/// Creates a topic for each conversation of the file.
+pub async fn import_bytes(
+    db: &DatabaseConnection,
+    bytes: &[u8],
+) -> Result<Vec<TopicModel>, String> {
+    let mut topics = Vec::new();
+    for export in parse_import(bytes)? {
+        topics.push(restore_topic(db, export).await?);
+    }
+    Ok(topics)
+}
+
+fn parse_date(date: &str) -> Result<DateTime<FixedOffset>, String> {
+    DateTime::parse_from_rfc3339(date).map_err(|e| format!("Invalid date '{}': {}", date, e))
+}
+
+pub async fn collect_topic(db: &DatabaseConnection, topic_id: i32) -> Result<TopicExport, String> {
+    let topic = Topics::find_by_id(topic_id)
+        .one(db)
+        .await
+        .map_err(|e| e.to_string())?
+        .ok_or_else(|| format!("Topic {} not found", topic_id))?;
+
+    let settings = Settings::find()
+        .filter(SettingsColumn::TopicId.eq(topic_id))
+        .order_by_asc(SettingsColumn::Id)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let anthropic_settings = AnthropicSettings::find()
+        .filter(AnthropicSettingsColumn::TopicId.eq(topic_id))
+        .order_by_asc(AnthropicSettingsColumn::Id)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let thinking = Thinking::find()
+        .filter(ThinkingColumn::TopicId.eq(topic_id))
+        .order_by_asc(ThinkingColumn::Id)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let messages = Messages::find()
+        .filter(MessagesColumn::TopicId.eq(topic_id))
+        .order_by_asc(MessagesColumn::Id)
+        .find_with_related(MessageBlocks)
+        .all(db)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    // Ids grow with insertion, so parents are listed before their children.
+    let index_of = |id: Option<i32>| {
+        id.and_then(|id| messages.iter().position(|(message, _)| message.id == id))
+    };
+    let active_leaf = index_of(topic.active_leaf_id);
+    let parents: Vec<Option<usize>> = messages
+        .iter()
+        .map(|(message, _)| index_of(message.parent_id))
+        .collect();
+
+    Ok(TopicExport {
+        version: EXPORT_VERSION,
+        name: topic.name,
+        created_at: topic.created_at.to_rfc3339(),
+        last_accessed_at: topic.last_accessed_at.to_rfc3339(),
+        settings: settings.into_iter().map(Into::into).collect(),
+        anthropic_settings: anthropic_settings.into_iter().map(Into::into).collect(),
+        thinking: thinking.into_iter().map(Into::into).collect(),
+        messages: messages
+            .into_iter()
+            .zip(parents)
+            .map(|((message, blocks), parent)| ExportedMessage {
+                parent,
+                ..StoredMessage::from_model(message, blocks).into()
+            })
+            .collect(),
+        active_leaf,
+    })
+}
+
+/// Creates a new topic from an export, in a single transaction.
+pub async fn restore_topic(
+    db: &DatabaseConnection,
+    export: TopicExport,
+) -> Result<TopicModel, String> {
+    if export.version > EXPORT_VERSION {
+        return Err(format!(
+            "Unsupported export version {}, this build reads up to {}",
+            export.version, EXPORT_VERSION
+        ));
+    }
+
+    let now = Utc::now().fixed_offset();
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+
+    let topic = TopicActiveModel {
+        id: NotSet,
+        name: Set(export.name),
+        created_at: Set(parse_date(&export.created_at)?),
+        last_accessed_at: Set(now),
+        active_leaf_id: Set(None),
+    }
+    .insert(&transaction)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    for settings in export.settings {
+        SettingsActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            provider: Set(settings.provider),
+            system: Set(settings.system),
+            model_name: Set(settings.model_name),
+            stream: Set(settings.stream),
+            max_tokens: Set(settings.max_tokens),
+            temperature: Set(settings.temperature),
+            top_k: Set(settings.top_k),
+            top_p: Set(settings.top_p),
+            credential_profile: Set(settings.credential_profile),
+        }
+        .insert(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    for settings in export.anthropic_settings {
+        AnthropicSettingsActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            budget_tokens: Set(settings.budget_tokens),
+        }
+        .insert(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    for thinking in export.thinking {
+        ThinkingActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            budget_tokens: Set(thinking.budget_tokens),
+            enabled: Set(thinking.enabled),
+        }
+        .insert(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let linear = export.version < 2;
+    let mut ids: Vec<i32> = Vec::with_capacity(export.messages.len());
+    for (index, message) in export.messages.into_iter().enumerate() {
+        let parent = if linear {
+            index.checked_sub(1)
+        } else {
+            message.parent
+        };
+        let parent_id = match parent {
+            Some(parent) => Some(
+                *ids.get(parent)
+                    .ok_or_else(|| format!("Message {} has a parent listed after it", index))?,
+            ),
+            None => None,
+        };
+
+        let inserted = MessagesActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            role: Set(message.role),
+            content: Set(message_blocks::flatten_text(&message.content)),
+            created_at: Set(parse_date(&message.created_at)?),
+            updated_at: Set(message.updated_at.as_deref().map(parse_date).transpose()?),
+            tokens_used: Set(message.tokens_used),
+            parent_id: Set(parent_id),
+        }
+        .insert(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
+        for block in message_blocks::blocks_from_content(inserted.id, &message.content) {
+            block
+                .insert(&transaction)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        ids.push(inserted.id);
+    }
+
+    let active_leaf = if linear {
+        ids.last().copied()
+    } else {
+        export.active_leaf.and_then(|index| ids.get(index).copied())
+    };
+    branches::set_active_leaf(&transaction, topic.id, active_leaf).await?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
+    Ok(TopicModel {
+        active_leaf_id: active_leaf,
+        ..topic
+    })
+}
+
+#[command]
+pub async fn export_topic(
+    db: State<'_, DatabaseConnection>,
+    topic_id: i32,
+    format: ExportFormat,
+) -> Result<String, AppError> {
+    Ok(render(&collect_topic(&db, topic_id).await?, format)?)
+}
+
+/// Imports the file at `path`, see `parse_import` for the formats.
+#[command]
+pub async fn import_topic(
+    db: State<'_, DatabaseConnection>,
+    path: String,
+) -> Result<Vec<TopicModel>, AppError> {
+    let bytes = std::fs::read(&path).map_err(|e| format!("Failed to read {}: {}", path, e))?;
+    Ok(import_bytes(&db, &bytes).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::ContentItem;
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::Database;
+
+    #[tokio::test]
+    async fn exported_topics_import_back() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let export = TopicExport {
+            version: EXPORT_VERSION,
+            name: "Dates".to_string(),
+            created_at: "2025-04-01T10:00:00+02:00".to_string(),
+            last_accessed_at: "2025-04-02T10:00:00+02:00".to_string(),
+            settings: vec![ExportedSettings {
+                provider: "anthropic".to_string(),
+                system: "Be brief.".to_string(),
+                model_name: "claude-3-7-sonnet-20250219".to_string(),
+                stream: true,
+                max_tokens: 1024,
+                temperature: Some(0.5),
+                top_k: None,
+                top_p: None,
+                credential_profile: Some("work".to_string()),
+            }],
+            anthropic_settings: vec![ExportedAnthropicSettings {
+                budget_tokens: 2048,
+            }],
+            thinking: vec![ExportedThinking {
+                budget_tokens: 1024,
+                enabled: true,
+            }],
+            messages: vec![
+                ExportedMessage {
+                    role: RoleType::User,
+                    content: ContentType::PlainText("What time is it?".to_string()),
+                    created_at: "2025-04-01T10:00:00+02:00".to_string(),
+                    updated_at: None,
+                    tokens_used: 0,
+                    parent: None,
+                },
+                ExportedMessage {
+                    role: RoleType::Assistant,
+                    content: ContentType::StructuredContent(vec![
+                        ContentItem::Thinking {
+                            thinking: "Check the clock.".to_string(),
+                            signature: "sig".to_string(),
+                        },
+                        ContentItem::Text {
+                            text: "Noon.".to_string(),
+                        },
+                    ]),
+                    created_at: "2025-04-01T10:00:05+02:00".to_string(),
+                    updated_at: None,
+                    tokens_used: 42,
+                    parent: Some(0),
+                },
+                ExportedMessage {
+                    role: RoleType::Assistant,
+                    content: ContentType::PlainText("Twelve o'clock.".to_string()),
+                    created_at: "2025-04-01T10:01:00+02:00".to_string(),
+                    updated_at: None,
+                    tokens_used: 12,
+                    parent: Some(0),
+                },
+            ],
+            active_leaf: Some(1),
+        };
+
+        let topic = restore_topic(&db, export.clone()).await.unwrap();
+        let exported = collect_topic(&db, topic.id).await.unwrap();
+
+        assert_eq!(exported.name, "Dates");
+        assert_eq!(exported.active_leaf, Some(1));
+        assert_eq!(exported.settings.len(), 1);
+        assert_eq!(
+            exported.settings[0].credential_profile.as_deref(),
+            Some("work")
+        );
+        assert_eq!(exported.anthropic_settings, export.anthropic_settings);
+        assert_eq!(exported.thinking, export.thinking);
+        assert_eq!(
+            serde_json::to_value(&exported.messages).unwrap(),
+            serde_json::to_value(&export.messages).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn first_version_exports_import_as_one_branch() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let message = |role, text: &str| ExportedMessage {
+            role,
+            content: ContentType::PlainText(text.to_string()),
+            created_at: "2025-04-01T10:00:00+02:00".to_string(),
+            updated_at: None,
+            tokens_used: 0,
+            parent: None,
+        };
+        let export = TopicExport {
+            version: 1,
+            name: "Old".to_string(),
+            created_at: "2025-04-01T10:00:00+02:00".to_string(),
+            last_accessed_at: "2025-04-01T10:00:00+02:00".to_string(),
+            settings: Vec::new(),
+            anthropic_settings: Vec::new(),
+            thinking: Vec::new(),
+            messages: vec![
+                message(RoleType::User, "Hi"),
+                message(RoleType::Assistant, "Hello"),
+                message(RoleType::User, "Bye"),
+            ],
+            active_leaf: None,
+        };
+
+        let topic = restore_topic(&db, export).await.unwrap();
+        let exported = collect_topic(&db, topic.id).await.unwrap();
+
+        let parents: Vec<_> = exported
+            .messages
+            .iter()
+            .map(|message| message.parent)
+            .collect();
+        assert_eq!(parents, vec![None, Some(0), Some(1)]);
+        assert_eq!(exported.active_leaf, Some(2));
+    }
+
+    #[test]
+    fn second_version_exports_have_no_anthropic_settings() {
+        let exports = parse_import(
+            br#"{
+                "version": 2,
+                "name": "Old",
+                "createdAt": "2025-04-01T10:00:00+02:00",
+                "lastAccessedAt": "2025-04-01T10:00:00+02:00",
+                "settings": [],
+                "messages": []
+            }"#,
+        )
+        .unwrap();
+
+        assert!(exports[0].anthropic_settings.is_empty());
+        assert!(exports[0].thinking.is_empty());
+    }
+
+    #[test]
+    fn renders_the_active_branch() {
+        let message = |role, content, parent| ExportedMessage {
+            role,
+            content,
+            created_at: "2025-04-01T10:00:00+02:00".to_string(),
+            updated_at: None,
+            tokens_used: 0,
+            parent,
+        };
+        let export = TopicExport {
+            version: EXPORT_VERSION,
+            name: "Fences <3".to_string(),
+            created_at: "2025-04-01T10:00:00+02:00".to_string(),
+            last_accessed_at: "2025-04-01T10:00:00+02:00".to_string(),
+            settings: Vec::new(),
+            anthropic_settings: Vec::new(),
+            thinking: Vec::new(),
+            messages: vec![
+                message(
+                    RoleType::User,
+                    ContentType::PlainText("Quote ``` please".to_string()),
+                    None,
+                ),
+                message(
+                    RoleType::Assistant,
+                    ContentType::PlainText("Discarded".to_string()),
+                    Some(0),
+                ),
+                message(
+                    RoleType::Assistant,
+                    ContentType::StructuredContent(vec![
+                        ContentItem::Thinking {
+                            thinking: "Use ``` inside".to_string(),
+                            signature: "sig".to_string(),
+                        },
+                        ContentItem::Text {
+                            text: "Done.".to_string(),
+                        },
+                    ]),
+                    Some(0),
+                ),
+            ],
+            active_leaf: Some(2),
+        };
+
+        let markdown = render(&export, ExportFormat::Markdown).unwrap();
+        assert_eq!(
+            markdown,
+            "# Fences <3\n\n## User\n\nQuote ``` please\n\n## Assistant\n\n\
+             ````thinking\nUse ``` inside\n````\n\nDone.\n"
+        );
+
+        let html = render(&export, ExportFormat::Html).unwrap();
+        assert!(html.contains("<title>Fences &lt;3</title>"));
+        assert!(!html.contains("Discarded"));
+        assert!(html.contains("<details><summary>Thinking</summary>"));
+    }
+
+    #[test]
+    fn detects_import_formats() {
+        let claude = serde_json::json!([{
+            "name": "Soup",
+            "created_at": "2025-04-01T10:00:00.000000Z",
+            "chat_messages": [
+                { "uuid": "a", "sender": "human", "text": "Recipe?" },
+                { "uuid": "b", "sender": "assistant", "text": "",
+                  "content": [{ "type": "text", "text": "Boil water." }] },
+                { "uuid": "c", "sender": "assistant", "text": "" },
+            ],
+        }]);
+        let exports = parse_import(claude.to_string().as_bytes()).unwrap();
+        assert_eq!(exports.len(), 1);
+        assert_eq!(exports[0].messages.len(), 2);
+        assert_eq!(exports[0].active_leaf, Some(1));
+
+        let mut archive = zip::ZipWriter::new(Cursor::new(Vec::new()));
+        archive
+            .start_file(
+                "export/conversations.json",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .unwrap();
+        std::io::Write::write_all(&mut archive, claude.to_string().as_bytes()).unwrap();
+        let bytes = archive.finish().unwrap().into_inner();
+        assert_eq!(parse_import(&bytes).unwrap()[0].name, "Soup");
+
+        assert!(parse_import(b"[{\"title\": \"?\"}]").is_err());
+    }
+}
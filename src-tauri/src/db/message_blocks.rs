@@ -0,0 +1,271 @@
+use sea_orm::{NotSet, Set};
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+use crate::core::models::{ContentItem, ContentType, ImageSource};
+use entity::message_blocks::{
+    ActiveModel as MessageBlocksActiveModel, BlockType, Model as MessageBlocksModel,
+};
+use entity::messages::{Model as MessagesModel, RoleType};
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../shared/types/db/messages.ts",
+    rename_all = "camelCase"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct StoredMessage {
+    pub id: i32,
+    pub topic_id: i32,
+    #[ts(type = "Role")]
+    pub role: RoleType,
+    pub content: ContentType,
+    #[ts(type = "string")]
+    pub created_at: String,
+    pub tokens_used: i32,
+    #[ts(type = "string | null")]
+    pub updated_at: Option<String>,
+    #[ts(type = "number | null")]
+    pub parent_id: Option<i32>,
+}
+
+impl StoredMessage {
+    pub fn from_model(message: MessagesModel, blocks: Vec<MessageBlocksModel>) -> Self {
+        let content = content_from_blocks(&message.content, blocks);
+
+        Self {
+            id: message.id,
+            topic_id: message.topic_id,
+            role: message.role,
+            content,
+            created_at: message.created_at.to_rfc3339(),
+            tokens_used: message.tokens_used,
+            updated_at: message.updated_at.map(|date| date.to_rfc3339()),
+            parent_id: message.parent_id,
+        }
+    }
+}
+
+/// Text stored in `messages.content`, kept for listing and search.
+pub fn flatten_text(content: &ContentType) -> String {
+    match content {
+        ContentType::PlainText(text) => text.clone(),
+        ContentType::StructuredContent(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                ContentItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+/// Plain text messages keep living in `messages.content` only; structured
+/// content gets one row per block so it can be replayed as-is.
+pub fn blocks_from_content(
+    message_id: i32,
+    content: &ContentType,
+) -> Vec<MessageBlocksActiveModel> {
+    let items = match content {
+        ContentType::PlainText(_) => return Vec::new(),
+        ContentType::StructuredContent(items) => items,
+    };
+
+    items
+        .iter()
+        .enumerate()
+        .map(|(position, item)| {
+            let mut block = MessageBlocksActiveModel {
+                id: NotSet,
+                message_id: Set(message_id),
+                position: Set(position as i32),
+                block_type: NotSet,
+                text: Set(None),
+                source_type: Set(None),
+                media_type: Set(None),
+                data: Set(None),
+                signature: Set(None),
+                tool_use_id: Set(None),
+                tool_name: Set(None),
+                tool_input: Set(None),
+                is_error: Set(None),
+            };
+
+            match item {
+                ContentItem::Text { text } => {
+                    block.block_type = Set(BlockType::Text);
+                    block.text = Set(Some(text.clone()));
+                }
+                ContentItem::Image { source } => {
+                    block.block_type = Set(BlockType::Image);
+                    block.source_type = Set(Some(source.source_type.clone()));
+                    block.media_type = Set(Some(source.media_type.clone()));
+                    block.data = Set(Some(source.data.clone()));
+                }
+                ContentItem::Thinking {
+                    thinking,
+                    signature,
+                } => {
+                    block.block_type = Set(BlockType::Thinking);
+                    block.text = Set(Some(thinking.clone()));
+                    block.signature = Set(Some(signature.clone()));
+                }
+                ContentItem::RedactedThinking { data } => {
+                    block.block_type = Set(BlockType::RedactedThinking);
+                    block.data = Set(Some(data.clone()));
+                }
+                ContentItem::ToolUse { id, name, input } => {
+                    block.block_type = Set(BlockType::ToolUse);
+                    block.tool_use_id = Set(Some(id.clone()));
+                    block.tool_name = Set(Some(name.clone()));
+                    block.tool_input = Set(Some(input.to_string()));
+                }
+                ContentItem::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => {
+                    block.block_type = Set(BlockType::ToolResult);
+                    block.tool_use_id = Set(Some(tool_use_id.clone()));
+                    block.text = Set(Some(content.clone()));
+                    block.is_error = Set(*is_error);
+                }
+            }
+
+            block
+        })
+        .collect()
+}
+
+/// Rebuilds the content of a message from its blocks, falling back to the
+/// flattened text for messages stored before `message_blocks` existed.
+pub fn content_from_blocks(fallback: &str, mut blocks: Vec<MessageBlocksModel>) -> ContentType {
+    if blocks.is_empty() {
+        return ContentType::PlainText(fallback.to_string());
+    }
+
+    blocks.sort_by_key(|block| block.position);
+
+    ContentType::StructuredContent(
+        blocks
+            .into_iter()
+            .map(|block| match block.block_type {
+                BlockType::Text => ContentItem::Text {
+                    text: block.text.unwrap_or_default(),
+                },
+                BlockType::Image => ContentItem::Image {
+                    source: ImageSource {
+                        source_type: block.source_type.unwrap_or_else(|| "base64".to_string()),
+                        media_type: block.media_type.unwrap_or_default(),
+                        data: block.data.unwrap_or_default(),
+                    },
+                },
+                BlockType::Thinking => ContentItem::Thinking {
+                    thinking: block.text.unwrap_or_default(),
+                    signature: block.signature.unwrap_or_default(),
+                },
+                BlockType::RedactedThinking => ContentItem::RedactedThinking {
+                    data: block.data.unwrap_or_default(),
+                },
+                BlockType::ToolUse => ContentItem::ToolUse {
+                    id: block.tool_use_id.unwrap_or_default(),
+                    name: block.tool_name.unwrap_or_default(),
+                    input: block
+                        .tool_input
+                        .and_then(|input| serde_json::from_str(&input).ok())
+                        .unwrap_or_else(|| serde_json::json!({})),
+                },
+                BlockType::ToolResult => ContentItem::ToolResult {
+                    tool_use_id: block.tool_use_id.unwrap_or_default(),
+                    content: block.text.unwrap_or_default(),
+                    is_error: block.is_error,
+                },
+            })
+            .collect(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stored(blocks: Vec<MessageBlocksActiveModel>) -> Vec<MessageBlocksModel> {
+        blocks
+            .into_iter()
+            .enumerate()
+            .map(|(id, block)| MessageBlocksModel {
+                id: id as i32 + 1,
+                message_id: block.message_id.unwrap(),
+                position: block.position.unwrap(),
+                block_type: block.block_type.unwrap(),
+                text: block.text.unwrap(),
+                source_type: block.source_type.unwrap(),
+                media_type: block.media_type.unwrap(),
+                data: block.data.unwrap(),
+                signature: block.signature.unwrap(),
+                tool_use_id: block.tool_use_id.unwrap(),
+                tool_name: block.tool_name.unwrap(),
+                tool_input: block.tool_input.unwrap(),
+                is_error: block.is_error.unwrap(),
+            })
+            .rev()
+            .collect()
+    }
+
+    #[test]
+    fn structured_content_round_trips_through_blocks() {
+        let content = ContentType::StructuredContent(vec![
+            ContentItem::Thinking {
+                thinking: "The user wants the time.".to_string(),
+                signature: "sig-123".to_string(),
+            },
+            ContentItem::RedactedThinking {
+                data: "opaque".to_string(),
+            },
+            ContentItem::Text {
+                text: "Let me check.".to_string(),
+            },
+            ContentItem::ToolUse {
+                id: "toolu_1".to_string(),
+                name: "current_datetime".to_string(),
+                input: serde_json::json!({ "timezone": "utc" }),
+            },
+            ContentItem::ToolResult {
+                tool_use_id: "toolu_1".to_string(),
+                content: "2025-04-12T09:30:15+00:00".to_string(),
+                is_error: Some(false),
+            },
+            ContentItem::Image {
+                source: ImageSource {
+                    source_type: "base64".to_string(),
+                    media_type: "image/png".to_string(),
+                    data: "AAAA".to_string(),
+                },
+            },
+        ]);
+
+        let blocks = stored(blocks_from_content(7, &content));
+        assert_eq!(blocks.len(), 6);
+        assert!(blocks.iter().all(|block| block.message_id == 7));
+
+        let rebuilt = content_from_blocks("Let me check.", blocks);
+        assert_eq!(
+            serde_json::to_value(&rebuilt).unwrap(),
+            serde_json::to_value(&content).unwrap()
+        );
+        assert_eq!(flatten_text(&content), "Let me check.");
+    }
+
+    #[test]
+    fn plain_text_is_not_split_into_blocks() {
+        let content = ContentType::PlainText("Hello".to_string());
+
+        assert!(blocks_from_content(1, &content).is_empty());
+        assert!(matches!(
+            content_from_blocks("Hello", Vec::new()),
+            ContentType::PlainText(text) if text == "Hello"
+        ));
+    }
+}
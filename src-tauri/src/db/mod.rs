@@ -1 +1,7 @@
+pub mod backup;
+pub mod branches;
+pub mod conversation;
+pub mod export;
+pub mod message_blocks;
+pub mod search;
 pub mod topics;
@@ -0,0 +1,359 @@
+use sea_orm::prelude::DateTimeWithTimeZone;
+use sea_orm::{ActiveEnum, DatabaseConnection, DbBackend, FromQueryResult, Statement, Value};
+use serde::{Deserialize, Serialize};
+use tauri::{command, State};
+use ts_rs::TS;
+
+use crate::core::error::AppError;
+use entity::messages::RoleType;
+
+const DEFAULT_LIMIT: u32 = 50;
+const MAX_LIMIT: u32 = 200;
+const SNIPPET_TOKENS: u32 = 16;
+
+/// Delimit matches in the snippet FTS5 returns, then stripped from it. Control
+/// characters never show up in messages.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../shared/types/db/search.ts",
+    rename_all = "camelCase"
+)]
+#[serde(default, rename_all = "camelCase")]
+pub struct SearchFilters {
+    /// Only topics that were configured with this provider.
+    #[ts(type = "string | null")]
+    pub provider: Option<String>,
+    #[ts(type = "string | null")]
+    pub model: Option<String>,
+    #[ts(type = "Role | null")]
+    pub role: Option<RoleType>,
+    /// RFC 3339 dates, both inclusive.
+    #[ts(type = "string | null")]
+    pub from: Option<String>,
+    #[ts(type = "string | null")]
+    pub to: Option<String>,
+    #[ts(type = "number | null")]
+    pub limit: Option<u32>,
+}
+
+/// Part of a snippet matching the query, in UTF-16 code units so it can
+/// index JavaScript strings directly.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/db/search.ts")]
+pub struct Highlight {
+    pub start: u32,
+    pub end: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(
+    export,
+    export_to = "../../shared/types/db/search.ts",
+    rename_all = "camelCase"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct SearchHit {
+    pub message_id: i32,
+    pub topic_id: i32,
+    pub topic_name: String,
+    #[ts(type = "Role")]
+    pub role: RoleType,
+    #[ts(type = "string")]
+    pub created_at: String,
+    pub snippet: String,
+    pub highlights: Vec<Highlight>,
+    /// BM25 score, lower is better.
+    pub rank: f64,
+}
+
+#[derive(Debug, FromQueryResult)]
+struct SearchRow {
+    message_id: i32,
+    topic_id: i32,
+    topic_name: String,
+    role: RoleType,
+    created_at: DateTimeWithTimeZone,
+    snippet: String,
+    rank: f64,
+}
+
+/// Turns what the user typed into an FTS5 query: every word must match, the
+/// last one as a prefix so results show up while typing. Quoting each word
+/// keeps FTS5 operators and punctuation from being parsed.
+fn match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+        .collect();
+    let (last, terms) = terms.split_last()?;
+
+    Some(
+        terms
+            .iter()
+            .cloned()
+            .chain(std::iter::once(format!("{}*", last)))
+            .collect::<Vec<_>>()
+            .join(" "),
+    )
+}
+
+/// Strips the match delimiters from a snippet and returns where they were.
+fn extract_highlights(marked: &str) -> (String, Vec<Highlight>) {
+    let mut snippet = String::with_capacity(marked.len());
+    let mut highlights = Vec::new();
+    let mut offset = 0u32;
+    let mut start = None;
+
+    for c in marked.chars() {
+        match c {
+            MATCH_START => start = Some(offset),
+            MATCH_END => {
+                if let Some(start) = start.take() {
+                    highlights.push(Highlight { start, end: offset });
+                }
+            }
+            c => {
+                snippet.push(c);
+                offset += c.len_utf16() as u32;
+            }
+        }
+    }
+
+    (snippet, highlights)
+}
+
+pub async fn search(
+    db: &DatabaseConnection,
+    query: &str,
+    filters: &SearchFilters,
+) -> Result<Vec<SearchHit>, String> {
+    let Some(expression) = match_expression(query) else {
+        return Ok(Vec::new());
+    };
+
+    let mut sql = format!(
+        "SELECT messages.id AS message_id, messages.topic_id, topics.name AS topic_name, \
+         messages.role, messages.created_at, \
+         snippet(messages_fts, 0, '{}', '{}', '…', {}) AS snippet, \
+         bm25(messages_fts, 1.0, 0.5) AS rank \
+         FROM messages_fts \
+         JOIN messages ON messages.id = messages_fts.rowid \
+         JOIN topics ON topics.id = messages.topic_id \
+         WHERE messages_fts MATCH ?",
+        MATCH_START, MATCH_END, SNIPPET_TOKENS
+    );
+    let mut values: Vec<Value> = vec![expression.into()];
+
+    if let Some(provider) = &filters.provider {
+        sql.push_str(
+            " AND EXISTS (SELECT 1 FROM models_settings \
+             WHERE models_settings.topic_id = messages.topic_id \
+             AND lower(models_settings.provider) = lower(?))",
+        );
+        values.push(provider.clone().into());
+    }
+    if let Some(model) = &filters.model {
+        sql.push_str(
+            " AND EXISTS (SELECT 1 FROM models_settings \
+             WHERE models_settings.topic_id = messages.topic_id AND models_settings.model_name = ?)",
+        );
+        values.push(model.clone().into());
+    }
+    if let Some(role) = &filters.role {
+        sql.push_str(" AND messages.role = ?");
+        values.push(role.to_value().into());
+    }
+    // julianday() normalizes the offsets, the stored dates and the filters
+    // may not share one.
+    if let Some(from) = &filters.from {
+        sql.push_str(" AND julianday(messages.created_at) >= julianday(?)");
+        values.push(from.clone().into());
+    }
+    if let Some(to) = &filters.to {
+        sql.push_str(" AND julianday(messages.created_at) <= julianday(?)");
+        values.push(to.clone().into());
+    }
+
+    sql.push_str(" ORDER BY rank LIMIT ?");
+    values.push(filters.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT).into());
+
+    let rows = SearchRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        values,
+    ))
+    .all(db)
+    .await
+    .map_err(|e| e.to_string())?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| {
+            let (snippet, highlights) = extract_highlights(&row.snippet);
+
+            SearchHit {
+                message_id: row.message_id,
+                topic_id: row.topic_id,
+                topic_name: row.topic_name,
+                role: row.role,
+                created_at: row.created_at.to_rfc3339(),
+                snippet,
+                highlights,
+                rank: row.rank,
+            }
+        })
+        .collect())
+}
+
+/// Ranked messages of every topic matching `query`.
+#[command]
+pub async fn search_messages(
+    db: State<'_, DatabaseConnection>,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SearchHit>, AppError> {
+    Ok(search(&db, &query, &filters.unwrap_or_default()).await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use entity::{messages, models_settings, topics};
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{ActiveModelTrait, Database, EntityTrait, NotSet, Set};
+
+    #[test]
+    fn quotes_terms_and_prefixes_the_last_one() {
+        assert_eq!(match_expression("   "), None);
+        assert_eq!(
+            match_expression("rust \"async\" traits"),
+            Some("\"rust\" \"\"\"async\"\"\" \"traits\"*".to_string())
+        );
+    }
+
+    #[test]
+    fn extracts_highlights_in_utf16_offsets() {
+        let (snippet, highlights) = extract_highlights("🦀 \u{2}crab\u{3} and \u{2}rust\u{3}");
+
+        assert_eq!(snippet, "🦀 crab and rust");
+        assert_eq!(
+            highlights,
+            vec![
+                Highlight { start: 3, end: 7 },
+                Highlight { start: 12, end: 16 }
+            ]
+        );
+    }
+
+    async fn insert_message(
+        db: &DatabaseConnection,
+        topic_id: i32,
+        role: RoleType,
+        content: &str,
+    ) -> i32 {
+        let now = Utc::now().fixed_offset();
+        messages::ActiveModel {
+            id: NotSet,
+            topic_id: Set(topic_id),
+            role: Set(role),
+            content: Set(content.to_string()),
+            created_at: Set(now),
+            updated_at: Set(Some(now)),
+            tokens_used: Set(0),
+            parent_id: Set(None),
+        }
+        .insert(db)
+        .await
+        .unwrap()
+        .id
+    }
+
+    #[tokio::test]
+    async fn index_follows_messages_and_topics() {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+
+        let now = Utc::now().fixed_offset();
+        let topic = topics::ActiveModel {
+            id: NotSet,
+            name: Set("Gardening".to_string()),
+            created_at: Set(now),
+            last_accessed_at: Set(now),
+            active_leaf_id: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+        models_settings::ActiveModel {
+            id: NotSet,
+            topic_id: Set(topic.id),
+            provider: Set("anthropic".to_string()),
+            system: Set(String::new()),
+            model_name: Set("claude-3-7-sonnet-20250219".to_string()),
+            stream: Set(true),
+            max_tokens: Set(1024),
+            temperature: Set(None),
+            top_k: Set(None),
+            top_p: Set(None),
+            credential_profile: Set(None),
+        }
+        .insert(&db)
+        .await
+        .unwrap();
+
+        let question =
+            insert_message(&db, topic.id, RoleType::User, "When to plant tomatoes?").await;
+        let answer = insert_message(
+            &db,
+            topic.id,
+            RoleType::Assistant,
+            "Plant tomatoes after the last frost.",
+        )
+        .await;
+
+        let hits = search(&db, "tomat", &SearchFilters::default())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+
+        let filters = SearchFilters {
+            role: Some(RoleType::Assistant),
+            provider: Some("Anthropic".to_string()),
+            ..Default::default()
+        };
+        let hits = search(&db, "tomatoes", &filters).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].message_id, answer);
+        assert_eq!(hits[0].topic_name, "Gardening");
+        assert_eq!(hits[0].snippet, "Plant tomatoes after the last frost.");
+        assert_eq!(hits[0].highlights, vec![Highlight { start: 6, end: 14 }]);
+
+        let filters = SearchFilters {
+            provider: Some("mistral".to_string()),
+            ..Default::default()
+        };
+        assert!(search(&db, "tomatoes", &filters).await.unwrap().is_empty());
+
+        let mut renamed: topics::ActiveModel = topic.into();
+        renamed.name = Set("Vegetables".to_string());
+        renamed.update(&db).await.unwrap();
+        let hits = search(&db, "vegetables", &SearchFilters::default())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 2);
+
+        messages::Entity::delete_by_id(question)
+            .exec(&db)
+            .await
+            .unwrap();
+        let hits = search(&db, "tomatoes", &SearchFilters::default())
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+}
@@ -1,20 +1,26 @@
 use chrono::Utc;
 use log::LevelFilter;
 use sea_orm::{
-    ActiveModelTrait, ColumnTrait, Condition, ConnectOptions, Database, DatabaseConnection, DbErr,
-    EntityTrait, IntoActiveModel, NotSet, QueryFilter, QueryOrder, Set, TransactionTrait,
+    ActiveModelTrait, ColumnTrait, ConnectOptions, ConnectionTrait, Database, DatabaseConnection,
+    DbErr, EntityTrait, IntoActiveModel, NotSet, QueryFilter, QueryOrder, Set, TransactionTrait,
+    TryIntoModel,
 };
 use tauri::State;
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::time::Duration;
 use tauri::command;
 use tauri::Manager;
 use tauri::AppHandle;
 
+use crate::core::error::AppError;
+use crate::core::models::ContentType;
+use crate::db::backup;
+use crate::db::branches;
+use crate::db::message_blocks::{self, StoredMessage};
+use entity::message_blocks::{Column as MessageBlocksColumn, Entity as MessageBlocks};
 use entity::messages::{
-    ActiveModel as MessagesActiveModel, Column as MessagesColumn, Entity as Messages,
-    Model as MessagesModel, RoleType,
+    ActiveModel as MessagesActiveModel, Column as MessagesColumn, Entity as Messages, RoleType,
 };
 use entity::topics::{
     ActiveModel as TopicActiveModel, Column as TopicsColumn, Entity as Topics, Model,
@@ -26,7 +32,7 @@ use migration::{Migrator, MigratorTrait};
 
 
 
-fn get_db_path(app: &tauri::AppHandle) -> PathBuf {
+pub(crate) fn get_db_path(app: &tauri::AppHandle) -> PathBuf {
     let app_dir = app
         .path()
         .app_data_dir()
@@ -36,7 +42,16 @@ fn get_db_path(app: &tauri::AppHandle) -> PathBuf {
 }
 
 pub async fn initialize_database(app_handle: &AppHandle) -> Result<DatabaseConnection, DbErr> {
-    let db_path = get_db_path(app_handle);
+    connect(&get_db_path(app_handle)).await
+}
+
+/// Opens the database at `db_path`, creating it if needed, and runs the
+/// pending migrations. A restore staged by `restore_database` is swapped in
+/// first, and the database is backed up before any migration.
+pub async fn connect(db_path: &Path) -> Result<DatabaseConnection, DbErr> {
+    if backup::apply_staged_restore(db_path).map_err(DbErr::Custom)? {
+        eprintln!("Restored the database from a backup");
+    }
     let db_url = format!("sqlite:{}?mode=rwc", db_path.to_string_lossy());
 
     let mut opt = ConnectOptions::new(&db_url);
@@ -48,35 +63,34 @@ pub async fn initialize_database(app_handle: &AppHandle) -> Result<DatabaseConne
         .sqlx_logging_level(LevelFilter::Info);
     let conn: DatabaseConnection = Database::connect(&db_url).await?;
 
+    backup::backup_before_migrations(&conn, db_path)
+        .await
+        .map_err(DbErr::Custom)?;
     Migrator::up(&conn, None).await?;
 
     Result::Ok(conn)
 }
 
 #[command]
-pub async fn get_all_topics(db: State<'_, DatabaseConnection>) -> Result<Vec<Model>, String> {
-    Topics::find()
+pub async fn get_all_topics(db: State<'_, DatabaseConnection>) -> Result<Vec<Model>, AppError> {
+    Ok(Topics::find()
         .order_by_desc(TopicsColumn::Id)
         .all(&*db)
         .await
-        .map_err(|e| e.to_string())
+        .map_err(|e| e.to_string())?)
 }
 
+/// Messages of the topic's active branch, the ones sent to the model.
 #[command]
 pub async fn get_messages_by_topic(
     db: State<'_, DatabaseConnection>,
     topic_id: i32,
-) -> Result<Vec<MessagesModel>, String> {
-    Messages::find()
-        .filter(MessagesColumn::TopicId.eq(topic_id))
-        .order_by_asc(MessagesColumn::CreatedAt)
-        .all(&*db)
-        .await
-        .map_err(|e| e.to_string())
+) -> Result<Vec<StoredMessage>, AppError> {
+    Ok(branches::active_path(&*db, topic_id).await?)
 }
 
 #[command]
-pub async fn add_topic(db: State<'_, DatabaseConnection>, name: String) -> Result<Model, String> {
+pub async fn add_topic(db: State<'_, DatabaseConnection>, name: String) -> Result<Model, AppError> {
     let now = Utc::now().fixed_offset();
 
     let new_topic = TopicActiveModel {
@@ -84,6 +98,7 @@ pub async fn add_topic(db: State<'_, DatabaseConnection>, name: String) -> Resul
         name: Set(name),
         created_at: Set(now),
         last_accessed_at: Set(now),
+        active_leaf_id: Set(None),
     };
 
     let result = new_topic
@@ -94,41 +109,92 @@ pub async fn add_topic(db: State<'_, DatabaseConnection>, name: String) -> Resul
     Ok(result)
 }
 
-#[command]
-pub async fn add_message(
-    db: State<'_, DatabaseConnection>,
+/// Inserts a message and its content blocks under `parent_id`.
+pub(crate) async fn insert_message<C: ConnectionTrait>(
+    db: &C,
     topic_id: i32,
     role: RoleType,
-    content: String,
-    tokens_used: Option<i32>,
-) -> Result<MessagesModel, String> {
+    content: &ContentType,
+    tokens_used: i32,
+    parent_id: Option<i32>,
+) -> Result<StoredMessage, String> {
     let timestamp = Utc::now().fixed_offset();
-    let tokens_used_value = tokens_used.unwrap_or(0);
 
     let new_message = MessagesActiveModel {
         id: NotSet,
         topic_id: Set(topic_id),
         role: Set(role),
-        content: Set(content),
+        content: Set(message_blocks::flatten_text(content)),
         created_at: Set(timestamp),
         updated_at: Set(Some(timestamp)),
-        tokens_used: Set(tokens_used_value),
+        tokens_used: Set(tokens_used),
+        parent_id: Set(parent_id),
     };
 
     let result = new_message
-        .insert(&*db)
+        .insert(db)
         .await
         .map_err(|e: DbErr| e.to_string())?;
 
-    println!("result: {:?}", result);
+    let mut blocks = Vec::new();
+    for block in message_blocks::blocks_from_content(result.id, content) {
+        blocks.push(block.insert(db).await.map_err(|e| e.to_string())?);
+    }
+
+    Ok(StoredMessage::from_model(result, blocks))
+}
+
+#[command]
+pub async fn add_message(
+    db: State<'_, DatabaseConnection>,
+    topic_id: i32,
+    role: RoleType,
+    content: ContentType,
+    tokens_used: Option<i32>,
+) -> Result<StoredMessage, AppError> {
+    let tokens_used_value = tokens_used.unwrap_or(0);
+
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+    let parent_id = branches::append_parent(&transaction, topic_id).await?;
+
+    let result = insert_message(
+        &transaction,
+        topic_id,
+        role,
+        &content,
+        tokens_used_value,
+        parent_id,
+    )
+    .await?;
+    branches::set_active_leaf(&transaction, topic_id, Some(result.id)).await?;
+
+    transaction.commit().await.map_err(|e| e.to_string())?;
 
     Ok(result)
 }
 
 #[command]
-pub async fn remove_topic(db: State<'_, DatabaseConnection>, topic_id: i32) -> Result<bool, String> {
+pub async fn remove_topic(
+    db: State<'_, DatabaseConnection>,
+    topic_id: i32,
+) -> Result<bool, AppError> {
     let transaction = db.begin().await.map_err(|e| e.to_string())?;
 
+    let message_ids: Vec<i32> = Messages::find()
+        .filter(MessagesColumn::TopicId.eq(topic_id))
+        .all(&transaction)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_iter()
+        .map(|message| message.id)
+        .collect();
+
+    MessageBlocks::delete_many()
+        .filter(MessageBlocksColumn::MessageId.is_in(message_ids))
+        .exec(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
     Messages::delete_many()
         .filter(MessagesColumn::TopicId.eq(topic_id))
         .exec(&transaction)
@@ -157,7 +223,7 @@ pub async fn edit_topic_name(
     db: State<'_, DatabaseConnection>,
     topic_id: i32,
     new_name: String,
-) -> Result<bool, String> {
+) -> Result<bool, AppError> {
     let topic_to_udpate = Topics::find_by_id(topic_id)
         .one(&*db)
         .await
@@ -180,18 +246,32 @@ pub async fn edit_topic_name(
 pub async fn remove_messages(
     db: State<'_, DatabaseConnection>,
     message_ids: Vec<i32>,
-) -> Result<u64, String> {
+) -> Result<u64, AppError> {
+    let transaction = db.begin().await.map_err(|e| e.to_string())?;
+    branches::detach(&transaction, &message_ids).await?;
+
+    MessageBlocks::delete_many()
+        .filter(MessageBlocksColumn::MessageId.is_in(message_ids.clone()))
+        .exec(&transaction)
+        .await
+        .map_err(|e| e.to_string())?;
+
     let result = Messages::delete_many()
         .filter(MessagesColumn::Id.is_in(message_ids))
-        .exec(&*db)
+        .exec(&transaction)
         .await
         .map_err(|e| e.to_string())?;
 
+    transaction.commit().await.map_err(|e| e.to_string())?;
+
     Ok(result.rows_affected)
 }
 
 #[command]
-pub async fn update_topic_access(topic_id: i32, db: State<'_, DatabaseConnection>) -> Result<bool, String> {
+pub async fn update_topic_access(
+    topic_id: i32,
+    db: State<'_, DatabaseConnection>,
+) -> Result<bool, AppError> {
     let topic_to_udpate = Topics::find_by_id(topic_id)
         .one(&*db)
         .await
@@ -213,7 +293,9 @@ pub async fn update_topic_access(topic_id: i32, db: State<'_, DatabaseConnection
 }
 
 #[command]
-pub async fn get_last_accessed_topic(db: State<'_, DatabaseConnection>) -> Result<Option<i32>, String> {
+pub async fn get_last_accessed_topic(
+    db: State<'_, DatabaseConnection>,
+) -> Result<Option<i32>, AppError> {
     let topic_result = Topics::find()
         .order_by_desc(TopicsColumn::LastAccessedAt)
         .one(&*db)
@@ -223,35 +305,39 @@ pub async fn get_last_accessed_topic(db: State<'_, DatabaseConnection>) -> Resul
     Ok(topic_result.map(|topic| topic.id))
 }
 
+/// Saves the model settings of a topic, updating the latest row when there
+/// is one instead of adding another.
 #[command]
 pub async fn add_settings(
     db: State<'_, DatabaseConnection>,
     topic_id: i32,
     settings: SettingsModel,
-) -> Result<SettingsModel, String> {
-    println!("settings: {:?}", settings);
-
-    let new_settings = SettingsActiveModel {
-        id: NotSet,
-        topic_id: Set(topic_id),
-        max_tokens: Set(settings.max_tokens),
-        model_name: Set(settings.model_name),
-        provider: Set(settings.provider),
-        system: Set(settings.system),
-        stream: Set(settings.stream),
-        temperature: Set(settings.temperature),
-        top_k: Set(Some(settings.top_k.unwrap_or(0.0))),
-        top_p: Set(Some(settings.top_p.unwrap_or(0.0))),
+) -> Result<SettingsModel, AppError> {
+    let mut new_settings = match latest_settings(&*db, topic_id).await? {
+        Some(existing) => existing.into_active_model(),
+        None => SettingsActiveModel {
+            id: NotSet,
+            topic_id: Set(topic_id),
+            ..Default::default()
+        },
     };
-    println!("new_settings: {:?}", new_settings);
+    new_settings.max_tokens = Set(settings.max_tokens);
+    new_settings.model_name = Set(settings.model_name);
+    new_settings.provider = Set(settings.provider);
+    new_settings.system = Set(settings.system);
+    new_settings.stream = Set(settings.stream);
+    new_settings.temperature = Set(settings.temperature);
+    new_settings.top_k = Set(settings.top_k);
+    new_settings.top_p = Set(settings.top_p);
+    new_settings.credential_profile = Set(settings.credential_profile);
 
     let result = new_settings
-        .insert(&*db)
+        .save(&*db)
         .await
+        .map_err(|e: DbErr| e.to_string())?
+        .try_into_model()
         .map_err(|e: DbErr| e.to_string())?;
 
-    println!("result: {:?}", result);
-
     Ok(result)
 }
 
@@ -259,11 +345,33 @@ pub async fn add_settings(
 pub async fn get_settings(
     db: State<'_, DatabaseConnection>,
     topic_id: i32,
-) -> Result<Vec<SettingsModel>, String> {
-    Settings::find()
+) -> Result<Vec<SettingsModel>, AppError> {
+    Ok(Settings::find()
         .filter(SettingsColumn::TopicId.eq(topic_id))
         .all(&*db)
         .await
+        .map_err(|e| e.to_string())?)
+}
+
+/// The settings a topic was last saved with.
+pub async fn latest_settings<C: ConnectionTrait>(
+    db: &C,
+    topic_id: i32,
+) -> Result<Option<SettingsModel>, String> {
+    Settings::find()
+        .filter(SettingsColumn::TopicId.eq(topic_id))
+        .order_by_desc(SettingsColumn::Id)
+        .one(db)
+        .await
         .map_err(|e| e.to_string())
 }
 
+/// Credential profile pinned by the latest settings of a topic, if any.
+pub async fn get_topic_credential_profile(
+    db: &DatabaseConnection,
+    topic_id: i32,
+) -> Result<Option<String>, String> {
+    let settings = latest_settings(db, topic_id).await?;
+
+    Ok(settings.and_then(|settings| settings.credential_profile))
+}
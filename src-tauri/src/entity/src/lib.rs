@@ -1,5 +1,6 @@
 pub mod anthropic_models_settings;
 pub mod app_appearance;
+pub mod message_blocks;
 pub mod messages;
 pub mod models_settings;
 pub mod providers_tools;
@@ -22,6 +23,12 @@ pub mod prelude {
     pub use super::anthropic_models_settings::Model as AnthropicModelsSettingsModel;
     pub use super::anthropic_models_settings::Relation as AnthropicModelsSettingsRelation;
 
+    pub use super::message_blocks::ActiveModel as MessageBlockActiveModel;
+    pub use super::message_blocks::Column as MessageBlockColumn;
+    pub use super::message_blocks::Entity as MessageBlocks;
+    pub use super::message_blocks::Model as MessageBlockModel;
+    pub use super::message_blocks::Relation as MessageBlockRelation;
+
     pub use super::messages::ActiveModel as MessageActiveModel;
     pub use super::messages::Column as MessageColumn;
     pub use super::messages::Entity as Messages;
@@ -0,0 +1,92 @@
+use sea_orm::entity::prelude::*;
+use serde::{Deserialize, Serialize};
+use ts_rs::TS;
+
+#[derive(Debug, Clone, PartialEq, Eq, EnumIter, DeriveActiveEnum, Serialize, Deserialize, TS)]
+#[sea_orm(rs_type = "String", db_type = "Text", enum_name = "block_type")]
+#[ts(
+    export,
+    export_to = "../../../../shared/types/db/enum.ts",
+    rename = "BlockType"
+)]
+pub enum BlockType {
+    #[sea_orm(string_value = "text")]
+    #[serde(rename = "text")]
+    Text,
+    #[sea_orm(string_value = "image")]
+    #[serde(rename = "image")]
+    Image,
+    #[sea_orm(string_value = "thinking")]
+    #[serde(rename = "thinking")]
+    Thinking,
+    #[sea_orm(string_value = "redacted_thinking")]
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking,
+    #[sea_orm(string_value = "tool_use")]
+    #[serde(rename = "tool_use")]
+    ToolUse,
+    #[sea_orm(string_value = "tool_result")]
+    #[serde(rename = "tool_result")]
+    ToolResult,
+}
+
+// One row per content block, the columns in use depend on `block_type`:
+// - text / thinking / tool_result : `text`
+// - image : `source_type`, `media_type`, `data`
+// - thinking : `signature`, redacted_thinking : `data`
+// - tool_use : `tool_use_id`, `tool_name`, `tool_input` (JSON)
+// - tool_result : `tool_use_id`, `is_error`
+#[derive(Clone, Debug, PartialEq, DeriveEntityModel, Serialize, Deserialize, TS)]
+#[sea_orm(table_name = "message_blocks")]
+#[ts(
+    export,
+    export_to = "../../../../shared/types/db/messages.ts",
+    rename = "MessageBlock",
+    rename_all = "camelCase"
+)]
+#[serde(rename_all = "camelCase")]
+pub struct Model {
+    #[sea_orm(primary_key)]
+    pub id: i32,
+    #[sea_orm(column_type = "Integer")]
+    pub message_id: i32,
+    pub position: i32,
+    pub block_type: BlockType,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub text: Option<String>,
+    #[sea_orm(nullable)]
+    pub source_type: Option<String>,
+    #[sea_orm(nullable)]
+    pub media_type: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub data: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub signature: Option<String>,
+    #[sea_orm(nullable)]
+    pub tool_use_id: Option<String>,
+    #[sea_orm(nullable)]
+    pub tool_name: Option<String>,
+    #[sea_orm(column_type = "Text", nullable)]
+    pub tool_input: Option<String>,
+    #[sea_orm(nullable)]
+    pub is_error: Option<bool>,
+}
+
+#[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
+pub enum Relation {
+    #[sea_orm(
+        belongs_to = "super::messages::Entity",
+        from = "Column::MessageId",
+        to = "super::messages::Column::Id",
+        on_delete = "Cascade"
+    )]
+    Message,
+}
+
+impl Related<super::messages::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::Message.def()
+    }
+}
+
+impl ActiveModelBehavior for ActiveModel {}
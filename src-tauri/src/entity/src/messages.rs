@@ -42,6 +42,10 @@ pub struct Model {
     pub tokens_used: i32,
     #[sea_orm(nullable)]
     pub updated_at: Option<DateTimeWithTimeZone>,
+    /// The message this one answers or follows, `None` for the first one.
+    /// Siblings are alternative branches of the conversation.
+    #[sea_orm(nullable)]
+    pub parent_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -52,6 +56,9 @@ pub enum Relation {
         to = "super::topics::Column::Id"
     )]
     Topic,
+
+    #[sea_orm(has_many = "super::message_blocks::Entity")]
+    MessageBlocks,
 }
 
 impl Related<super::topics::Entity> for Entity {
@@ -60,4 +67,10 @@ impl Related<super::topics::Entity> for Entity {
     }
 }
 
+impl Related<super::message_blocks::Entity> for Entity {
+    fn to() -> RelationDef {
+        Relation::MessageBlocks.def()
+    }
+}
+
 impl ActiveModelBehavior for ActiveModel {}
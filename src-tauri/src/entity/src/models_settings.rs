@@ -34,6 +34,10 @@ pub struct Model {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[sea_orm(column_type = "Float", nullable)]
     pub top_p: Option<f32>,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[sea_orm(nullable)]
+    pub credential_profile: Option<String>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
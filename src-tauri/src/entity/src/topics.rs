@@ -17,6 +17,9 @@ pub struct Model {
     pub name: String,
     pub created_at: DateTimeWithTimeZone,
     pub last_accessed_at: DateTimeWithTimeZone,
+    /// Last message of the branch shown and sent to the model.
+    #[sea_orm(nullable)]
+    pub active_leaf_id: Option<i32>,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
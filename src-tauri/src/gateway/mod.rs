@@ -0,0 +1,270 @@
+mod openai;
+mod server;
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_store::StoreExt;
+use ts_rs::TS;
+
+use crate::core::models::TokenUsage;
+use crate::utils::crypto;
+
+pub const GATEWAY_STORE: &str = "gateway.json";
+
+const SETTINGS_KEY: &str = "settings";
+const CLIENTS_KEY: &str = "clients";
+const TOKEN_PREFIX: &str = "lhe-";
+const TOKEN_LEN: usize = 32;
+
+/// The local OpenAI-compatible server, persisted in the gateway store. It
+/// only ever listens on 127.0.0.1.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(default)]
+pub struct GatewaySettings {
+    pub enabled: bool,
+    pub port: u16,
+}
+
+impl Default for GatewaySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            port: 4891,
+        }
+    }
+}
+
+/// What a client sent through the gateway since it was created.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+#[serde(default)]
+pub struct GatewayUsage {
+    pub requests: u64,
+    /// Requests the provider failed or refused.
+    pub errors: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    #[ts(optional)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_used_at: Option<String>,
+}
+
+impl GatewayUsage {
+    fn record(&mut self, usage: Option<&TokenUsage>, failed: bool) {
+        self.requests += 1;
+        if failed {
+            self.errors += 1;
+        }
+        if let Some(usage) = usage {
+            self.prompt_tokens += u64::from(usage.prompt_tokens);
+            self.completion_tokens += u64::from(usage.completion_tokens);
+        }
+        self.last_used_at = Some(chrono::Utc::now().to_rfc3339());
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, TS)]
+#[ts(export, export_to = "../../shared/types/llm/core.ts")]
+pub struct GatewayClient {
+    pub name: String,
+    pub created_at: String,
+    pub usage: GatewayUsage,
+}
+
+/// Only a hash of the token is kept, the token itself is shown once when
+/// the client is created.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct StoredClient {
+    name: String,
+    token_hash: String,
+    created_at: String,
+    #[serde(default)]
+    usage: GatewayUsage,
+}
+
+fn hash_token(token: &str) -> String {
+    format!("{:x}", Sha256::digest(token.as_bytes()))
+}
+
+/// Handle on the running server, managed by the app.
+#[derive(Default)]
+pub struct Gateway {
+    server: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Serializes the read-modify-write of the clients entry, requests
+    /// finishing together would otherwise lose usage.
+    clients_lock: Mutex<()>,
+}
+
+pub fn load_settings<R: Runtime>(app: &AppHandle<R>) -> Result<GatewaySettings, String> {
+    let store = app.store(GATEWAY_STORE).map_err(|e| e.to_string())?;
+
+    store
+        .get(SETTINGS_KEY)
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Invalid gateway settings: {}", e))
+        })
+        .unwrap_or_else(|| Ok(GatewaySettings::default()))
+}
+
+/// Persists the settings, then starts, stops or moves the server to match.
+pub async fn save_settings<R: Runtime>(
+    app: &AppHandle<R>,
+    settings: &GatewaySettings,
+) -> Result<(), String> {
+    let store = app.store(GATEWAY_STORE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(settings).map_err(|e| e.to_string())?;
+    store.set(SETTINGS_KEY, value);
+    store.save().map_err(|e| e.to_string())?;
+
+    apply_settings(app).await
+}
+
+/// Stops the running server, if any, and starts a new one when enabled.
+pub async fn apply_settings<R: Runtime>(app: &AppHandle<R>) -> Result<(), String> {
+    let gateway = app
+        .try_state::<Gateway>()
+        .ok_or("Gateway is not initialized")?;
+    // Aborting drops the listener so the port is free again, connections
+    // already accepted keep streaming until they are done.
+    let server = gateway.server.lock().unwrap().take();
+    if let Some(server) = server {
+        server.abort();
+        let _ = server.await;
+    }
+
+    let settings = load_settings(app)?;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let address = SocketAddr::from((Ipv4Addr::LOCALHOST, settings.port));
+    let listener = tokio::net::TcpListener::bind(address)
+        .await
+        .map_err(|e| format!("Failed to listen on {}: {}", address, e))?;
+
+    let router = server::router(app.clone());
+    let server = tauri::async_runtime::spawn(async move {
+        if let Err(e) = axum::serve(listener, router).await {
+            eprintln!("Gateway stopped: {}", e);
+        }
+    });
+    *gateway.server.lock().unwrap() = Some(server);
+
+    Ok(())
+}
+
+fn load_clients<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<StoredClient>, String> {
+    let store = app.store(GATEWAY_STORE).map_err(|e| e.to_string())?;
+
+    store
+        .get(CLIENTS_KEY)
+        .map(|value| {
+            serde_json::from_value(value).map_err(|e| format!("Invalid gateway clients: {}", e))
+        })
+        .unwrap_or_else(|| Ok(Vec::new()))
+}
+
+fn save_clients<R: Runtime>(app: &AppHandle<R>, clients: &[StoredClient]) -> Result<(), String> {
+    let store = app.store(GATEWAY_STORE).map_err(|e| e.to_string())?;
+    let value = serde_json::to_value(clients).map_err(|e| e.to_string())?;
+    store.set(CLIENTS_KEY, value);
+    store.save().map_err(|e| e.to_string())
+}
+
+fn update_clients<R: Runtime, T>(
+    app: &AppHandle<R>,
+    update: impl FnOnce(&mut Vec<StoredClient>) -> Result<T, String>,
+) -> Result<T, String> {
+    let gateway = app
+        .try_state::<Gateway>()
+        .ok_or("Gateway is not initialized")?;
+    let _guard = gateway.clients_lock.lock().unwrap();
+
+    let mut clients = load_clients(app)?;
+    let result = update(&mut clients)?;
+    save_clients(app, &clients)?;
+
+    Ok(result)
+}
+
+pub fn list_clients<R: Runtime>(app: &AppHandle<R>) -> Result<Vec<GatewayClient>, String> {
+    Ok(load_clients(app)?
+        .into_iter()
+        .map(|client| GatewayClient {
+            name: client.name,
+            created_at: client.created_at,
+            usage: client.usage,
+        })
+        .collect())
+}
+
+/// Registers a client and returns its bearer token.
+pub fn create_client<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Client name cannot be empty".to_string());
+    }
+
+    let token = format!(
+        "{}{}",
+        TOKEN_PREFIX,
+        crypto::random_bytes(TOKEN_LEN)
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>()
+    );
+
+    update_clients(app, |clients| {
+        if clients.iter().any(|client| client.name == name) {
+            return Err(format!("Client '{}' already exists", name));
+        }
+        clients.push(StoredClient {
+            name: name.to_string(),
+            token_hash: hash_token(&token),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            usage: GatewayUsage::default(),
+        });
+        Ok(())
+    })?;
+
+    Ok(token)
+}
+
+pub fn delete_client<R: Runtime>(app: &AppHandle<R>, name: &str) -> Result<(), String> {
+    update_clients(app, |clients| {
+        let count = clients.len();
+        clients.retain(|client| client.name != name);
+        if clients.len() == count {
+            return Err(format!("Client '{}' not found", name));
+        }
+        Ok(())
+    })
+}
+
+/// Name of the client owning the token.
+fn authenticate<R: Runtime>(app: &AppHandle<R>, token: &str) -> Result<Option<String>, String> {
+    let token_hash = hash_token(token);
+
+    Ok(load_clients(app)?
+        .into_iter()
+        .find(|client| client.token_hash == token_hash)
+        .map(|client| client.name))
+}
+
+fn record_usage<R: Runtime>(
+    app: &AppHandle<R>,
+    name: &str,
+    usage: Option<&TokenUsage>,
+    failed: bool,
+) -> Result<(), String> {
+    update_clients(app, |clients| {
+        if let Some(client) = clients.iter_mut().find(|client| client.name == name) {
+            client.usage.record(usage, failed);
+        }
+        Ok(())
+    })
+}
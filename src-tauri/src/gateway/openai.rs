@@ -0,0 +1,380 @@
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::core::models::{
+    ChatMessage, ChatRequest, ChatRole, ContentItem, ContentType, ImageSource, ProviderType,
+    StreamResponse, TokenUsage,
+};
+use crate::core::stream::{StopReason, StreamEvent};
+use crate::providers::anthropic::AnthropicSystemPrompt;
+
+/// Used when the client leaves `max_tokens` out, Anthropic requires one.
+pub const DEFAULT_MAX_TOKENS: u32 = 4096;
+
+/// The subset of the OpenAI chat completions request the gateway forwards.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChatCompletionRequest {
+    /// `<provider>/<model>`, e.g. `anthropic/claude-3-7-sonnet-20250219`.
+    pub model: String,
+    pub messages: Vec<Message>,
+    #[serde(default)]
+    pub stream: bool,
+    pub max_tokens: Option<u32>,
+    pub max_completion_tokens: Option<u32>,
+    pub temperature: Option<f32>,
+    pub top_p: Option<f32>,
+    pub seed: Option<i32>,
+    pub frequency_penalty: Option<f32>,
+    pub presence_penalty: Option<f32>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Message {
+    pub role: String,
+    #[serde(default)]
+    pub content: Option<Content>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Content {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ImageUrl {
+    pub url: String,
+}
+
+/// Splits a gateway model id into the provider and the provider's model.
+pub fn route_model(model: &str) -> Result<(ProviderType, String), String> {
+    let (provider, model) = model
+        .split_once('/')
+        .filter(|(_, model)| !model.is_empty())
+        .ok_or_else(|| format!("Model must look like <provider>/<model>, got '{}'", model))?;
+    let provider = ProviderType::from_str(provider)
+        .ok_or_else(|| format!("Unknown provider: {}", provider))?;
+
+    Ok((provider, model.to_string()))
+}
+
+pub fn model_id(provider: &ProviderType, model: &str) -> String {
+    format!("{}/{}", provider.as_str(), model)
+}
+
+impl ChatCompletionRequest {
+    /// System and developer messages become the system prompt, the others
+    /// the conversation.
+    pub fn into_chat_request(self) -> Result<ChatRequest, String> {
+        let mut system = Vec::new();
+        let mut messages = Vec::new();
+
+        for message in self.messages {
+            let content = match message.content {
+                Some(content) => content_type(content)?,
+                None => continue,
+            };
+            let role = match message.role.as_str() {
+                "system" | "developer" => {
+                    system.push(text_of(&content));
+                    continue;
+                }
+                "user" => ChatRole::User,
+                "assistant" => ChatRole::Assistant,
+                role => return Err(format!("Unsupported message role: {}", role)),
+            };
+            messages.push(ChatMessage { role, content });
+        }
+
+        if messages.is_empty() {
+            return Err("At least one user message is required".to_string());
+        }
+
+        Ok(ChatRequest {
+            messages,
+            system: AnthropicSystemPrompt::Simple(system.join("\n\n")),
+            max_tokens: Some(
+                self.max_completion_tokens
+                    .or(self.max_tokens)
+                    .unwrap_or(DEFAULT_MAX_TOKENS),
+            ),
+            stream: Some(true),
+            temperature: self.temperature,
+            top_p: self.top_p,
+            seed: self.seed,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+            ..Default::default()
+        })
+    }
+}
+
+fn content_type(content: Content) -> Result<ContentType, String> {
+    let parts = match content {
+        Content::Text(text) => return Ok(ContentType::PlainText(text)),
+        Content::Parts(parts) => parts,
+    };
+
+    parts
+        .into_iter()
+        .map(|part| match part {
+            ContentPart::Text { text } => Ok(ContentItem::Text { text }),
+            ContentPart::ImageUrl { image_url } => {
+                image_source(&image_url.url).map(|source| ContentItem::Image { source })
+            }
+        })
+        .collect::<Result<Vec<_>, String>>()
+        .map(ContentType::StructuredContent)
+}
+
+/// Only inline `data:` images are supported, the gateway does not download.
+fn image_source(url: &str) -> Result<ImageSource, String> {
+    let (media_type, data) = url
+        .strip_prefix("data:")
+        .and_then(|url| url.split_once(";base64,"))
+        .ok_or_else(|| "Only base64 data URLs are supported for images".to_string())?;
+
+    Ok(ImageSource {
+        source_type: "base64".to_string(),
+        media_type: media_type.to_string(),
+        data: data.to_string(),
+    })
+}
+
+fn text_of(content: &ContentType) -> String {
+    match content {
+        ContentType::PlainText(text) => text.clone(),
+        ContentType::StructuredContent(items) => items
+            .iter()
+            .filter_map(|item| match item {
+                ContentItem::Text { text } => Some(text.as_str()),
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+    }
+}
+
+pub fn finish_reason(reason: &StopReason) -> &'static str {
+    match reason {
+        StopReason::MaxTokens => "length",
+        StopReason::ToolUse => "tool_calls",
+        StopReason::EndTurn | StopReason::StopSequence | StopReason::Cancelled => "stop",
+    }
+}
+
+pub fn error_body(message: &str, error_type: &str) -> Value {
+    json!({
+        "error": {
+            "message": message,
+            "type": error_type,
+            "code": null,
+        }
+    })
+}
+
+pub fn completion(id: &str, model: &str, created: i64, response: &StreamResponse) -> Value {
+    let reason = response.stop_reason.clone().unwrap_or(StopReason::EndTurn);
+
+    json!({
+        "id": id,
+        "object": "chat.completion",
+        "created": created,
+        "model": model,
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": response.response,
+            },
+            "finish_reason": finish_reason(&reason),
+        }],
+        "usage": response.usage,
+    })
+}
+
+/// Turns the stream events of one generation into `chat.completion.chunk`
+/// objects. Usage is sent with the final chunk, as OpenAI does when asked
+/// for it.
+pub struct CompletionChunks {
+    id: String,
+    model: String,
+    created: i64,
+    usage: Option<TokenUsage>,
+}
+
+impl CompletionChunks {
+    pub fn new(id: String, model: String, created: i64) -> Self {
+        Self {
+            id,
+            model,
+            created,
+            usage: None,
+        }
+    }
+
+    pub fn chunk(&mut self, event: StreamEvent) -> Option<Value> {
+        match event {
+            StreamEvent::Start { .. } => {
+                Some(self.delta(json!({ "role": "assistant", "content": "" }), None))
+            }
+            StreamEvent::TextDelta { text, .. } => {
+                Some(self.delta(json!({ "content": text }), None))
+            }
+            StreamEvent::ThinkingDelta { thinking, .. } => {
+                Some(self.delta(json!({ "reasoning_content": thinking }), None))
+            }
+            StreamEvent::Usage { usage, .. } => {
+                self.usage = Some(usage);
+                None
+            }
+            StreamEvent::Stop { reason, .. } => {
+                let mut chunk = self.delta(json!({}), Some(finish_reason(&reason)));
+                if let Some(usage) = self.usage.take() {
+                    chunk["usage"] = json!(usage);
+                }
+                Some(chunk)
+            }
+            StreamEvent::Error { error, .. } => {
+                Some(error_body(&error.to_string(), "provider_error"))
+            }
+            // Tools run inside the app, clients only see the final answer.
+            StreamEvent::Retrying { .. } | StreamEvent::ToolCall { .. } => None,
+        }
+    }
+
+    fn delta(&self, delta: Value, finish_reason: Option<&str>) -> Value {
+        json!({
+            "id": self.id,
+            "object": "chat.completion.chunk",
+            "created": self.created,
+            "model": self.model,
+            "choices": [{
+                "index": 0,
+                "delta": delta,
+                "finish_reason": finish_reason,
+            }],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn routes_models_by_provider_prefix() {
+        let (provider, model) = route_model("cloudflare/@cf/meta/llama-3-8b-instruct").unwrap();
+        assert_eq!(provider.as_str(), "cloudflare");
+        assert_eq!(model, "@cf/meta/llama-3-8b-instruct");
+
+        assert!(route_model("gpt-4o").is_err());
+        assert!(route_model("nope/gpt-4o").is_err());
+        assert!(route_model("openai/").is_err());
+    }
+
+    #[test]
+    fn translates_openai_requests() {
+        let request: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "anthropic/claude-3-7-sonnet-20250219",
+            "messages": [
+                { "role": "system", "content": "Be brief." },
+                { "role": "developer", "content": [{ "type": "text", "text": "Use French." }] },
+                { "role": "user", "content": [
+                    { "type": "text", "text": "What is this?" },
+                    { "type": "image_url", "image_url": { "url": "data:image/png;base64,iVBOR" } }
+                ] },
+                { "role": "assistant", "content": "Un chat." },
+                { "role": "user", "content": "Sure?" }
+            ],
+            "max_tokens": 100,
+            "temperature": 0.2
+        }))
+        .unwrap();
+
+        let request = request.into_chat_request().unwrap();
+        let value = serde_json::to_value(&request).unwrap();
+
+        assert_eq!(value["system"], "Be brief.\n\nUse French.");
+        assert_eq!(value["max_tokens"], 100);
+        assert_eq!(request.messages.len(), 3);
+        assert_eq!(
+            value["messages"][0]["content"][1],
+            json!({
+                "type": "image",
+                "source": { "type": "base64", "media_type": "image/png", "data": "iVBOR" }
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_unsupported_messages() {
+        let request = |messages: Value| {
+            serde_json::from_value::<ChatCompletionRequest>(json!({
+                "model": "openai/gpt-4o",
+                "messages": messages,
+            }))
+            .unwrap()
+            .into_chat_request()
+        };
+
+        assert!(request(json!([{ "role": "tool", "content": "42" }])).is_err());
+        assert!(request(json!([{ "role": "system", "content": "Hi" }])).is_err());
+        assert!(request(json!([{ "role": "user", "content": [
+            { "type": "image_url", "image_url": { "url": "https://example.com/cat.png" } }
+        ] }]))
+        .is_err());
+    }
+
+    #[test]
+    fn streams_chunks_with_usage_on_the_last_one() {
+        let request_id = "req-1".to_string();
+        let mut chunks =
+            CompletionChunks::new("chatcmpl-1".to_string(), "openai/gpt-4o".to_string(), 0);
+
+        let start = chunks
+            .chunk(StreamEvent::Start {
+                request_id: request_id.clone(),
+            })
+            .unwrap();
+        assert_eq!(start["choices"][0]["delta"]["role"], "assistant");
+
+        let text = chunks
+            .chunk(StreamEvent::TextDelta {
+                request_id: request_id.clone(),
+                text: "Hi".to_string(),
+            })
+            .unwrap();
+        assert_eq!(text["choices"][0]["delta"]["content"], "Hi");
+        assert!(text["choices"][0]["finish_reason"].is_null());
+
+        assert!(chunks
+            .chunk(StreamEvent::Usage {
+                request_id: request_id.clone(),
+                usage: TokenUsage {
+                    prompt_tokens: 3,
+                    completion_tokens: 1,
+                    total_tokens: 4,
+                },
+            })
+            .is_none());
+
+        let stop = chunks
+            .chunk(StreamEvent::Stop {
+                request_id,
+                reason: StopReason::MaxTokens,
+            })
+            .unwrap();
+        assert_eq!(stop["object"], "chat.completion.chunk");
+        assert_eq!(stop["choices"][0]["finish_reason"], "length");
+        assert_eq!(stop["usage"]["total_tokens"], 4);
+    }
+}
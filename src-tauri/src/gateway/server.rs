@@ -0,0 +1,330 @@
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::sse::{Event, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use futures_util::future::{self, AbortHandle};
+use serde_json::{json, Value};
+use std::convert::Infallible;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager, Runtime};
+
+use super::openai::{self, ChatCompletionRequest, CompletionChunks};
+use crate::api::commands;
+use crate::core::error::AppError;
+use crate::core::llm_trait::{self, ProviderRegistry};
+use crate::core::sink::ChannelSink;
+use crate::core::stream::{self, StopReason, StreamEmitter};
+
+/// Aborts a streamed generation when the response body is dropped.
+struct AbortOnDrop(AbortHandle);
+
+impl Drop for AbortOnDrop {
+    fn drop(&mut self) {
+        self.0.abort();
+    }
+}
+
+/// An error in the shape OpenAI clients expect.
+struct GatewayError {
+    status: StatusCode,
+    error_type: &'static str,
+    message: String,
+}
+
+impl GatewayError {
+    fn new(status: StatusCode, error_type: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            error_type,
+            message: message.into(),
+        }
+    }
+
+    fn invalid_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, "invalid_request_error", message)
+    }
+}
+
+impl From<AppError> for GatewayError {
+    fn from(error: AppError) -> Self {
+        let status = match &error {
+            AppError::Api {
+                status: Some(status),
+                ..
+            } => StatusCode::from_u16(*status).unwrap_or(StatusCode::BAD_GATEWAY),
+            AppError::Api { .. } | AppError::Network { .. } => StatusCode::BAD_GATEWAY,
+            AppError::Internal { .. } => StatusCode::INTERNAL_SERVER_ERROR,
+        };
+
+        Self::new(status, "provider_error", error.to_string())
+    }
+}
+
+impl IntoResponse for GatewayError {
+    fn into_response(self) -> Response {
+        (
+            self.status,
+            Json(openai::error_body(&self.message, self.error_type)),
+        )
+            .into_response()
+    }
+}
+
+pub fn router<R: Runtime>(app: AppHandle<R>) -> Router {
+    Router::new()
+        .route("/v1/models", get(list_models::<R>))
+        .route("/v1/chat/completions", post(chat_completions::<R>))
+        .with_state(app)
+}
+
+/// Name of the client whose bearer token authorizes the request.
+fn authenticate<R: Runtime>(
+    app: &AppHandle<R>,
+    headers: &HeaderMap,
+) -> Result<String, GatewayError> {
+    let unauthorized = |message: &str| {
+        GatewayError::new(StatusCode::UNAUTHORIZED, "authentication_error", message)
+    };
+
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or_else(|| unauthorized("Missing bearer token"))?;
+
+    super::authenticate(app, token.trim())
+        .map_err(|e| GatewayError::new(StatusCode::INTERNAL_SERVER_ERROR, "server_error", e))?
+        .ok_or_else(|| unauthorized("Invalid bearer token"))
+}
+
+fn record_usage<R: Runtime>(
+    app: &AppHandle<R>,
+    client: &str,
+    usage: Option<&crate::core::models::TokenUsage>,
+    failed: bool,
+) {
+    if let Err(e) = super::record_usage(app, client, usage, failed) {
+        eprintln!("Failed to record gateway usage of {}: {}", client, e);
+    }
+}
+
+/// Models of every provider with credentials, as `<provider>/<model>`.
+async fn list_models<R: Runtime>(
+    State(app): State<AppHandle<R>>,
+    headers: HeaderMap,
+) -> Result<Json<Value>, GatewayError> {
+    authenticate(&app, &headers)?;
+    let registry = app.state::<ProviderRegistry>();
+
+    let mut data = Vec::new();
+    for provider in commands::get_supported_providers() {
+        let Ok(provider_impl) = llm_trait::get_provider(&registry, &provider) else {
+            continue;
+        };
+        if !provider_impl
+            .has_credentials(&app, None)
+            .await
+            .unwrap_or(false)
+        {
+            continue;
+        }
+
+        match provider_impl.list_models(&app, None).await {
+            Ok(models) => data.extend(models.iter().map(|model| {
+                json!({
+                    "id": openai::model_id(&provider, model),
+                    "object": "model",
+                    "created": 0,
+                    "owned_by": provider.as_str(),
+                })
+            })),
+            Err(e) => eprintln!("Gateway could not list {} models: {}", provider.as_str(), e),
+        }
+    }
+
+    Ok(Json(json!({ "object": "list", "data": data })))
+}
+
+async fn chat_completions<R: Runtime>(
+    State(app): State<AppHandle<R>>,
+    headers: HeaderMap,
+    body: String,
+) -> Result<Response, GatewayError> {
+    let client = authenticate(&app, &headers)?;
+
+    let completion: ChatCompletionRequest = serde_json::from_str(&body)
+        .map_err(|e| GatewayError::invalid_request(format!("Invalid request: {}", e)))?;
+    let (provider, model) =
+        openai::route_model(&completion.model).map_err(GatewayError::invalid_request)?;
+    let model_id = completion.model.clone();
+    let streaming = completion.stream;
+    let request = completion
+        .into_chat_request()
+        .map_err(GatewayError::invalid_request)?;
+
+    let provider_impl = llm_trait::get_provider(&app.state::<ProviderRegistry>(), &provider)
+        .map_err(|e| GatewayError::invalid_request(e.to_string()))?;
+
+    let (sink, receiver) = ChannelSink::new();
+    let events = StreamEmitter::new(Arc::new(sink), stream::new_request_id());
+    let id = format!("chatcmpl-{}", events.request_id());
+    let created = chrono::Utc::now().timestamp();
+
+    if !streaming {
+        let result = provider_impl
+            .send_message(events, &app, None, model, request)
+            .await;
+        record_usage(
+            &app,
+            &client,
+            result
+                .as_ref()
+                .ok()
+                .and_then(|response| response.usage.as_ref()),
+            result.is_err(),
+        );
+
+        let response = result?;
+        return Ok(Json(openai::completion(&id, &model_id, created, &response)).into_response());
+    }
+
+    events.start().map_err(AppError::from)?;
+    let generation_app = app.clone();
+    let (generation, abort_handle) = future::abortable(async move {
+        let result = provider_impl
+            .send_message(events.clone(), &generation_app, None, model, request)
+            .await;
+
+        match &result {
+            Ok(response) => {
+                if let Some(usage) = &response.usage {
+                    let _ = events.usage(usage);
+                }
+                let _ = events.stop(response.stop_reason.clone().unwrap_or(StopReason::EndTurn));
+            }
+            Err(e) => {
+                let _ = events.error(e);
+            }
+        }
+
+        record_usage(
+            &generation_app,
+            &client,
+            result
+                .as_ref()
+                .ok()
+                .and_then(|response| response.usage.as_ref()),
+            result.is_err(),
+        );
+    });
+    tauri::async_runtime::spawn(generation);
+
+    // The receiver closes once the generation dropped its emitter, then the
+    // stream ends with `[DONE]`. A client going away drops the stream and
+    // with it the guard, which aborts the generation.
+    let chunks = CompletionChunks::new(id, model_id, created);
+    let guard = AbortOnDrop(abort_handle);
+    let events =
+        futures_util::stream::unfold(Some((receiver, chunks, guard)), |state| async move {
+            let (mut receiver, mut chunks, guard) = state?;
+            while let Some(event) = receiver.recv().await {
+                if let Some(chunk) = chunks.chunk(event) {
+                    let event = Event::default().data(chunk.to_string());
+                    return Some((Ok::<_, Infallible>(event), Some((receiver, chunks, guard))));
+                }
+            }
+            Some((Ok(Event::default().data("[DONE]")), None))
+        });
+
+    Ok(Sse::new(events).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::context::ProviderContext;
+    use crate::core::endpoints::EndpointOverrides;
+    use crate::core::retry::RetryPolicy;
+    use crate::core::sink::CollectorSink;
+    use crate::core::tools::ToolRegistry;
+    use async_trait::async_trait;
+    use std::collections::HashMap;
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    const ANTHROPIC_TEXT: &str = include_str!("../providers/fixtures/anthropic_text.sse");
+
+    /// Anthropic credentials and base URL pointing at the mock server.
+    struct MockContext {
+        endpoints: EndpointOverrides,
+    }
+
+    #[async_trait]
+    impl ProviderContext for MockContext {
+        async fn raw_credentials(
+            &self,
+            _provider_id: &str,
+            _profile: Option<&str>,
+        ) -> Result<Value, String> {
+            Ok(json!({ "api_key": "test-key" }))
+        }
+
+        fn endpoint_params(&self, provider_id: &str) -> Result<HashMap<String, String>, String> {
+            self.endpoints.endpoint_params(provider_id)
+        }
+
+        fn client(&self) -> Result<reqwest::Client, String> {
+            Ok(reqwest::Client::new())
+        }
+
+        fn retry_policy(&self) -> RetryPolicy {
+            RetryPolicy {
+                max_attempts: 1,
+                ..RetryPolicy::default()
+            }
+        }
+
+        fn tools(&self) -> ToolRegistry {
+            ToolRegistry::new()
+        }
+    }
+
+    #[tokio::test]
+    async fn forwards_the_routed_model_to_anthropic() {
+        let server = MockServer::start().await;
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(body_partial_json(
+                json!({ "model": "claude-3-7-sonnet-20250219" }),
+            ))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(ANTHROPIC_TEXT, "text/event-stream"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+        let context = MockContext {
+            endpoints: EndpointOverrides::new().with_base_url("anthropic", &server.uri()),
+        };
+
+        let completion: ChatCompletionRequest = serde_json::from_value(json!({
+            "model": "anthropic/claude-3-7-sonnet-20250219",
+            "messages": [{ "role": "user", "content": "Ça va ?" }],
+            "stream": true,
+        }))
+        .unwrap();
+        let (provider, model) = openai::route_model(&completion.model).unwrap();
+        let request = completion.into_chat_request().unwrap();
+        let events = StreamEmitter::new(Arc::new(CollectorSink::new()), "req-gateway".to_string());
+
+        let response = llm_trait::get_provider(&ProviderRegistry::with_defaults(), &provider)
+            .unwrap()
+            .send_message(events, &context, None, model, request)
+            .await
+            .unwrap();
+
+        assert_eq!(response.response, "Bonjour, ça va ?");
+    }
+}
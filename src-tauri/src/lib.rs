@@ -1,10 +1,20 @@
 mod api;
-mod core;
-mod db;
-mod providers;
-mod utils;
+pub mod core;
+pub mod db;
+mod gateway;
+pub mod providers;
+pub mod utils;
 
 use api::commands;
+use core::credentials;
+use core::endpoints::EndpointOverrides;
+use core::llm_trait::ProviderRegistry;
+use core::network::{self, HttpClient};
+use core::retry::AppRetryPolicy;
+use core::stream::GenerationRegistry;
+use core::tools::ToolRegistry;
+use core::vault::{self, CredentialVault};
+use gateway::Gateway;
 use tauri::Manager;
 use tauri_plugin_store::StoreExt;
 
@@ -28,15 +38,78 @@ pub fn run() {
             app.store("credentials.json")
                 .expect("Failed to create store");
 
+            match CredentialVault::load(app.handle()) {
+                Ok(credential_vault) => {
+                    if let Ok(key) = credential_vault.key() {
+                        match vault::migrate_legacy_credentials(app.handle(), &key) {
+                            Ok(0) => {}
+                            Ok(count) => println!("Re-encrypted {} legacy credentials", count),
+                            Err(e) => eprintln!("Failed to migrate credentials: {}", e),
+                        }
+                        match credentials::migrate_to_profiles(app.handle(), &key) {
+                            Ok(0) => {}
+                            Ok(count) => println!("Moved {} credentials to default profiles", count),
+                            Err(e) => eprintln!("Failed to migrate credential profiles: {}", e),
+                        }
+                    }
+                    app.manage(credential_vault);
+                }
+                Err(e) => {
+                    eprintln!("Failed to load credential vault: {}", e);
+                }
+            }
+
+            app.manage(ProviderRegistry::with_defaults());
+            app.manage(ToolRegistry::with_defaults());
+            app.manage(GenerationRegistry::new());
+            app.manage(EndpointOverrides::from_env());
+
+            let network_settings = network::load_settings(app.handle()).unwrap_or_else(|e| {
+                eprintln!("Failed to load network settings: {}", e);
+                Default::default()
+            });
+            match HttpClient::new(&network_settings) {
+                Ok(http_client) => {
+                    app.manage(http_client);
+                }
+                Err(e) => eprintln!("Failed to configure HTTP client: {}", e),
+            }
+            app.manage(AppRetryPolicy::new(network_settings.retry_policy()));
+
+            app.manage(Gateway::default());
+            if let Err(e) = tauri::async_runtime::block_on(gateway::apply_settings(app.handle())) {
+                eprintln!("Failed to start gateway: {}", e);
+            }
+
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::send_message,
+            commands::send_message_for_topic,
+            commands::regenerate_message,
+            commands::cancel_generation,
             commands::list_models,
             commands::get_model_details,
             commands::has_credentials,
+            commands::validate_credentials,
             commands::save_credentials,
+            commands::list_credential_profiles,
+            commands::create_credential_profile,
+            commands::rename_credential_profile,
+            commands::delete_credential_profile,
+            commands::set_default_credential_profile,
+            commands::is_credentials_locked,
+            commands::unlock_credentials,
+            commands::set_credentials_passphrase,
             commands::get_supported_providers,
+            commands::list_tools,
+            commands::get_network_settings,
+            commands::save_network_settings,
+            commands::get_gateway_settings,
+            commands::save_gateway_settings,
+            commands::list_gateway_clients,
+            commands::create_gateway_client,
+            commands::delete_gateway_client,
             db::topics::get_all_topics,
             db::topics::get_messages_by_topic,
             db::topics::add_topic,
@@ -48,6 +121,15 @@ pub fn run() {
             db::topics::update_topic_access,
             db::topics::add_settings,
             db::topics::get_settings,
+            db::search::search_messages,
+            db::export::export_topic,
+            db::export::import_topic,
+            db::backup::backup_database,
+            db::backup::restore_database,
+            db::backup::check_database,
+            db::branches::fork_message,
+            db::branches::list_sibling_branches,
+            db::branches::switch_branch,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
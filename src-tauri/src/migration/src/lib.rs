@@ -10,6 +10,10 @@ mod m20250330_100443_create_thinking;
 mod m20250330_100509_create_tool_properties;
 mod m20250330_100533_create_tool_required_props;
 mod m20250330_100601_create_tools;
+mod m20250412_093015_create_message_blocks;
+mod m20250419_181204_add_credential_profile_to_models_settings;
+mod m20250426_101500_create_messages_fts;
+mod m20250427_184210_add_message_branches;
 
 pub struct Migrator;
 
@@ -27,6 +31,10 @@ impl MigratorTrait for Migrator {
             Box::new(m20250330_100509_create_tool_properties::Migration),
             Box::new(m20250330_100533_create_tool_required_props::Migration),
             Box::new(m20250330_100601_create_tools::Migration),
+            Box::new(m20250412_093015_create_message_blocks::Migration),
+            Box::new(m20250419_181204_add_credential_profile_to_models_settings::Migration),
+            Box::new(m20250426_101500_create_messages_fts::Migration),
+            Box::new(m20250427_184210_add_message_branches::Migration),
         ]
     }
 }
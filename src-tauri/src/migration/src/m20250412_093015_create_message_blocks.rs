@@ -0,0 +1,79 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .create_table(
+                Table::create()
+                    .table(MessageBlocks::Table)
+                    .if_not_exists()
+                    .col(pk_auto(MessageBlocks::Id))
+                    .col(integer(MessageBlocks::MessageId).not_null())
+                    .col(integer(MessageBlocks::Position).not_null())
+                    .col(string(MessageBlocks::BlockType).not_null())
+                    .col(text_null(MessageBlocks::Text))
+                    .col(string_null(MessageBlocks::SourceType))
+                    .col(string_null(MessageBlocks::MediaType))
+                    .col(text_null(MessageBlocks::Data))
+                    .col(text_null(MessageBlocks::Signature))
+                    .col(string_null(MessageBlocks::ToolUseId))
+                    .col(string_null(MessageBlocks::ToolName))
+                    .col(text_null(MessageBlocks::ToolInput))
+                    .col(boolean_null(MessageBlocks::IsError))
+                    .foreign_key(
+                        ForeignKey::create()
+                            .name("fk_message_blocks_message_id")
+                            .from(MessageBlocks::Table, MessageBlocks::MessageId)
+                            .to(Messages::Table, Messages::Id)
+                            .on_delete(ForeignKeyAction::Cascade),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_message_blocks_message_id")
+                    .table(MessageBlocks::Table)
+                    .col(MessageBlocks::MessageId)
+                    .if_not_exists()
+                    .to_owned(),
+            )
+            .await
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_table(Table::drop().table(MessageBlocks::Table).to_owned())
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum MessageBlocks {
+    Table,
+    Id,
+    MessageId,
+    Position,
+    BlockType,
+    Text,
+    SourceType,
+    MediaType,
+    Data,
+    Signature,
+    ToolUseId,
+    ToolName,
+    ToolInput,
+    IsError,
+}
+
+#[derive(DeriveIden)]
+enum Messages {
+    Table,
+    Id,
+}
@@ -0,0 +1,59 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// FTS5 index over message content and the name of their topic, keyed by
+/// message id. Triggers keep it in sync, so the app never writes to it.
+const UP: &str = r#"
+CREATE VIRTUAL TABLE IF NOT EXISTS messages_fts USING fts5(
+    content,
+    topic_name,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO messages_fts (rowid, content, topic_name)
+SELECT messages.id, messages.content, topics.name
+FROM messages JOIN topics ON topics.id = messages.topic_id;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_insert AFTER INSERT ON messages BEGIN
+    INSERT INTO messages_fts (rowid, content, topic_name)
+    VALUES (new.id, new.content, (SELECT name FROM topics WHERE id = new.topic_id));
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_update AFTER UPDATE OF content, topic_id ON messages BEGIN
+    DELETE FROM messages_fts WHERE rowid = old.id;
+    INSERT INTO messages_fts (rowid, content, topic_name)
+    VALUES (new.id, new.content, (SELECT name FROM topics WHERE id = new.topic_id));
+END;
+
+CREATE TRIGGER IF NOT EXISTS messages_fts_delete AFTER DELETE ON messages BEGIN
+    DELETE FROM messages_fts WHERE rowid = old.id;
+END;
+
+CREATE TRIGGER IF NOT EXISTS topics_fts_rename AFTER UPDATE OF name ON topics BEGIN
+    UPDATE messages_fts SET topic_name = new.name
+    WHERE rowid IN (SELECT id FROM messages WHERE topic_id = new.id);
+END;
+"#;
+
+const DOWN: &str = r#"
+DROP TRIGGER IF EXISTS topics_fts_rename;
+DROP TRIGGER IF EXISTS messages_fts_delete;
+DROP TRIGGER IF EXISTS messages_fts_update;
+DROP TRIGGER IF EXISTS messages_fts_insert;
+DROP TABLE IF EXISTS messages_fts;
+"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(UP).await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager.get_connection().execute_unprepared(DOWN).await?;
+        Ok(())
+    }
+}
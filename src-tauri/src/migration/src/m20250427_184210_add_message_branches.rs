@@ -0,0 +1,98 @@
+use sea_orm_migration::{prelude::*, schema::*};
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+/// Existing topics become a single branch: every message is the child of
+/// the one inserted before it, and the last one is the active leaf.
+const BACKFILL: &str = r#"
+UPDATE messages SET parent_id = (
+    SELECT MAX(previous.id) FROM messages AS previous
+    WHERE previous.topic_id = messages.topic_id AND previous.id < messages.id
+);
+
+UPDATE topics SET active_leaf_id = (
+    SELECT MAX(messages.id) FROM messages WHERE messages.topic_id = topics.id
+);
+"#;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .add_column(integer_null(Messages::ParentId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Topics::Table)
+                    .add_column(integer_null(Topics::ActiveLeafId))
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .create_index(
+                Index::create()
+                    .name("idx_messages_parent_id")
+                    .table(Messages::Table)
+                    .col(Messages::ParentId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .get_connection()
+            .execute_unprepared(BACKFILL)
+            .await?;
+
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .drop_index(
+                Index::drop()
+                    .name("idx_messages_parent_id")
+                    .table(Messages::Table)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Topics::Table)
+                    .drop_column(Topics::ActiveLeafId)
+                    .to_owned(),
+            )
+            .await?;
+
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(Messages::Table)
+                    .drop_column(Messages::ParentId)
+                    .to_owned(),
+            )
+            .await
+    }
+}
+
+#[derive(DeriveIden)]
+enum Messages {
+    Table,
+    ParentId,
+}
+
+#[derive(DeriveIden)]
+enum Topics {
+    Table,
+    ActiveLeafId,
+}
@@ -1,16 +1,21 @@
-use crate::core::credentials::{self, AnthropicCredentials};
+use crate::core::context::ProviderContext;
+use crate::core::credentials::AnthropicCredentials;
 use crate::core::endpoints;
+use crate::core::error::AppError;
 use crate::core::llm_trait::{AnthropicAdapter, LLMProvider};
 use crate::core::models::{
     BaseModelSettings, ChatRequest, ChatRole, ContentItem, ContentType, ProviderCapabilities,
     ProviderType, StreamResponse, TokenUsage,
 };
+use crate::core::retry::{Retry, RetryPolicy};
+use crate::core::sse::{self, SseEvent};
+use crate::core::stream::{self, StreamEmitter};
+use crate::core::tools::{ToolCallEvent, ToolRegistry, MAX_TOOL_ITERATIONS};
+use crate::core::validation::{self, CredentialValidation};
+use async_trait::async_trait;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::Arc;
-use tauri::Emitter;
-use tauri::Window;
+use std::collections::{BTreeMap, HashMap};
 use ts_rs::TS;
 
 #[derive(Debug, Clone)]
@@ -33,6 +38,27 @@ pub enum ContentBlock {
 
     #[serde(rename = "image")]
     Image { source: AnthropicImageSource },
+
+    #[serde(rename = "thinking")]
+    Thinking { thinking: String, signature: String },
+
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+
+    #[serde(rename = "tool_use")]
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+
+    #[serde(rename = "tool_result")]
+    ToolResult {
+        tool_use_id: String,
+        content: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        is_error: Option<bool>,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, TS)]
@@ -294,6 +320,12 @@ pub enum AnthropicSystemPrompt {
     Structured(SystemPromptData),
 }
 
+impl Default for AnthropicSystemPrompt {
+    fn default() -> Self {
+        AnthropicSystemPrompt::Simple(String::new())
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AnthropicChatRequest {
     pub model: Option<String>,
@@ -332,7 +364,21 @@ pub enum ResponseContentBlock {
     Text { text: String },
 
     #[serde(rename = "thinking")]
-    Thinking { thinking: String },
+    Thinking {
+        thinking: String,
+        #[serde(default)]
+        signature: String,
+    },
+
+    #[serde(rename = "redacted_thinking")]
+    RedactedThinking { data: String },
+
+    #[serde(rename = "tool_use")]
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -346,9 +392,12 @@ pub enum DeltaType {
 
     #[serde(rename = "signature_delta")]
     SignatureDelta { signature: String },
+
+    #[serde(rename = "input_json_delta")]
+    InputJsonDelta { partial_json: String },
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum StopReason {
     EndTurn,
@@ -357,6 +406,17 @@ pub enum StopReason {
     ToolUse,
 }
 
+impl From<StopReason> for stream::StopReason {
+    fn from(reason: StopReason) -> Self {
+        match reason {
+            StopReason::EndTurn => stream::StopReason::EndTurn,
+            StopReason::MaxTokens => stream::StopReason::MaxTokens,
+            StopReason::StopSequence => stream::StopReason::StopSequence,
+            StopReason::ToolUse => stream::StopReason::ToolUse,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Usage {
     pub input_tokens: u32,
@@ -519,7 +579,7 @@ pub struct AnthropicResponse {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
-pub enum StreamEvent {
+pub enum AnthropicStreamEvent {
     #[serde(rename = "message_start")]
     MessageStart { message: AnthropicResponse },
 
@@ -561,13 +621,41 @@ pub struct MessageDelta {
 //     pub data: String,
 // }
 
+#[derive(Debug, Clone)]
+enum StreamedBlock {
+    Text(String),
+    Thinking {
+        thinking: String,
+        signature: String,
+    },
+    RedactedThinking(String),
+    ToolUse {
+        id: String,
+        name: String,
+        partial_json: String,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct ToolUseRequest {
+    pub id: String,
+    pub name: String,
+    pub input: serde_json::Value,
+}
+
 #[derive(Debug, Clone, Default)]
-struct AnthropicStreamProcessor {
+pub struct AnthropicStreamProcessor {
     current_content: String,
     thinking_content: Option<String>,
+    pending_text: String,
+    pending_thinking: String,
     is_complete: bool,
     usage: Option<AnthropicUsage>,
+    usage_updated: bool,
     last_output_tokens: Option<u32>,
+    blocks: BTreeMap<usize, StreamedBlock>,
+    stop_reason: Option<StopReason>,
+    error: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -586,84 +674,223 @@ impl AnthropicStreamProcessor {
         Self {
             current_content: String::new(),
             thinking_content: None,
+            pending_text: String::new(),
+            pending_thinking: String::new(),
             is_complete: false,
             usage: None,
+            usage_updated: false,
             last_output_tokens: None,
+            blocks: BTreeMap::new(),
+            stop_reason: None,
+            error: None,
         }
     }
 
-    pub fn process_sse_line(&mut self, line: &str) -> Result<bool, String> {
-        if line.is_empty() || line.starts_with(":") {
-            return Ok(false);
+    fn push_text(&mut self, text: &str) {
+        self.current_content.push_str(text);
+        self.pending_text.push_str(text);
+    }
+
+    fn push_thinking(&mut self, thinking: &str) {
+        self.thinking_content
+            .get_or_insert_with(String::new)
+            .push_str(thinking);
+        self.pending_thinking.push_str(thinking);
+    }
+
+    /// Text received since the previous call.
+    pub fn take_text_delta(&mut self) -> String {
+        std::mem::take(&mut self.pending_text)
+    }
+
+    /// Thinking received since the previous call.
+    pub fn take_thinking_delta(&mut self) -> String {
+        std::mem::take(&mut self.pending_thinking)
+    }
+
+    /// Usage so far, if it changed since the previous call.
+    pub fn take_usage_update(&mut self) -> Option<AnthropicUsage> {
+        if std::mem::take(&mut self.usage_updated) {
+            self.get_usage()
+        } else {
+            None
         }
+    }
 
-        if line.starts_with("event: ") {
+    /// Applies one server-sent event, returning true once the message is
+    /// complete or failed.
+    pub fn process_event(&mut self, event: &SseEvent) -> Result<bool, String> {
+        if event.event == "ping" {
             return Ok(false);
         }
 
-        if line.starts_with("data: ") {
-            let data = line.strip_prefix("data: ").unwrap();
-
-            if data.contains("\"type\": \"ping\"") || data.contains("\"type\":\"ping\"") {
-                return Ok(false);
-            }
-
-            match serde_json::from_str::<serde_json::Value>(data) {
-                Ok(json) => {
-                    if let Some(event_type) = json.get("type").and_then(|t| t.as_str()) {
-                        match event_type {
-                            "message_start" => {
-                                if let Some(message_json) = json.get("message") {
-                                    if let Some(usage_json) = message_json.get("usage") {
-                                        if let Ok(usage) =
-                                            serde_json::from_value(usage_json.clone())
-                                        {
-                                            self.usage = Some(usage);
-                                        }
+        let data = event.data.as_str();
+
+        match serde_json::from_str::<serde_json::Value>(data) {
+            Ok(json) => {
+                if let Some(event_type) = json.get("type").and_then(|t| t.as_str()) {
+                    match event_type {
+                        "message_start" => {
+                            if let Some(message_json) = json.get("message") {
+                                if let Some(usage_json) = message_json.get("usage") {
+                                    if let Ok(usage) = serde_json::from_value(usage_json.clone()) {
+                                        self.usage = Some(usage);
+                                        self.usage_updated = true;
                                     }
                                 }
                             }
-                            "content_block_delta" => {
-                                if let Some(delta) = json
-                                    .get("delta")
-                                    .and_then(|d| d.get("text"))
-                                    .and_then(|t| t.as_str())
-                                {
-                                    self.current_content.push_str(delta);
+                        }
+                        "content_block_start" => {
+                            let index = json.get("index").and_then(|i| i.as_u64());
+                            let block = json.get("content_block").and_then(|b| {
+                                serde_json::from_value::<ResponseContentBlock>(b.clone()).ok()
+                            });
+
+                            match (index, block) {
+                                (Some(index), Some(ResponseContentBlock::Text { text })) => {
+                                    self.push_text(&text);
+                                    self.blocks
+                                        .insert(index as usize, StreamedBlock::Text(text));
+                                }
+                                (
+                                    Some(index),
+                                    Some(ResponseContentBlock::Thinking {
+                                        thinking,
+                                        signature,
+                                    }),
+                                ) => {
+                                    self.push_thinking(&thinking);
+                                    self.blocks.insert(
+                                        index as usize,
+                                        StreamedBlock::Thinking {
+                                            thinking,
+                                            signature,
+                                        },
+                                    );
+                                }
+                                (
+                                    Some(index),
+                                    Some(ResponseContentBlock::RedactedThinking { data }),
+                                ) => {
+                                    self.blocks.insert(
+                                        index as usize,
+                                        StreamedBlock::RedactedThinking(data),
+                                    );
+                                }
+                                (
+                                    Some(index),
+                                    Some(ResponseContentBlock::ToolUse { id, name, .. }),
+                                ) => {
+                                    self.blocks.insert(
+                                        index as usize,
+                                        StreamedBlock::ToolUse {
+                                            id,
+                                            name,
+                                            partial_json: String::new(),
+                                        },
+                                    );
                                 }
+                                _ => {}
                             }
-                            "message_delta" => {
-                                if let Some(usage_json) = json.get("usage") {
-                                    if let Some(output_tokens) =
-                                        usage_json.get("output_tokens").and_then(|t| t.as_u64())
+                        }
+                        "content_block_delta" => {
+                            let index =
+                                json.get("index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+                            let delta = json
+                                .get("delta")
+                                .and_then(|d| serde_json::from_value::<DeltaType>(d.clone()).ok());
+
+                            match delta {
+                                Some(DeltaType::TextDelta { text }) => {
+                                    self.push_text(&text);
+                                    if let StreamedBlock::Text(block_text) = self
+                                        .blocks
+                                        .entry(index)
+                                        .or_insert_with(|| StreamedBlock::Text(String::new()))
                                     {
-                                        self.last_output_tokens = Some(output_tokens as u32);
+                                        block_text.push_str(&text);
                                     }
                                 }
-                            }
-                            "message_stop" => {
-                                self.is_complete = true;
-                                if let Some(tokens) = self.last_output_tokens {
-                                    if let Some(usage) = &mut self.usage {
-                                        usage.output_tokens = tokens;
-                                    } else if tokens > 0 {
-                                        self.usage = Some(AnthropicUsage {
-                                            input_tokens: 0,
-                                            output_tokens: tokens,
-                                            cache_creation_input_tokens: 0,
-                                            cache_read_input_tokens: 0,
-                                        });
+                                Some(DeltaType::ThinkingDelta { thinking }) => {
+                                    self.push_thinking(&thinking);
+                                    if let StreamedBlock::Thinking {
+                                        thinking: block_thinking,
+                                        ..
+                                    } = self.blocks.entry(index).or_insert_with(|| {
+                                        StreamedBlock::Thinking {
+                                            thinking: String::new(),
+                                            signature: String::new(),
+                                        }
+                                    }) {
+                                        block_thinking.push_str(&thinking);
+                                    }
+                                }
+                                Some(DeltaType::SignatureDelta { signature }) => {
+                                    if let Some(StreamedBlock::Thinking {
+                                        signature: block_signature,
+                                        ..
+                                    }) = self.blocks.get_mut(&index)
+                                    {
+                                        block_signature.push_str(&signature);
+                                    }
+                                }
+                                Some(DeltaType::InputJsonDelta {
+                                    partial_json: fragment,
+                                }) => {
+                                    if let Some(StreamedBlock::ToolUse { partial_json, .. }) =
+                                        self.blocks.get_mut(&index)
+                                    {
+                                        partial_json.push_str(&fragment);
                                     }
                                 }
-                                return Ok(true);
+                                _ => {}
+                            }
+                        }
+                        "message_delta" => {
+                            if let Some(stop_reason) = json
+                                .get("delta")
+                                .and_then(|d| d.get("stop_reason"))
+                                .and_then(|r| serde_json::from_value(r.clone()).ok())
+                            {
+                                self.stop_reason = Some(stop_reason);
+                            }
+
+                            if let Some(usage_json) = json.get("usage") {
+                                if let Some(output_tokens) =
+                                    usage_json.get("output_tokens").and_then(|t| t.as_u64())
+                                {
+                                    self.last_output_tokens = Some(output_tokens as u32);
+                                    self.usage_updated = true;
+                                }
                             }
-                            _ => {}
                         }
+                        "error" => {
+                            self.error = json.get("error").cloned();
+                            self.is_complete = true;
+                            return Ok(true);
+                        }
+                        "message_stop" => {
+                            self.is_complete = true;
+                            if let Some(tokens) = self.last_output_tokens {
+                                if let Some(usage) = &mut self.usage {
+                                    usage.output_tokens = tokens;
+                                } else if tokens > 0 {
+                                    self.usage = Some(AnthropicUsage {
+                                        input_tokens: 0,
+                                        output_tokens: tokens,
+                                        cache_creation_input_tokens: 0,
+                                        cache_read_input_tokens: 0,
+                                    });
+                                }
+                            }
+                            return Ok(true);
+                        }
+                        _ => {}
                     }
                 }
-                Err(e) => {
-                    println!("Error parsing SSE data: {}. Raw data: {}", e, data);
-                }
+            }
+            Err(e) => {
+                println!("Error parsing SSE data: {}. Raw data: {}", e, data);
             }
         }
 
@@ -678,21 +905,78 @@ impl AnthropicStreamProcessor {
         self.thinking_content.clone()
     }
 
+    pub fn get_stop_reason(&self) -> Option<StopReason> {
+        self.stop_reason.clone()
+    }
+
+    pub fn get_tool_uses(&self) -> Result<Vec<ToolUseRequest>, String> {
+        self.blocks
+            .values()
+            .filter_map(|block| match block {
+                StreamedBlock::ToolUse {
+                    id,
+                    name,
+                    partial_json,
+                } => Some(parse_tool_input(partial_json).map(|input| ToolUseRequest {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input,
+                })),
+                _ => None,
+            })
+            .collect()
+    }
+
     pub fn get_message(&self) -> Option<AnthropicMessage> {
         if self.is_complete || !self.current_content.is_empty() {
-            let content = vec![ContentBlock::Text {
-                text: self.current_content.clone(),
-            }];
-
             Some(AnthropicMessage {
                 role: AnthropicMessageRole::Assistant,
-                content: MessageContent::Blocks(content),
+                content: MessageContent::Blocks(convert_content_to_anthropic_content_blocks(
+                    &ContentType::StructuredContent(self.get_content_items()),
+                )),
             })
         } else {
             None
         }
     }
 
+    /// Content blocks of the streamed message in index order, thinking
+    /// signatures included so they can be sent back on the next turn.
+    pub fn get_content_items(&self) -> Vec<ContentItem> {
+        if self.blocks.is_empty() {
+            return vec![ContentItem::Text {
+                text: self.current_content.clone(),
+            }];
+        }
+
+        self.blocks
+            .values()
+            .filter_map(|block| match block {
+                StreamedBlock::Text(text) if text.is_empty() => None,
+                StreamedBlock::Text(text) => Some(ContentItem::Text { text: text.clone() }),
+                StreamedBlock::Thinking {
+                    thinking,
+                    signature,
+                } => Some(ContentItem::Thinking {
+                    thinking: thinking.clone(),
+                    signature: signature.clone(),
+                }),
+                StreamedBlock::RedactedThinking(data) => {
+                    Some(ContentItem::RedactedThinking { data: data.clone() })
+                }
+                StreamedBlock::ToolUse {
+                    id,
+                    name,
+                    partial_json,
+                } => Some(ContentItem::ToolUse {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: parse_tool_input(partial_json).unwrap_or_else(|_| serde_json::json!({})),
+                }),
+            })
+            .collect()
+    }
+
     pub fn get_usage(&self) -> Option<AnthropicUsage> {
         if let (Some(mut usage), Some(tokens)) = (self.usage.clone(), self.last_output_tokens) {
             usage.output_tokens = tokens;
@@ -703,21 +987,21 @@ impl AnthropicStreamProcessor {
     }
 }
 
-struct AnthropicProviderWrapper(crate::providers::anthropic::AnthropicProvider);
-
-impl LLMProvider for AnthropicProviderWrapper {
-    fn provider_id(&self) -> &'static str {
-        "anthropic"
+fn parse_tool_input(partial_json: &str) -> Result<serde_json::Value, String> {
+    if partial_json.trim().is_empty() {
+        return Ok(serde_json::json!({}));
     }
+
+    serde_json::from_str(partial_json).map_err(|e| format!("Invalid tool input JSON: {}", e))
 }
 
-impl AnthropicAdapter for AnthropicProviderWrapper {
+impl AnthropicAdapter for AnthropicProvider {
     fn adapt_request(&self, request: ChatRequest) -> AnthropicChatRequest {
         let anthropic_messages: Vec<AnthropicMessage> = request
             .messages
             .iter()
             .filter(|msg| !matches!(msg.role, ChatRole::System))
-            .map(|msg| create_anthropic_message(msg.role.clone(), &msg.content))
+            .flat_map(|msg| create_anthropic_messages(msg.role.clone(), &msg.content))
             .collect();
 
         AnthropicChatRequest {
@@ -753,7 +1037,7 @@ impl AnthropicAdapter for AnthropicProviderWrapper {
             .join("");
 
         let thinking_content = response.content.iter().find_map(|block| {
-            if let ResponseContentBlock::Thinking { thinking } = block {
+            if let ResponseContentBlock::Thinking { thinking, .. } = block {
                 Some(thinking.clone())
             } else {
                 None
@@ -770,6 +1054,8 @@ impl AnthropicAdapter for AnthropicProviderWrapper {
             response: text_content,
             usage,
             thinking: thinking_content,
+            content: None,
+            stop_reason: response.stop_reason.map(Into::into),
         }
     }
 }
@@ -790,11 +1076,83 @@ fn convert_content_to_anthropic_content_blocks(content: &ContentType) -> Vec<Con
                         data: source.data.clone(),
                     },
                 },
+                ContentItem::Thinking {
+                    thinking,
+                    signature,
+                } => ContentBlock::Thinking {
+                    thinking: thinking.clone(),
+                    signature: signature.clone(),
+                },
+                ContentItem::RedactedThinking { data } => {
+                    ContentBlock::RedactedThinking { data: data.clone() }
+                }
+                ContentItem::ToolUse { id, name, input } => ContentBlock::ToolUse {
+                    id: id.clone(),
+                    name: name.clone(),
+                    input: input.clone(),
+                },
+                ContentItem::ToolResult {
+                    tool_use_id,
+                    content,
+                    is_error,
+                } => ContentBlock::ToolResult {
+                    tool_use_id: tool_use_id.clone(),
+                    content: content.clone(),
+                    is_error: *is_error,
+                },
             })
             .collect(),
     }
 }
 
+/// A stored assistant reply from a tool loop holds its `tool_result` blocks
+/// inline; Anthropic expects them in user turns, so split them back out.
+fn create_anthropic_messages(role: ChatRole, content: &ContentType) -> Vec<AnthropicMessage> {
+    let items = match (&role, content) {
+        (ChatRole::Assistant, ContentType::StructuredContent(items))
+            if items
+                .iter()
+                .any(|item| matches!(item, ContentItem::ToolResult { .. })) =>
+        {
+            items
+        }
+        _ => return vec![create_anthropic_message(role, content)],
+    };
+
+    let turn_role = |is_tool_result: bool| {
+        if is_tool_result {
+            ChatRole::User
+        } else {
+            ChatRole::Assistant
+        }
+    };
+
+    let mut messages = Vec::new();
+    let mut turn: Vec<ContentItem> = Vec::new();
+    let mut turn_is_tool_result = false;
+
+    for item in items {
+        let is_tool_result = matches!(item, ContentItem::ToolResult { .. });
+        if is_tool_result != turn_is_tool_result && !turn.is_empty() {
+            messages.push(create_anthropic_message(
+                turn_role(turn_is_tool_result),
+                &ContentType::StructuredContent(std::mem::take(&mut turn)),
+            ));
+        }
+        turn_is_tool_result = is_tool_result;
+        turn.push(item.clone());
+    }
+
+    if !turn.is_empty() {
+        messages.push(create_anthropic_message(
+            turn_role(turn_is_tool_result),
+            &ContentType::StructuredContent(turn),
+        ));
+    }
+
+    messages
+}
+
 fn create_anthropic_message(role: ChatRole, content: &ContentType) -> AnthropicMessage {
     AnthropicMessage {
         role: match role {
@@ -830,212 +1188,771 @@ fn convert_media_type(media_type: &str) -> ImageMediaType {
     }
 }
 
-impl AnthropicProvider {
-    pub fn send_message_impl<R: tauri::Runtime>(
-        &self,
-        window: Window<R>,
-        app: Arc<tauri::AppHandle<R>>,
-        _model: String,
-        request: ChatRequest,
-    ) -> tauri::async_runtime::JoinHandle<Result<StreamResponse, String>> {
-        let wrapper = AnthropicProviderWrapper(self.clone());
-        let anthropic_request = wrapper.adapt_request(request);
-
-        tauri::async_runtime::spawn(async move {
-            let AnthropicCredentials { api_key } =
-                credentials::get_anthropic_credentials(&app).await?;
-
-            let api_url = endpoints::get_api_url("anthropic", None)?;
-
-            let client = reqwest::Client::new();
-
-            let response = client
+#[allow(clippy::too_many_arguments)]
+pub async fn stream_message<T, K, U>(
+    client: &reqwest::Client,
+    api_url: &str,
+    api_key: &str,
+    request: &AnthropicChatRequest,
+    retry: &Retry<'_>,
+    mut on_text: T,
+    mut on_thinking: K,
+    mut on_usage: U,
+) -> Result<AnthropicStreamProcessor, AppError>
+where
+    T: FnMut(&str) + Send,
+    K: FnMut(&str) + Send,
+    U: FnMut(&AnthropicUsage) + Send,
+{
+    let response = retry
+        .send("anthropic", || {
+            client
                 .post(api_url)
                 .header("x-api-key", api_key)
                 .header("anthropic-version", "2023-06-01")
                 .header("Content-Type", "application/json")
-                .json(&anthropic_request)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if response.status().is_client_error() || response.status().is_server_error() {
-                let error_body = response.text().await.map_err(|e| e.to_string())?;
-                return Err(format!("Anthropic API Error: {}", error_body));
-            }
-
-            let mut stream_processor = AnthropicStreamProcessor::new();
-            let mut buffer = String::new();
-            let mut stream = response.bytes_stream();
-            let mut stream_completed = false;
-
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| e.to_string())?;
-                let chunk_str = String::from_utf8(chunk.to_vec()).map_err(|e| e.to_string())?;
-
-                buffer.push_str(&chunk_str);
-
-                let mut processed_up_to = 0;
-                let mut lines_start = 0;
+                .json(request)
+        })
+        .await?;
 
-                while let Some(pos) = buffer[lines_start..].find('\n') {
-                    let line_end = lines_start + pos;
-                    let line = buffer[lines_start..line_end].trim().to_string();
+    let mut stream_processor = AnthropicStreamProcessor::new();
+    let mut events = Box::pin(sse::sse_stream(response.bytes_stream()));
 
-                    if !line.is_empty() {
-                        match stream_processor.process_sse_line(&line) {
-                            Ok(is_complete) => {
-                                let current_text = stream_processor.get_text_content();
-                                if !current_text.is_empty() {
-                                    let _ = window.emit("stream-response", &current_text);
-                                }
+    while let Some(event) = events.next().await {
+        let event = event.map_err(|e| AppError::network("anthropic", e))?;
 
-                                if is_complete {
-                                    stream_completed = true;
-                                }
-                            }
-                            Err(e) => {
-                                println!("Error processing line: {}", e);
-                            }
-                        }
-                    }
+        match stream_processor.process_event(&event) {
+            Ok(is_complete) => {
+                let thinking = stream_processor.take_thinking_delta();
+                if !thinking.is_empty() {
+                    on_thinking(&thinking);
+                }
 
-                    lines_start = line_end + 1;
-                    processed_up_to = lines_start;
+                let text = stream_processor.take_text_delta();
+                if !text.is_empty() {
+                    on_text(&text);
                 }
 
-                if processed_up_to > 0 {
-                    buffer = buffer[processed_up_to..].to_string();
+                if let Some(usage) = stream_processor.take_usage_update() {
+                    on_usage(&usage);
                 }
 
-                if stream_completed {
+                if is_complete {
                     break;
                 }
             }
+            Err(e) => {
+                println!("Error processing event: {}", e);
+            }
+        }
+    }
 
-            if !buffer.trim().is_empty() {
-                let _ = stream_processor.process_sse_line(&buffer);
+    if let Some(error) = &stream_processor.error {
+        return Err(AppError::from_stream_error("anthropic", error));
+    }
 
-                let current_text = stream_processor.get_text_content();
-                if !current_text.is_empty() {
-                    let _ = window.emit("stream-response", &current_text);
-                }
+    Ok(stream_processor)
+}
+
+fn add_usage(total: Option<TokenUsage>, turn: &AnthropicUsage) -> TokenUsage {
+    let total = total.unwrap_or(TokenUsage {
+        prompt_tokens: 0,
+        completion_tokens: 0,
+        total_tokens: 0,
+    });
+
+    TokenUsage {
+        prompt_tokens: total.prompt_tokens + turn.input_tokens,
+        completion_tokens: total.completion_tokens + turn.output_tokens,
+        total_tokens: total.total_tokens + turn.input_tokens + turn.output_tokens,
+    }
+}
+
+/// Streams a message and, while Claude stops with `tool_use`, runs the requested
+/// tools locally and sends their results back, up to `MAX_TOOL_ITERATIONS` turns.
+#[allow(clippy::too_many_arguments)]
+pub async fn run_tool_loop<T, K, U, C>(
+    client: &reqwest::Client,
+    api_url: &str,
+    api_key: &str,
+    mut request: AnthropicChatRequest,
+    tools: &ToolRegistry,
+    retry: &Retry<'_>,
+    mut on_text: T,
+    mut on_thinking: K,
+    mut on_usage: U,
+    mut on_tool_call: C,
+) -> Result<StreamResponse, AppError>
+where
+    T: FnMut(&str) + Send,
+    K: FnMut(&str) + Send,
+    U: FnMut(&TokenUsage) + Send,
+    C: FnMut(&ToolCallEvent) + Send,
+{
+    let mut response_text = String::new();
+    let mut response_thinking: Option<String> = None;
+    let mut content: Vec<ContentItem> = Vec::new();
+    let mut usage: Option<TokenUsage> = None;
+
+    for iteration in 0..MAX_TOOL_ITERATIONS {
+        let previous_usage = usage.clone();
+        let stream_processor = stream_message(
+            client,
+            api_url,
+            api_key,
+            &request,
+            retry,
+            &mut on_text,
+            &mut on_thinking,
+            |turn_usage| on_usage(&add_usage(previous_usage.clone(), turn_usage)),
+        )
+        .await?;
+
+        response_text.push_str(&stream_processor.get_text_content());
+        if let Some(thinking) = stream_processor.get_thinking_content() {
+            response_thinking
+                .get_or_insert_with(String::new)
+                .push_str(&thinking);
+        }
+        content.extend(stream_processor.get_content_items());
+
+        if let Some(turn_usage) = stream_processor.get_usage() {
+            usage = Some(add_usage(usage, &turn_usage));
+        }
+
+        if stream_processor.get_stop_reason() != Some(StopReason::ToolUse) {
+            if stream_processor.get_message().is_none() && response_text.is_empty() {
+                return Err("Failed to get complete response from Anthropic API".into());
             }
 
-            let usage = stream_processor.get_usage().map(|u| TokenUsage {
-                prompt_tokens: u.input_tokens,
-                completion_tokens: u.output_tokens,
-                total_tokens: u.input_tokens + u.output_tokens,
+            return Ok(StreamResponse {
+                response: response_text,
+                usage,
+                thinking: response_thinking,
+                content: Some(content),
+                stop_reason: stream_processor.get_stop_reason().map(Into::into),
             });
+        }
 
-            if let Some(_message) = stream_processor.get_message() {
-                let final_text = stream_processor.get_text_content();
-
-                Ok(StreamResponse {
-                    response: final_text,
-                    usage,
-                    thinking: stream_processor.get_thinking_content(),
-                })
-            } else if !stream_processor.get_text_content().is_empty() {
-                let text = stream_processor.get_text_content();
-                Ok(StreamResponse {
-                    response: text,
-                    usage,
-                    thinking: stream_processor.get_thinking_content(),
-                })
-            } else {
-                Err("Failed to get complete response from Anthropic API".to_string())
-            }
-        })
+        let assistant_message = stream_processor
+            .get_message()
+            .ok_or("Anthropic stopped for tool use without a message")?;
+        request.messages.push(assistant_message);
+
+        let mut tool_results = Vec::new();
+
+        for tool_use in stream_processor.get_tool_uses()? {
+            let result = tools.execute(&tool_use.name, tool_use.input.clone()).await;
+            let is_error = result.is_err();
+            let output = result.unwrap_or_else(|e| e);
+
+            on_tool_call(&ToolCallEvent {
+                iteration,
+                tool_use_id: tool_use.id.clone(),
+                name: tool_use.name,
+                input: tool_use.input,
+                output: output.clone(),
+                is_error,
+            });
+
+            content.push(ContentItem::ToolResult {
+                tool_use_id: tool_use.id.clone(),
+                content: output.clone(),
+                is_error: is_error.then_some(true),
+            });
+
+            tool_results.push(ContentBlock::ToolResult {
+                tool_use_id: tool_use.id,
+                content: output,
+                is_error: is_error.then_some(true),
+            });
+        }
+
+        request.messages.push(AnthropicMessage {
+            role: AnthropicMessageRole::User,
+            content: MessageContent::Blocks(tool_results),
+        });
+    }
+
+    Err(format!(
+        "Tool use did not finish after {} iterations",
+        MAX_TOOL_ITERATIONS
+    )
+    .into())
+}
+
+/// Runs the tool loop and forwards what it streams to `events`, which is
+/// all `send_message` does once credentials and endpoints are resolved.
+pub async fn stream_generation(
+    events: &StreamEmitter,
+    client: &reqwest::Client,
+    policy: RetryPolicy,
+    api_url: &str,
+    api_key: &str,
+    request: AnthropicChatRequest,
+    tools: &ToolRegistry,
+) -> Result<StreamResponse, AppError> {
+    let retry = Retry::new(policy, |attempt| {
+        let _ = events.retrying(attempt);
+    });
+
+    run_tool_loop(
+        client,
+        api_url,
+        api_key,
+        request,
+        tools,
+        &retry,
+        |text| {
+            let _ = events.text_delta(text);
+        },
+        |thinking| {
+            let _ = events.thinking_delta(thinking);
+        },
+        |usage| {
+            let _ = events.usage(usage);
+        },
+        |tool_call| {
+            let _ = events.tool_call(tool_call);
+        },
+    )
+    .await
+}
+
+pub async fn fetch_models(
+    client: &reqwest::Client,
+    models_url: &str,
+    api_key: &str,
+) -> Result<Vec<String>, AppError> {
+    let response = client
+        .get(models_url)
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01")
+        .header("Content-Type", "application/json")
+        .send()
+        .await
+        .map_err(|e| AppError::network("anthropic", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::from_response(
+            "anthropic",
+            status,
+            &headers,
+            &body,
+        ));
     }
 
-    #[allow(dead_code)]
-    pub fn list_models_impl<R: tauri::Runtime>(
+    let models_response: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| AppError::network("anthropic", e))?;
+
+    let models = if let Some(data) = models_response.get("data").and_then(|d| d.as_array()) {
+        data.iter()
+            .filter_map(|model| model.get("id").and_then(|id| id.as_str()).map(String::from))
+            .collect()
+    } else {
+        vec![
+            "claude-3-opus-20240229".to_string(),
+            "claude-3-sonnet-20240229".to_string(),
+            "claude-3-haiku-20240307".to_string(),
+        ]
+    };
+
+    Ok(models)
+}
+
+pub async fn probe_credentials(
+    client: &reqwest::Client,
+    models_url: &str,
+    api_key: &str,
+) -> CredentialValidation {
+    let request = client
+        .get(models_url)
+        .header("x-api-key", api_key)
+        .header("anthropic-version", "2023-06-01");
+
+    validation::probe_models(request, Some("anthropic-organization-id")).await
+}
+
+#[async_trait]
+impl LLMProvider for AnthropicProvider {
+    fn provider_id(&self) -> &'static str {
+        "anthropic"
+    }
+
+    async fn send_message(
         &self,
-        app: Arc<tauri::AppHandle<R>>,
-    ) -> tauri::async_runtime::JoinHandle<Result<Vec<String>, String>> {
-        tauri::async_runtime::spawn(async move {
-            let AnthropicCredentials { api_key } =
-                credentials::get_anthropic_credentials(&app).await?;
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        mut request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        request.model.get_or_insert(model);
+        let anthropic_request = self.adapt_request(request);
+
+        let AnthropicCredentials { api_key } =
+            context.credentials("anthropic", profile.as_deref()).await?;
+
+        let api_url =
+            endpoints::get_api_url("anthropic", Some(&context.endpoint_params("anthropic")?))?;
+
+        stream_generation(
+            &events,
+            &context.client()?,
+            context.retry_policy(),
+            &api_url,
+            &api_key,
+            anthropic_request,
+            &context.tools(),
+        )
+        .await
+    }
 
-            let api_url = endpoints::get_models_url("anthropic", None)?;
+    async fn list_models(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let AnthropicCredentials { api_key } =
+            context.credentials("anthropic", profile.as_deref()).await?;
 
-            let client = reqwest::Client::new();
+        let models_url =
+            endpoints::get_models_url("anthropic", Some(&context.endpoint_params("anthropic")?))?;
 
-            let response = client
-                .get(api_url)
-                .header("x-api-key", api_key)
-                .header("anthropic-version", "2023-06-01")
-                .header("Content-Type", "application/json")
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
+        fetch_models(&context.client()?, &models_url, &api_key).await
+    }
 
-            if !response.status().is_success() {
-                return Err(format!(
-                    "API request failed with status: {}",
-                    response.status()
-                ));
+    async fn has_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError> {
+        Ok(context
+            .credentials::<AnthropicCredentials>("anthropic", profile.as_deref())
+            .await
+            .is_ok())
+    }
+
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError> {
+        let AnthropicCredentials { api_key } =
+            match context.credentials("anthropic", profile.as_deref()).await {
+                Ok(credentials) => credentials,
+                Err(e) => return Ok(CredentialValidation::missing(e)),
+            };
+        let models_url =
+            endpoints::get_models_url("anthropic", Some(&context.endpoint_params("anthropic")?))?;
+
+        Ok(probe_credentials(&context.client()?, &models_url, &api_key).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::ChatMessage;
+    use crate::core::sse::SseParser;
+    use crate::core::validation::CredentialStatus;
+    use std::time::Duration;
+    use wiremock::matchers::{body_string_contains, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_request() -> AnthropicChatRequest {
+        AnthropicChatRequest {
+            model: Some("claude-3-7-sonnet-20250219".to_string()),
+            messages: vec![AnthropicMessage {
+                role: AnthropicMessageRole::User,
+                content: MessageContent::String("What time is it?".to_string()),
+            }],
+            system: AnthropicSystemPrompt::Simple("Be brief.".to_string()),
+            temperature: None,
+            max_tokens: Some(256),
+            stream: Some(true),
+            thinking: None,
+            tools: Some(ToolRegistry::with_defaults().definitions()),
+            tool_choice: None,
+            top_k: None,
+            top_p: None,
+        }
+    }
+
+    const TOOL_USE_STREAM: &str = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_1\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude\",\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":20,\"output_tokens\":1}}}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\"Let me check.\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"tool_use\",\"id\":\"toolu_1\",\"name\":\"current_datetime\",\"input\":{}}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"{\\\"timez\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"input_json_delta\",\"partial_json\":\"one\\\": \\\"utc\\\"}\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"tool_use\",\"stop_sequence\":null},\"usage\":{\"output_tokens\":15}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    const END_TURN_STREAM: &str = concat!(
+        "event: message_start\n",
+        "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_2\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude\",\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":40,\"output_tokens\":1}}}\n\n",
+        "event: content_block_start\n",
+        "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+        "event: content_block_delta\n",
+        "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"text_delta\",\"text\":\" It is noon.\"}}\n\n",
+        "event: content_block_stop\n",
+        "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+        "event: message_delta\n",
+        "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\",\"stop_sequence\":null},\"usage\":{\"output_tokens\":5}}\n\n",
+        "event: message_stop\n",
+        "data: {\"type\":\"message_stop\"}\n\n",
+    );
+
+    #[test]
+    fn stream_processor_collects_tool_use_blocks() {
+        let mut processor = AnthropicStreamProcessor::new();
+        for event in SseParser::new().feed(TOOL_USE_STREAM.as_bytes()) {
+            processor.process_event(&event).unwrap();
+        }
+
+        assert_eq!(processor.get_stop_reason(), Some(StopReason::ToolUse));
+        assert_eq!(processor.get_text_content(), "Let me check.");
+
+        let tool_uses = processor.get_tool_uses().unwrap();
+        assert_eq!(tool_uses.len(), 1);
+        assert_eq!(tool_uses[0].id, "toolu_1");
+        assert_eq!(tool_uses[0].name, "current_datetime");
+        assert_eq!(tool_uses[0].input, serde_json::json!({ "timezone": "utc" }));
+
+        let message = processor.get_message().unwrap();
+        match message.content {
+            MessageContent::Blocks(blocks) => {
+                assert_eq!(blocks.len(), 2);
+                assert!(matches!(blocks[1], ContentBlock::ToolUse { .. }));
             }
+            other => panic!("unexpected content: {:?}", other),
+        }
+    }
 
-            let models_response: serde_json::Value =
-                response.json().await.map_err(|e| e.to_string())?;
+    #[test]
+    fn stream_processor_collects_thinking_and_signature() {
+        let stream = concat!(
+            "data: {\"type\":\"message_start\",\"message\":{\"id\":\"msg_3\",\"type\":\"message\",\"role\":\"assistant\",\"content\":[],\"model\":\"claude\",\"stop_reason\":null,\"stop_sequence\":null,\"usage\":{\"input_tokens\":10,\"output_tokens\":1}}}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":0,\"content_block\":{\"type\":\"thinking\",\"thinking\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"Two plus \"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"thinking_delta\",\"thinking\":\"two is four.\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":0,\"delta\":{\"type\":\"signature_delta\",\"signature\":\"EqQBCgIYAhIM\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":0}\n\n",
+            "data: {\"type\":\"content_block_start\",\"index\":1,\"content_block\":{\"type\":\"text\",\"text\":\"\"}}\n\n",
+            "data: {\"type\":\"content_block_delta\",\"index\":1,\"delta\":{\"type\":\"text_delta\",\"text\":\"4\"}}\n\n",
+            "data: {\"type\":\"content_block_stop\",\"index\":1}\n\n",
+            "data: {\"type\":\"message_delta\",\"delta\":{\"stop_reason\":\"end_turn\",\"stop_sequence\":null},\"usage\":{\"output_tokens\":30}}\n\n",
+            "data: {\"type\":\"message_stop\"}\n\n",
+        );
+
+        let mut processor = AnthropicStreamProcessor::new();
+        for event in SseParser::new().feed(stream.as_bytes()) {
+            processor.process_event(&event).unwrap();
+        }
 
-            let models = if let Some(data) = models_response.get("data").and_then(|d| d.as_array())
-            {
-                data.iter()
-                    .filter_map(|model| {
-                        model.get("id").and_then(|id| id.as_str()).map(String::from)
-                    })
-                    .collect()
-            } else {
-                vec![
-                    "claude-3-opus-20240229".to_string(),
-                    "claude-3-sonnet-20240229".to_string(),
-                    "claude-3-haiku-20240307".to_string(),
-                ]
-            };
+        assert_eq!(
+            processor.get_thinking_content().as_deref(),
+            Some("Two plus two is four.")
+        );
+        assert_eq!(processor.get_text_content(), "4");
+
+        let items = processor.get_content_items();
+        assert!(matches!(
+            &items[0],
+            ContentItem::Thinking { thinking, signature }
+                if thinking == "Two plus two is four." && signature == "EqQBCgIYAhIM"
+        ));
+
+        let message = processor.get_message().unwrap();
+        let body = serde_json::to_value(&message).unwrap();
+        assert_eq!(body["content"][0]["type"], "thinking");
+        assert_eq!(body["content"][0]["signature"], "EqQBCgIYAhIM");
+    }
 
-            Ok(models)
-        })
+    #[test]
+    fn adapt_request_splits_tool_results_out_of_assistant_turns() {
+        let request = ChatRequest {
+            messages: vec![
+                ChatMessage {
+                    role: ChatRole::User,
+                    content: ContentType::PlainText("What time is it?".to_string()),
+                },
+                ChatMessage {
+                    role: ChatRole::Assistant,
+                    content: ContentType::StructuredContent(vec![
+                        ContentItem::Thinking {
+                            thinking: "Use the tool.".to_string(),
+                            signature: "sig".to_string(),
+                        },
+                        ContentItem::ToolUse {
+                            id: "toolu_1".to_string(),
+                            name: "current_datetime".to_string(),
+                            input: serde_json::json!({}),
+                        },
+                        ContentItem::ToolResult {
+                            tool_use_id: "toolu_1".to_string(),
+                            content: "noon".to_string(),
+                            is_error: None,
+                        },
+                        ContentItem::Text {
+                            text: "It is noon.".to_string(),
+                        },
+                    ]),
+                },
+            ],
+            system: AnthropicSystemPrompt::Simple("Be brief.".to_string()),
+            max_tokens: Some(2048),
+            stream: Some(true),
+            ..Default::default()
+        };
+
+        let adapted = AnthropicProvider {}.adapt_request(request);
+        let roles: Vec<_> = adapted
+            .messages
+            .iter()
+            .map(|message| serde_json::to_value(&message.role).unwrap())
+            .collect();
+
+        assert_eq!(
+            roles,
+            vec!["user", "assistant", "user", "assistant"]
+                .into_iter()
+                .map(serde_json::Value::from)
+                .collect::<Vec<_>>()
+        );
     }
 
-    // #[allow(dead_code)]
-    // pub fn get_model_details_impl<R: tauri::Runtime>(
-    //     &self,
-    //     app: Arc<tauri::AppHandle<R>>,
-    //     model: String,
-    // ) -> tauri::async_runtime::JoinHandle<Result<serde_json::Value, String>> {
-    //     tauri::async_runtime::spawn(async move {
-    //         let AnthropicCredentials {
-    //             api_key,
-    //         } = credentials::get_anthropic_credentials(&app).await?;
-    //
-    //         let details_url = endpoints::get_model_schema_url("anthropic", &params, &model)?;
-    //
-    //         let client = reqwest::Client::new();
-    //         let response = client
-    //             .get(details_url)
-    //             .header("Authorization", format!("Bearer {}", api_token))
-    //             .send()
-    //             .await
-    //             .map_err(|e| e.to_string())?;
-    //
-    //         let result: CloudFlareModelDetailsResponse =
-    //             response.json().await.map_err(|e| e.to_string())?;
-    //
-    //         Ok(serde_json::to_value(result.result).map_err(|e| e.to_string())?)
-    //     })
-    // }
-
-    #[allow(dead_code)]
-    pub fn has_credentials_impl<R: tauri::Runtime>(
-        &self,
-        app: Arc<tauri::AppHandle<R>>,
-    ) -> tauri::async_runtime::JoinHandle<Result<bool, String>> {
-        tauri::async_runtime::spawn(async move {
-            let result = credentials::get_cloudflare_credentials(&app).await.is_ok();
-            Ok(result)
-        })
+    #[tokio::test]
+    async fn run_tool_loop_sends_tool_results_until_end_turn() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .and(body_string_contains("tool_result"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(END_TURN_STREAM, "text/event-stream"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(TOOL_USE_STREAM, "text/event-stream"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let mut tool_calls = Vec::new();
+        let response = run_tool_loop(
+            &reqwest::Client::new(),
+            &format!("{}/v1/messages", server.uri()),
+            "test-key",
+            chat_request(),
+            &ToolRegistry::with_defaults(),
+            &Retry::never(),
+            |_| {},
+            |_| {},
+            |_| {},
+            |event| tool_calls.push(event.clone()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.response, "Let me check. It is noon.");
+        assert_eq!(tool_calls.len(), 1);
+        assert_eq!(tool_calls[0].name, "current_datetime");
+        assert!(!tool_calls[0].is_error);
+
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 60);
+        assert_eq!(usage.completion_tokens, 20);
+
+        let content = response.content.unwrap();
+        assert_eq!(content.len(), 4);
+        assert!(matches!(content[2], ContentItem::ToolResult { .. }));
+    }
+
+    #[tokio::test]
+    async fn probe_credentials_reports_organization_and_models() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("x-api-key", "test-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("anthropic-organization-id", "org-123")
+                    .set_body_json(serde_json::json!({
+                        "data": [{ "id": "claude-3-7-sonnet-20250219", "type": "model" }]
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(401).set_body_json(serde_json::json!({
+                "type": "error",
+                "error": { "type": "authentication_error", "message": "invalid x-api-key" }
+            })))
+            .mount(&server)
+            .await;
+
+        let client = reqwest::Client::new();
+        let models_url = format!("{}/v1/models", server.uri());
+
+        let validation = probe_credentials(&client, &models_url, "test-key").await;
+        assert_eq!(validation.status, CredentialStatus::Valid);
+        let account = validation.account.unwrap();
+        assert_eq!(account.organization_id.as_deref(), Some("org-123"));
+        assert_eq!(account.models, vec!["claude-3-7-sonnet-20250219"]);
+
+        let validation = probe_credentials(&client, &models_url, "wrong-key").await;
+        assert_eq!(validation.status, CredentialStatus::InvalidKey);
+    }
+
+    #[tokio::test]
+    async fn stream_message_returns_structured_api_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(
+                ResponseTemplate::new(529)
+                    .insert_header("retry-after", "3")
+                    .set_body_json(serde_json::json!({
+                        "type": "error",
+                        "error": { "type": "overloaded_error", "message": "Overloaded" }
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let error = stream_message(
+            &reqwest::Client::new(),
+            &format!("{}/v1/messages", server.uri()),
+            "test-key",
+            &chat_request(),
+            &Retry::never(),
+            |_| {},
+            |_| {},
+            |_| {},
+        )
+        .await
+        .err()
+        .unwrap();
+
+        assert_eq!(
+            error,
+            AppError::Api {
+                provider: "anthropic".to_string(),
+                status: Some(529),
+                error_type: Some("overloaded_error".to_string()),
+                retry_after: Some(3),
+                message: "Overloaded".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_message_retries_overloaded_responses() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(ResponseTemplate::new(529).set_body_json(serde_json::json!({
+                "type": "error",
+                "error": { "type": "overloaded_error", "message": "Overloaded" }
+            })))
+            .up_to_n_times(2)
+            .expect(2)
+            .mount(&server)
+            .await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/messages"))
+            .respond_with(
+                ResponseTemplate::new(200).set_body_raw(END_TURN_STREAM, "text/event-stream"),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let attempts = std::sync::Mutex::new(Vec::new());
+        let retry = Retry::new(
+            RetryPolicy {
+                max_attempts: 3,
+                base_delay: Duration::from_millis(1),
+                max_delay: Duration::from_millis(5),
+            },
+            |attempt| attempts.lock().unwrap().push(attempt.clone()),
+        );
+
+        let processor = stream_message(
+            &reqwest::Client::new(),
+            &format!("{}/v1/messages", server.uri()),
+            "test-key",
+            &chat_request(),
+            &retry,
+            |_| {},
+            |_| {},
+            |_| {},
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(processor.get_text_content(), " It is noon.");
+        drop(retry);
+        let attempts = attempts.into_inner().unwrap();
+        assert_eq!(
+            attempts.iter().map(|a| a.attempt).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+        assert!(matches!(
+            attempts[0].error,
+            AppError::Api {
+                status: Some(529),
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn processor_keeps_mid_stream_errors() {
+        let mut processor = AnthropicStreamProcessor::new();
+        let event = SseEvent {
+            event: "error".to_string(),
+            data: r#"{"type":"error","error":{"type":"overloaded_error","message":"Overloaded"}}"#
+                .to_string(),
+            id: None,
+        };
+
+        assert!(processor.process_event(&event).unwrap());
+        assert_eq!(
+            AppError::from_stream_error("anthropic", processor.error.as_ref().unwrap()).to_string(),
+            "anthropic API error: Overloaded"
+        );
     }
 }
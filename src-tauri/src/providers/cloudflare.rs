@@ -1,17 +1,23 @@
-use crate::core::credentials::{self, CloudflareCredentials};
+use crate::core::context::ProviderContext;
+use crate::core::credentials::CloudflareCredentials;
 use crate::core::endpoints;
+use crate::core::error::AppError;
+use crate::core::llm_trait::LLMProvider;
 use crate::core::models::{ChatRequest, StreamResponse};
+use crate::core::retry::{Retry, RetryPolicy};
+use crate::core::sse;
+use crate::core::stream::StreamEmitter;
+use crate::core::validation::{AccountMetadata, CredentialStatus, CredentialValidation};
+use async_trait::async_trait;
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
-use std::collections::HashMap;
-use std::sync::Arc;
-use tauri::Emitter;
-use tauri::Window;
 use ts_rs::TS;
 
 pub struct CloudflareProvider {}
 
+pub const DEFAULT_MODEL: &str = "@cf/meta/llama-3-8b-instruct";
+
 #[derive(Debug, Serialize, Deserialize, TS)]
 #[ts(export)]
 pub struct CloudflareError {
@@ -67,148 +73,378 @@ pub struct CloudFlareModelDetailsResponse {
     pub success: bool,
 }
 
-impl CloudflareProvider {
-    #[allow(dead_code)]
-    pub fn send_message_impl<R: tauri::Runtime>(
-        &self,
-        window: Window<R>,
-        app: Arc<tauri::AppHandle<R>>,
-        model: String,
-        request: ChatRequest,
-    ) -> tauri::async_runtime::JoinHandle<Result<StreamResponse, String>> {
-        tauri::async_runtime::spawn(async move {
-            let CloudflareCredentials {
-                account_id,
-                api_token,
-            } = credentials::get_cloudflare_credentials(&app).await?;
+#[derive(Debug, Deserialize)]
+struct CloudflareEnvelope {
+    success: bool,
+    #[serde(default)]
+    errors: Vec<CloudflareError>,
+    #[serde(default)]
+    result: Value,
+}
+
+async fn get_envelope(
+    client: &reqwest::Client,
+    url: &str,
+    api_token: &str,
+) -> Result<(reqwest::StatusCode, CloudflareEnvelope), reqwest::Error> {
+    let response = client.get(url).bearer_auth(api_token).send().await?;
+    let status = response.status();
+
+    Ok((status, response.json().await?))
+}
+
+/// Searches the account's models. When that is refused, verifying the token
+/// on its own tells a bad token apart from a bad account id.
+pub async fn probe_credentials(
+    client: &reqwest::Client,
+    api_base: &str,
+    credentials: &CloudflareCredentials,
+) -> CredentialValidation {
+    let account_url = format!("{}/accounts/{}", api_base, credentials.account_id);
 
-            let mut params = HashMap::new();
-            params.insert("account_id".to_string(), account_id.clone());
-            params.insert("model".to_string(), model.clone());
+    let (status, envelope) = match get_envelope(
+        client,
+        &format!("{}/ai/models/search", account_url),
+        &credentials.api_token,
+    )
+    .await
+    {
+        Ok(result) => result,
+        Err(e) => return CredentialValidation::from_request_error(&e),
+    };
 
-            let api_url = endpoints::get_api_url("cloudflare", Some(&params))?;
+    if envelope.success {
+        let models = envelope
+            .result
+            .as_array()
+            .map(|models| {
+                models
+                    .iter()
+                    .filter_map(|model| model.get("name").and_then(|name| name.as_str()))
+                    .map(String::from)
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        // Reading the account name needs an extra permission, so it is optional.
+        let account_name = match get_envelope(client, &account_url, &credentials.api_token).await {
+            Ok((_, account)) if account.success => account
+                .result
+                .get("name")
+                .and_then(|name| name.as_str())
+                .map(String::from),
+            _ => None,
+        };
+
+        return CredentialValidation::valid(AccountMetadata {
+            organization_id: Some(credentials.account_id.clone()),
+            account_name,
+            models,
+        });
+    }
+
+    let message = envelope
+        .errors
+        .iter()
+        .map(|error| format!("{} ({})", error.message, error.code))
+        .collect::<Vec<_>>()
+        .join(", ");
 
-            let client = reqwest::Client::new();
+    // 7000 and 7003: no route for the account identifier.
+    if status == reqwest::StatusCode::NOT_FOUND
+        || envelope
+            .errors
+            .iter()
+            .any(|error| error.code == 7000 || error.code == 7003)
+    {
+        return CredentialValidation::failed(CredentialStatus::WrongAccountId, message);
+    }
+
+    if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        let verify_url = format!("{}/user/tokens/verify", api_base);
+        return match get_envelope(client, &verify_url, &credentials.api_token).await {
+            Ok((_, verified)) if verified.success => {
+                CredentialValidation::failed(CredentialStatus::WrongAccountId, message)
+            }
+            Ok(_) => CredentialValidation::failed(CredentialStatus::InvalidKey, message),
+            Err(e) => CredentialValidation::from_request_error(&e),
+        };
+    }
 
-            let response = client
+    CredentialValidation::from_http_status(status, &message)
+}
+
+/// Streams a Workers AI completion and forwards it to `events`, which is
+/// all `send_message` does once credentials and endpoints are resolved.
+pub async fn stream_generation(
+    events: &StreamEmitter,
+    client: &reqwest::Client,
+    policy: RetryPolicy,
+    api_url: &str,
+    api_token: &str,
+    request: &ChatRequest,
+) -> Result<StreamResponse, AppError> {
+    let retry = Retry::new(policy, |attempt| {
+        let _ = events.retrying(attempt);
+    });
+
+    let response = retry
+        .send("cloudflare", || {
+            client
                 .post(api_url)
                 .header("Authorization", format!("Bearer {}", api_token))
-                .json(&request)
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
-
-            if response.status() == 400 {
-                let error_body = response.text().await.map_err(|e| e.to_string())?;
-                println!("Error response body: {}", error_body);
-                return Err(format!("API Error: {}", error_body));
-            }
+                .json(request)
+        })
+        .await?;
+
+    let mut accumulated_text = String::new();
+    let mut tokens_usage = None;
+
+    let mut stream = Box::pin(sse::sse_stream(response.bytes_stream()));
+
+    while let Some(event) = stream.next().await {
+        let event = event.map_err(|e| AppError::network("cloudflare", e))?;
+
+        if event.data == "[DONE]" {
+            break;
+        }
 
-            let mut accumulated_text = String::new();
-            let mut buffer = String::new();
-
-            let mut stream = response.bytes_stream();
-            let mut tokens_usage = None;
-
-            while let Some(chunk) = stream.next().await {
-                let chunk = chunk.map_err(|e| e.to_string())?;
-                let chunk_str = String::from_utf8(chunk.to_vec()).map_err(|e| e.to_string())?;
-
-                buffer.push_str(&chunk_str);
-
-                while let Some(pos) = buffer.find('\n') {
-                    let line = buffer[..pos].trim().to_string();
-                    let remaining = buffer[pos + 1..].to_string();
-                    buffer = remaining;
-
-                    if let Some(data) = line.strip_prefix("data: ") {
-                        if data == "[DONE]" {
-                            return Ok(StreamResponse {
-                                response: accumulated_text,
-                                usage: tokens_usage,
-                                thinking: None,
-                            });
-                        }
-
-                        match serde_json::from_str::<StreamResponse>(data) {
-                            Ok(stream_response) => {
-                                accumulated_text.push_str(&stream_response.response);
-                                if let Some(usage) = &stream_response.usage {
-                                    tokens_usage = Some(usage.clone());
-                                }
-                                window
-                                    .emit("stream-response", &stream_response.response)
-                                    .map_err(|e| e.to_string())?;
-                            }
-                            Err(e) => {
-                                eprintln!("Error parsing stream response: {}", e);
-                            }
-                        }
-                    }
+        match serde_json::from_str::<StreamResponse>(&event.data) {
+            Ok(stream_response) => {
+                accumulated_text.push_str(&stream_response.response);
+                if !stream_response.response.is_empty() {
+                    events.text_delta(&stream_response.response)?;
                 }
+                if let Some(usage) = &stream_response.usage {
+                    events.usage(usage)?;
+                    tokens_usage = Some(usage.clone());
+                }
+            }
+            Err(e) => {
+                eprintln!("Error parsing stream response: {}", e);
             }
+        }
+    }
 
-            Ok(StreamResponse {
-                response: accumulated_text,
-                usage: tokens_usage,
-                thinking: None,
-            })
-        })
+    Ok(StreamResponse {
+        response: accumulated_text,
+        usage: tokens_usage,
+        thinking: None,
+        content: None,
+        stop_reason: None,
+    })
+}
+
+#[async_trait]
+impl LLMProvider for CloudflareProvider {
+    fn provider_id(&self) -> &'static str {
+        "cloudflare"
     }
 
-    #[allow(dead_code)]
-    pub fn list_models_impl<R: tauri::Runtime>(
+    async fn send_message(
         &self,
-        _app: Arc<tauri::AppHandle<R>>,
-    ) -> tauri::async_runtime::JoinHandle<Result<Vec<String>, String>> {
-        tauri::async_runtime::spawn(async move {
-            // Implémentation pour CloudFlare
-            // ...
-            Ok(vec!["@cf/meta/llama-3-8b-instruct".to_string()])
-        })
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        let CloudflareCredentials {
+            account_id,
+            api_token,
+        } = context
+            .credentials::<CloudflareCredentials>("cloudflare", profile.as_deref())
+            .await?;
+
+        let mut params = context.endpoint_params("cloudflare")?;
+        params.insert("account_id".to_string(), account_id.clone());
+        params.insert("model".to_string(), model.clone());
+
+        let api_url = endpoints::get_api_url("cloudflare", Some(&params))?;
+
+        stream_generation(
+            &events,
+            &context.client()?,
+            context.retry_policy(),
+            &api_url,
+            &api_token,
+            &request,
+        )
+        .await
     }
 
-    #[allow(dead_code)]
-    pub fn get_model_details_impl<R: tauri::Runtime>(
+    async fn list_models(
         &self,
-        app: Arc<tauri::AppHandle<R>>,
+        _context: &dyn ProviderContext,
+        _profile: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        // Implémentation pour CloudFlare
+        // ...
+        Ok(vec![DEFAULT_MODEL.to_string()])
+    }
+
+    async fn model_details(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
         model: String,
-    ) -> tauri::async_runtime::JoinHandle<Result<serde_json::Value, String>> {
-        tauri::async_runtime::spawn(async move {
-            let CloudflareCredentials {
-                account_id,
-                api_token,
-            } = credentials::get_cloudflare_credentials(&app).await?;
-
-            let mut params = HashMap::new();
-            params.insert("account_id".to_string(), account_id.clone());
-            params.insert("model".to_string(), model.clone());
-
-            let details_url = endpoints::get_model_schema_url("cloudflare", Some(&params), &model)?;
-
-            let client = reqwest::Client::new();
-            let response = client
-                .get(details_url)
-                .header("Authorization", format!("Bearer {}", api_token))
-                .send()
-                .await
-                .map_err(|e| e.to_string())?;
+    ) -> Result<serde_json::Value, AppError> {
+        let CloudflareCredentials {
+            account_id,
+            api_token,
+        } = context
+            .credentials::<CloudflareCredentials>("cloudflare", profile.as_deref())
+            .await?;
 
-            let result: CloudFlareModelDetailsResponse =
-                response.json().await.map_err(|e| e.to_string())?;
+        let mut params = context.endpoint_params("cloudflare")?;
+        params.insert("account_id".to_string(), account_id.clone());
+        params.insert("model".to_string(), model.clone());
 
-            Ok(serde_json::to_value(result.result).map_err(|e| e.to_string())?)
-        })
+        let details_url = endpoints::get_model_schema_url("cloudflare", Some(&params), &model)?;
+
+        let response = context
+            .client()?
+            .get(details_url)
+            .header("Authorization", format!("Bearer {}", api_token))
+            .send()
+            .await
+            .map_err(|e| AppError::network("cloudflare", e))?;
+
+        let status = response.status();
+        if !status.is_success() {
+            let headers = response.headers().clone();
+            let body = response.text().await.unwrap_or_default();
+            return Err(AppError::from_response(
+                "cloudflare",
+                status,
+                &headers,
+                &body,
+            ));
+        }
+
+        let result: CloudFlareModelDetailsResponse = response
+            .json()
+            .await
+            .map_err(|e| AppError::network("cloudflare", e))?;
+
+        serde_json::to_value(result.result).map_err(|e| AppError::from(e.to_string()))
     }
 
-    #[allow(dead_code)]
-    pub fn has_credentials_impl<R: tauri::Runtime>(
+    async fn has_credentials(
         &self,
-        app: Arc<tauri::AppHandle<R>>,
-    ) -> tauri::async_runtime::JoinHandle<Result<bool, String>> {
-        tauri::async_runtime::spawn(async move {
-            let result = credentials::get_cloudflare_credentials(&app).await.is_ok();
-            Ok(result)
-        })
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError> {
+        Ok(context
+            .credentials::<CloudflareCredentials>("cloudflare", profile.as_deref())
+            .await
+            .is_ok())
+    }
+
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError> {
+        let api_base =
+            endpoints::get_api_base("cloudflare", Some(&context.endpoint_params("cloudflare")?))?;
+        match context
+            .credentials::<CloudflareCredentials>("cloudflare", profile.as_deref())
+            .await
+        {
+            Ok(credentials) => {
+                Ok(probe_credentials(&context.client()?, &api_base, &credentials).await)
+            }
+            Err(e) => Ok(CredentialValidation::missing(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::validation::CredentialStatus;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn cloudflare_credentials(account_id: &str) -> CloudflareCredentials {
+        CloudflareCredentials {
+            account_id: account_id.to_string(),
+            api_token: "token".to_string(),
+        }
+    }
+
+    fn refused() -> ResponseTemplate {
+        ResponseTemplate::new(403).set_body_json(serde_json::json!({
+            "success": false,
+            "errors": [{ "code": 10000, "message": "Authentication error" }],
+            "messages": [],
+            "result": null
+        }))
+    }
+
+    #[tokio::test]
+    async fn probe_credentials_tells_wrong_account_from_invalid_token() {
+        let server = MockServer::start().await;
+        let client = reqwest::Client::new();
+
+        Mock::given(method("GET"))
+            .and(path("/accounts/good/ai/models/search"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "errors": [],
+                "result": [{ "name": "@cf/meta/llama-3-8b-instruct" }]
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/good"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "errors": [],
+                "result": { "id": "good", "name": "Team account" }
+            })))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/accounts/other/ai/models/search"))
+            .respond_with(refused())
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/user/tokens/verify"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "success": true,
+                "errors": [],
+                "result": { "status": "active" }
+            })))
+            .mount(&server)
+            .await;
+
+        let validation =
+            probe_credentials(&client, &server.uri(), &cloudflare_credentials("good")).await;
+        assert_eq!(validation.status, CredentialStatus::Valid);
+        let account = validation.account.unwrap();
+        assert_eq!(account.account_name.as_deref(), Some("Team account"));
+        assert_eq!(account.models, vec!["@cf/meta/llama-3-8b-instruct"]);
+
+        let validation =
+            probe_credentials(&client, &server.uri(), &cloudflare_credentials("other")).await;
+        assert_eq!(validation.status, CredentialStatus::WrongAccountId);
+    }
+
+    #[tokio::test]
+    async fn probe_credentials_reports_invalid_token() {
+        let server = MockServer::start().await;
+        let client = reqwest::Client::new();
+
+        Mock::given(method("GET"))
+            .respond_with(refused())
+            .mount(&server)
+            .await;
+
+        let validation =
+            probe_credentials(&client, &server.uri(), &cloudflare_credentials("good")).await;
+        assert_eq!(validation.status, CredentialStatus::InvalidKey);
     }
 }
@@ -0,0 +1,328 @@
+//! Replays recorded provider transcripts from a local server and checks
+//! both the returned `StreamResponse` and the events the sink received.
+//!
+//! The `.sse` files next to this module are response bodies as the
+//! providers send them. Providers are driven one level below
+//! `LLMProvider::send_message`, so no credentials or stores are involved,
+//! while URLs still go through `core::endpoints` and its base URL overrides.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use tauri::test::{mock_app, MockRuntime};
+use tauri::{App, Manager};
+use wiremock::matchers::{body_string_contains, header, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+use crate::core::endpoints::{self, EndpointOverrides};
+use crate::core::error::AppError;
+use crate::core::models::{ChatRequest, StreamResponse};
+use crate::core::retry::RetryPolicy;
+use crate::core::sink::CollectorSink;
+use crate::core::stream::{StopReason, StreamEmitter, StreamEvent};
+use crate::core::tools::ToolRegistry;
+use crate::providers::anthropic::{self, AnthropicChatRequest};
+use crate::providers::cloudflare;
+
+const ANTHROPIC_TEXT: &str = include_str!("anthropic_text.sse");
+const ANTHROPIC_THINKING: &str = include_str!("anthropic_thinking.sse");
+const ANTHROPIC_TOOL_USE: &str = include_str!("anthropic_tool_use.sse");
+const ANTHROPIC_TOOL_RESULT: &str = include_str!("anthropic_tool_result.sse");
+const ANTHROPIC_ERROR: &str = include_str!("anthropic_error.sse");
+const CLOUDFLARE_TEXT: &str = include_str!("cloudflare_text.sse");
+
+const CLOUDFLARE_MODEL: &str = "@cf/meta/llama-3-8b-instruct";
+
+struct Harness {
+    server: MockServer,
+    app: App<MockRuntime>,
+    events: StreamEmitter,
+    received: Arc<CollectorSink>,
+}
+
+impl Harness {
+    async fn start() -> Self {
+        let server = MockServer::start().await;
+
+        let app = mock_app();
+        app.manage(
+            EndpointOverrides::new()
+                .with_base_url("anthropic", &server.uri())
+                .with_base_url("cloudflare", &server.uri()),
+        );
+
+        let received = Arc::new(CollectorSink::new());
+        let events = StreamEmitter::new(received.clone(), "req-fixture".to_string());
+
+        Self {
+            server,
+            app,
+            events,
+            received,
+        }
+    }
+
+    async fn replay(&self, url_path: &str, transcript: &str) {
+        Mock::given(method("POST"))
+            .and(path(url_path))
+            .respond_with(sse_response(transcript))
+            .mount(&self.server)
+            .await;
+    }
+
+    fn anthropic_url(&self) -> String {
+        let params = endpoints::endpoint_params(self.app.handle(), "anthropic").unwrap();
+        endpoints::get_api_url("anthropic", Some(&params)).unwrap()
+    }
+
+    fn cloudflare_url(&self) -> String {
+        let mut params = endpoints::endpoint_params(self.app.handle(), "cloudflare").unwrap();
+        params.insert("account_id".to_string(), "acct".to_string());
+        params.insert("model".to_string(), CLOUDFLARE_MODEL.to_string());
+        endpoints::get_api_url("cloudflare", Some(&params)).unwrap()
+    }
+
+    async fn anthropic(&self, request: AnthropicChatRequest) -> Result<StreamResponse, AppError> {
+        anthropic::stream_generation(
+            &self.events,
+            &reqwest::Client::new(),
+            fast_retries(),
+            &self.anthropic_url(),
+            "test-key",
+            request,
+            &ToolRegistry::with_defaults(),
+        )
+        .await
+    }
+
+    async fn cloudflare(&self, request: &ChatRequest) -> Result<StreamResponse, AppError> {
+        cloudflare::stream_generation(
+            &self.events,
+            &reqwest::Client::new(),
+            fast_retries(),
+            &self.cloudflare_url(),
+            "test-token",
+            request,
+        )
+        .await
+    }
+
+    /// The events received so far, in a compact form easy to compare.
+    fn received(&self) -> Vec<String> {
+        self.received
+            .events()
+            .iter()
+            .map(|event| match event {
+                StreamEvent::Start { request_id } => format!("start:{}", request_id),
+                StreamEvent::Retrying { retry, .. } => format!("retrying:{}", retry.attempt),
+                StreamEvent::TextDelta { text, .. } => format!("text:{}", text),
+                StreamEvent::ThinkingDelta { thinking, .. } => format!("thinking:{}", thinking),
+                StreamEvent::ToolCall { tool_call, .. } => {
+                    format!("tool_call:{}", tool_call.name)
+                }
+                StreamEvent::Usage { usage, .. } => {
+                    format!("usage:{}/{}", usage.prompt_tokens, usage.completion_tokens)
+                }
+                StreamEvent::Stop { reason, .. } => format!("stop:{:?}", reason),
+                StreamEvent::Error { error, .. } => format!("error:{}", error),
+            })
+            .collect()
+    }
+}
+
+fn sse_response(transcript: &str) -> ResponseTemplate {
+    ResponseTemplate::new(200).set_body_raw(transcript, "text/event-stream")
+}
+
+fn fast_retries() -> RetryPolicy {
+    RetryPolicy {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5),
+    }
+}
+
+fn anthropic_request(with_tools: bool) -> AnthropicChatRequest {
+    serde_json::from_value(serde_json::json!({
+        "model": "claude-3-7-sonnet-20250219",
+        "messages": [{ "role": "user", "content": "What time is it?" }],
+        "system": "Be brief.",
+        "max_tokens": 1024,
+        "stream": true,
+        "tools": with_tools.then(|| ToolRegistry::with_defaults().definitions()),
+    }))
+    .unwrap()
+}
+
+fn cloudflare_request() -> ChatRequest {
+    serde_json::from_value(serde_json::json!({
+        "messages": [{ "role": "user", "content": "Say hello" }],
+        "system": "Be brief.",
+        "max_tokens": 256,
+        "stream": true,
+    }))
+    .unwrap()
+}
+
+fn labels(labels: &[&str]) -> Vec<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+}
+
+#[tokio::test]
+async fn anthropic_text() {
+    let harness = Harness::start().await;
+    harness.replay("/v1/messages", ANTHROPIC_TEXT).await;
+
+    let response = harness.anthropic(anthropic_request(false)).await.unwrap();
+
+    assert_eq!(response.response, "Bonjour, ça va ?");
+    assert_eq!(response.thinking, None);
+    assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+    let usage = response.usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens), (25, 9));
+    assert_eq!(
+        harness.received(),
+        labels(&["usage:25/1", "text:Bonjour", "text:, ça va ?", "usage:25/9"])
+    );
+}
+
+#[tokio::test]
+async fn anthropic_thinking() {
+    let harness = Harness::start().await;
+    harness.replay("/v1/messages", ANTHROPIC_THINKING).await;
+
+    let response = harness.anthropic(anthropic_request(false)).await.unwrap();
+
+    assert_eq!(response.response, "4");
+    assert_eq!(response.thinking.as_deref(), Some("Let me add. 2 + 2 = 4."));
+    assert_eq!(
+        harness.received(),
+        labels(&[
+            "usage:30/1",
+            "thinking:Let me add.",
+            "thinking: 2 + 2 = 4.",
+            "text:4",
+            "usage:30/42",
+        ])
+    );
+}
+
+#[tokio::test]
+async fn anthropic_tool_use() {
+    let harness = Harness::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_string_contains("tool_result"))
+        .respond_with(sse_response(ANTHROPIC_TOOL_RESULT))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "test-key"))
+        .respond_with(sse_response(ANTHROPIC_TOOL_USE))
+        .expect(1)
+        .mount(&harness.server)
+        .await;
+
+    let response = harness.anthropic(anthropic_request(true)).await.unwrap();
+
+    assert_eq!(response.response, "Let me check. It is noon.");
+    assert_eq!(response.stop_reason, Some(StopReason::EndTurn));
+    let usage = response.usage.unwrap();
+    assert_eq!((usage.prompt_tokens, usage.completion_tokens), (60, 20));
+    assert_eq!(
+        harness.received(),
+        labels(&[
+            "usage:20/1",
+            "text:Let me check.",
+            "usage:20/15",
+            "tool_call:current_datetime",
+            "usage:60/16",
+            "text: It is noon.",
+            "usage:60/20",
+        ])
+    );
+}
+
+#[tokio::test]
+async fn anthropic_error_mid_stream() {
+    let harness = Harness::start().await;
+    harness.replay("/v1/messages", ANTHROPIC_ERROR).await;
+
+    let error = harness
+        .anthropic(anthropic_request(false))
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        error,
+        AppError::Api {
+            provider: "anthropic".to_string(),
+            status: None,
+            error_type: Some("overloaded_error".to_string()),
+            retry_after: None,
+            message: "Overloaded".to_string(),
+        }
+    );
+    assert_eq!(harness.received(), labels(&["usage:12/1"]));
+}
+
+#[tokio::test]
+async fn anthropic_retries_before_streaming() {
+    let harness = Harness::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .respond_with(ResponseTemplate::new(529).set_body_json(serde_json::json!({
+            "type": "error",
+            "error": { "type": "overloaded_error", "message": "Overloaded" }
+        })))
+        .up_to_n_times(2)
+        .expect(2)
+        .mount(&harness.server)
+        .await;
+    harness.replay("/v1/messages", ANTHROPIC_TEXT).await;
+
+    let response = harness.anthropic(anthropic_request(false)).await.unwrap();
+
+    assert_eq!(response.response, "Bonjour, ça va ?");
+    assert_eq!(
+        harness.received(),
+        labels(&[
+            "retrying:1",
+            "retrying:2",
+            "usage:25/1",
+            "text:Bonjour",
+            "text:, ça va ?",
+            "usage:25/9",
+        ])
+    );
+}
+
+#[tokio::test]
+async fn cloudflare_text_and_usage() {
+    let harness = Harness::start().await;
+    harness
+        .replay(
+            &format!("/accounts/acct/ai/run/{}", CLOUDFLARE_MODEL),
+            CLOUDFLARE_TEXT,
+        )
+        .await;
+
+    let response = harness.cloudflare(&cloudflare_request()).await.unwrap();
+
+    assert_eq!(response.response, "Salut le monde");
+    let usage = response.usage.unwrap();
+    assert_eq!(
+        (
+            usage.prompt_tokens,
+            usage.completion_tokens,
+            usage.total_tokens
+        ),
+        (14, 3, 17)
+    );
+    assert_eq!(
+        harness.received(),
+        labels(&["text:Salut", "text: le monde", "usage:14/3"])
+    );
+}
@@ -0,0 +1,549 @@
+use crate::core::context::ProviderContext;
+use crate::core::credentials::MistralCredentials;
+use crate::core::endpoints;
+use crate::core::error::AppError;
+use crate::core::llm_trait::LLMProvider;
+use crate::core::models::{
+    ChatRequest, ChatRole, ContentItem, ContentType, StreamResponse, TokenUsage,
+};
+use crate::core::retry::Retry;
+use crate::core::sse;
+use crate::core::stream::{StopReason, StreamEmitter};
+use crate::core::validation::{self, CredentialValidation};
+use crate::providers::anthropic::AnthropicSystemPrompt;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+
+pub struct MistralProvider {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum MistralRole {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum MistralContent {
+    Text(String),
+    Chunks(Vec<MistralContentChunk>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum MistralContentChunk {
+    #[serde(rename = "text")]
+    Text { text: String },
+
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: String },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralMessage {
+    pub role: MistralRole,
+    pub content: MistralContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralChatRequest {
+    pub model: String,
+    pub messages: Vec<MistralMessage>,
+    pub stream: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub random_seed: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<MistralChoice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<MistralUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralChoice {
+    pub delta: MistralDelta,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralModelsResponse {
+    pub data: Vec<MistralModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MistralModel {
+    pub id: String,
+}
+
+impl From<MistralUsage> for TokenUsage {
+    fn from(usage: MistralUsage) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+fn convert_content(content: &ContentType) -> MistralContent {
+    match content {
+        ContentType::PlainText(text) => MistralContent::Text(text.clone()),
+        ContentType::StructuredContent(items) => MistralContent::Chunks(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    ContentItem::Text { text } => {
+                        Some(MistralContentChunk::Text { text: text.clone() })
+                    }
+                    ContentItem::Image { source } => Some(MistralContentChunk::ImageUrl {
+                        image_url: format!("data:{};base64,{}", source.media_type, source.data),
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn convert_role(role: &ChatRole) -> MistralRole {
+    match role {
+        ChatRole::System => MistralRole::System,
+        ChatRole::User => MistralRole::User,
+        ChatRole::Assistant => MistralRole::Assistant,
+    }
+}
+
+pub fn adapt_request(model: String, request: ChatRequest) -> MistralChatRequest {
+    let system_prompt = match request.system {
+        AnthropicSystemPrompt::Simple(text) => text,
+        AnthropicSystemPrompt::Structured(data) => data.text,
+    };
+
+    let mut messages = Vec::with_capacity(request.messages.len() + 1);
+
+    if !system_prompt.is_empty() {
+        messages.push(MistralMessage {
+            role: MistralRole::System,
+            content: MistralContent::Text(system_prompt),
+        });
+    }
+
+    messages.extend(request.messages.iter().map(|msg| MistralMessage {
+        role: convert_role(&msg.role),
+        content: convert_content(&msg.content),
+    }));
+
+    MistralChatRequest {
+        model,
+        messages,
+        stream: true,
+        temperature: request.temperature,
+        top_p: request.top_p,
+        max_tokens: request.max_tokens,
+        random_seed: request.seed,
+        frequency_penalty: request.frequency_penalty,
+        presence_penalty: request.presence_penalty,
+    }
+}
+
+pub async fn stream_chat<F>(
+    client: &reqwest::Client,
+    api_url: &str,
+    api_key: &str,
+    request: &MistralChatRequest,
+    retry: &Retry<'_>,
+    mut on_delta: F,
+) -> Result<StreamResponse, AppError>
+where
+    F: FnMut(&str) -> Result<(), String> + Send,
+{
+    let response = retry
+        .send("mistral", || {
+            client
+                .post(api_url)
+                .header("Authorization", format!("Bearer {}", api_key))
+                .header("Accept", "text/event-stream")
+                .json(request)
+        })
+        .await?;
+
+    let mut accumulated_text = String::new();
+    let mut tokens_usage = None;
+    let mut stop_reason = None;
+
+    let mut stream = Box::pin(sse::sse_stream(response.bytes_stream()));
+
+    while let Some(event) = stream.next().await {
+        let event = event.map_err(|e| AppError::network("mistral", e))?;
+        let data = event.data.trim();
+
+        if data == "[DONE]" {
+            break;
+        }
+
+        match serde_json::from_str::<MistralStreamChunk>(data) {
+            Ok(stream_chunk) => {
+                if let Some(usage) = stream_chunk.usage {
+                    tokens_usage = Some(usage.into());
+                }
+
+                for choice in stream_chunk.choices {
+                    if let Some(reason) = choice.finish_reason.as_deref() {
+                        stop_reason = Some(StopReason::from_finish_reason(reason));
+                    }
+
+                    if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                        accumulated_text.push_str(&content);
+                        on_delta(&content)?;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error parsing Mistral stream chunk: {}", e);
+            }
+        }
+    }
+
+    Ok(StreamResponse {
+        response: accumulated_text,
+        usage: tokens_usage,
+        thinking: None,
+        content: None,
+        stop_reason,
+    })
+}
+
+pub async fn fetch_models(
+    client: &reqwest::Client,
+    models_url: &str,
+    api_key: &str,
+) -> Result<Vec<String>, AppError> {
+    let response = client
+        .get(models_url)
+        .header("Authorization", format!("Bearer {}", api_key))
+        .send()
+        .await
+        .map_err(|e| AppError::network("mistral", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::from_response("mistral", status, &headers, &body));
+    }
+
+    let models_response: MistralModelsResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::network("mistral", e))?;
+
+    Ok(models_response
+        .data
+        .into_iter()
+        .map(|model| model.id)
+        .collect())
+}
+
+pub async fn probe_credentials(
+    client: &reqwest::Client,
+    models_url: &str,
+    api_key: &str,
+) -> CredentialValidation {
+    let request = client
+        .get(models_url)
+        .header("Authorization", format!("Bearer {}", api_key));
+
+    validation::probe_models(request, None).await
+}
+
+#[async_trait]
+impl LLMProvider for MistralProvider {
+    fn provider_id(&self) -> &'static str {
+        "mistral"
+    }
+
+    async fn send_message(
+        &self,
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        let MistralCredentials { api_key } = context
+            .credentials::<MistralCredentials>("mistral", profile.as_deref())
+            .await?;
+
+        let api_url =
+            endpoints::get_api_url("mistral", Some(&context.endpoint_params("mistral")?))?;
+        let mistral_request = adapt_request(model, request);
+
+        let retry = Retry::new(context.retry_policy(), |attempt| {
+            let _ = events.retrying(attempt);
+        });
+
+        stream_chat(
+            &context.client()?,
+            &api_url,
+            &api_key,
+            &mistral_request,
+            &retry,
+            |delta| events.text_delta(delta),
+        )
+        .await
+    }
+
+    async fn list_models(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let MistralCredentials { api_key } = context
+            .credentials::<MistralCredentials>("mistral", profile.as_deref())
+            .await?;
+
+        let models_url =
+            endpoints::get_models_url("mistral", Some(&context.endpoint_params("mistral")?))?;
+
+        fetch_models(&context.client()?, &models_url, &api_key).await
+    }
+
+    async fn has_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError> {
+        Ok(context
+            .credentials::<MistralCredentials>("mistral", profile.as_deref())
+            .await
+            .is_ok())
+    }
+
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError> {
+        let MistralCredentials { api_key } = match context
+            .credentials::<MistralCredentials>("mistral", profile.as_deref())
+            .await
+        {
+            Ok(credentials) => credentials,
+            Err(e) => return Ok(CredentialValidation::missing(e)),
+        };
+        let models_url =
+            endpoints::get_models_url("mistral", Some(&context.endpoint_params("mistral")?))?;
+
+        Ok(probe_credentials(&context.client()?, &models_url, &api_key).await)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::{ChatMessage, ImageSource};
+    use wiremock::matchers::{header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_request(messages: Vec<ChatMessage>) -> ChatRequest {
+        ChatRequest {
+            messages,
+            system: AnthropicSystemPrompt::Simple("Be brief.".to_string()),
+            max_tokens: Some(64),
+            stream: Some(true),
+            temperature: Some(0.2),
+            seed: Some(7),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adapt_request_prepends_system_and_maps_images() {
+        let request = chat_request(vec![ChatMessage {
+            role: ChatRole::User,
+            content: ContentType::StructuredContent(vec![
+                ContentItem::Text {
+                    text: "What is this?".to_string(),
+                },
+                ContentItem::Image {
+                    source: ImageSource {
+                        source_type: "base64".to_string(),
+                        media_type: "image/png".to_string(),
+                        data: "AAAA".to_string(),
+                    },
+                },
+            ]),
+        }]);
+
+        let adapted = adapt_request("pixtral-12b".to_string(), request);
+
+        assert_eq!(adapted.model, "pixtral-12b");
+        assert_eq!(adapted.random_seed, Some(7));
+        assert_eq!(adapted.messages.len(), 2);
+        assert_eq!(adapted.messages[0].role, MistralRole::System);
+        assert_eq!(
+            adapted.messages[1].content,
+            MistralContent::Chunks(vec![
+                MistralContentChunk::Text {
+                    text: "What is this?".to_string()
+                },
+                MistralContentChunk::ImageUrl {
+                    image_url: "data:image/png;base64,AAAA".to_string()
+                },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_chat_collects_deltas_and_usage() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"id\":\"1\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"id\":\"1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Bonjour\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"id\":\"1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" le monde\"},\"finish_reason\":\"stop\"}],\"usage\":{\"prompt_tokens\":12,\"completion_tokens\":3,\"total_tokens\":15}}\n\n",
+            "data: [DONE]\n\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = adapt_request(
+            "mistral-small-latest".to_string(),
+            chat_request(vec![ChatMessage {
+                role: ChatRole::User,
+                content: ContentType::PlainText("Salut".to_string()),
+            }]),
+        );
+
+        let mut deltas = Vec::new();
+        let response = stream_chat(
+            &reqwest::Client::new(),
+            &format!("{}/v1/chat/completions", server.uri()),
+            "test-key",
+            &request,
+            &Retry::never(),
+            |delta| {
+                deltas.push(delta.to_string());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(deltas, vec!["Bonjour", " le monde"]);
+        assert_eq!(response.response, "Bonjour le monde");
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 12);
+        assert_eq!(usage.completion_tokens, 3);
+        assert_eq!(usage.total_tokens, 15);
+    }
+
+    #[tokio::test]
+    async fn stream_chat_surfaces_api_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .respond_with(
+                ResponseTemplate::new(401).set_body_string("{\"message\":\"Unauthorized\"}"),
+            )
+            .mount(&server)
+            .await;
+
+        let request = adapt_request("mistral-small-latest".to_string(), chat_request(vec![]));
+
+        let result = stream_chat(
+            &reqwest::Client::new(),
+            &format!("{}/v1/chat/completions", server.uri()),
+            "bad-key",
+            &request,
+            &Retry::never(),
+            |_| Ok(()),
+        )
+        .await;
+
+        assert_eq!(
+            result.unwrap_err(),
+            AppError::Api {
+                provider: "mistral".to_string(),
+                status: Some(401),
+                error_type: None,
+                retry_after: None,
+                message: "Unauthorized".to_string(),
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_models_lists_ids() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .and(header("authorization", "Bearer test-key"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [
+                    { "id": "mistral-large-latest", "object": "model" },
+                    { "id": "codestral-latest", "object": "model" }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let models = fetch_models(
+            &reqwest::Client::new(),
+            &format!("{}/v1/models", server.uri()),
+            "test-key",
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(models, vec!["mistral-large-latest", "codestral-latest"]);
+    }
+}
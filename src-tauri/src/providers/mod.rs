@@ -1,2 +1,7 @@
 pub mod anthropic;
 pub mod cloudflare;
+#[cfg(test)]
+mod fixtures;
+pub mod mistral;
+pub mod ollama;
+pub mod openai;
@@ -0,0 +1,614 @@
+use crate::core::context::ProviderContext;
+use crate::core::credentials::OllamaCredentials;
+use crate::core::endpoints;
+use crate::core::error::AppError;
+use crate::core::llm_trait::LLMProvider;
+use crate::core::models::{
+    ChatRequest, ChatRole, ContentItem, ContentType, StreamResponse, TokenUsage,
+};
+use crate::core::retry::Retry;
+use crate::core::stream::{StopReason, StreamEmitter};
+use crate::core::validation::{AccountMetadata, CredentialStatus, CredentialValidation};
+use crate::providers::anthropic::AnthropicSystemPrompt;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub struct OllamaProvider {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OllamaRole {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaMessage {
+    pub role: OllamaRole,
+    pub content: String,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub images: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OllamaOptions {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_k: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub num_predict: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub repeat_penalty: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaChatRequest {
+    pub model: String,
+    pub messages: Vec<OllamaMessage>,
+    pub stream: bool,
+    pub options: OllamaOptions,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaChatChunk {
+    #[serde(default)]
+    pub message: Option<OllamaMessage>,
+
+    #[serde(default)]
+    pub done: bool,
+
+    #[serde(default)]
+    pub done_reason: Option<String>,
+
+    #[serde(default)]
+    pub prompt_eval_count: Option<u32>,
+
+    #[serde(default)]
+    pub eval_count: Option<u32>,
+
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaTagsResponse {
+    pub models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+}
+
+impl OllamaCredentials {
+    pub fn endpoint_params(&self) -> HashMap<String, String> {
+        let host = Some(self.host.trim())
+            .filter(|host| !host.is_empty())
+            .unwrap_or(endpoints::OLLAMA_DEFAULT_HOST)
+            .trim_end_matches('/');
+
+        let mut params = HashMap::new();
+        params.insert("host".to_string(), host.to_string());
+        params
+    }
+}
+
+fn convert_role(role: &ChatRole) -> OllamaRole {
+    match role {
+        ChatRole::System => OllamaRole::System,
+        ChatRole::User => OllamaRole::User,
+        ChatRole::Assistant => OllamaRole::Assistant,
+    }
+}
+
+fn create_ollama_message(role: &ChatRole, content: &ContentType) -> OllamaMessage {
+    match content {
+        ContentType::PlainText(text) => OllamaMessage {
+            role: convert_role(role),
+            content: text.clone(),
+            images: Vec::new(),
+        },
+        ContentType::StructuredContent(items) => {
+            let mut texts = Vec::new();
+            let mut images = Vec::new();
+
+            for item in items {
+                match item {
+                    ContentItem::Text { text } => texts.push(text.as_str()),
+                    ContentItem::Image { source } => images.push(source.data.clone()),
+                    _ => {}
+                }
+            }
+
+            OllamaMessage {
+                role: convert_role(role),
+                content: texts.join("\n"),
+                images,
+            }
+        }
+    }
+}
+
+pub fn adapt_request(model: String, request: ChatRequest) -> OllamaChatRequest {
+    let system_prompt = match request.system {
+        AnthropicSystemPrompt::Simple(text) => text,
+        AnthropicSystemPrompt::Structured(data) => data.text,
+    };
+
+    let mut messages = Vec::with_capacity(request.messages.len() + 1);
+
+    if !system_prompt.is_empty() {
+        messages.push(OllamaMessage {
+            role: OllamaRole::System,
+            content: system_prompt,
+            images: Vec::new(),
+        });
+    }
+
+    messages.extend(
+        request
+            .messages
+            .iter()
+            .map(|msg| create_ollama_message(&msg.role, &msg.content)),
+    );
+
+    OllamaChatRequest {
+        model,
+        messages,
+        stream: true,
+        options: OllamaOptions {
+            temperature: request.temperature,
+            top_p: request.top_p,
+            top_k: request.top_k,
+            seed: request.seed,
+            num_predict: request.max_tokens,
+            repeat_penalty: request.repetition_penalty,
+            frequency_penalty: request.frequency_penalty,
+            presence_penalty: request.presence_penalty,
+        },
+    }
+}
+
+/// Removes the first complete line from `buffer`. Lines are split on raw
+/// bytes and only decoded once complete, so a UTF-8 character split across
+/// two chunks is decoded correctly.
+fn take_line(buffer: &mut Vec<u8>) -> Option<String> {
+    let end = buffer.iter().position(|&byte| byte == b'\n')?;
+    let line: Vec<u8> = buffer.drain(..=end).collect();
+
+    Some(String::from_utf8_lossy(&line).into_owned())
+}
+
+pub async fn stream_chat<F>(
+    client: &reqwest::Client,
+    credentials: &OllamaCredentials,
+    request: &OllamaChatRequest,
+    retry: &Retry<'_>,
+    mut on_delta: F,
+) -> Result<StreamResponse, AppError>
+where
+    F: FnMut(&str) -> Result<(), String> + Send,
+{
+    let api_url = endpoints::get_api_url("ollama", Some(&credentials.endpoint_params()))?;
+
+    let response = retry
+        .send("ollama", || client.post(&api_url).json(request))
+        .await?;
+
+    let mut accumulated_text = String::new();
+    let mut buffer = Vec::new();
+
+    let mut stream = response.bytes_stream();
+
+    // Ollama streams newline-delimited JSON objects rather than SSE.
+    loop {
+        let ended = match stream.next().await {
+            Some(chunk) => {
+                buffer.extend_from_slice(&chunk.map_err(|e| AppError::network("ollama", e))?);
+                false
+            }
+            // The last object may come without its trailing newline.
+            None => {
+                buffer.push(b'\n');
+                true
+            }
+        };
+
+        while let Some(line) = take_line(&mut buffer) {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let chat_chunk = match serde_json::from_str::<OllamaChatChunk>(line) {
+                Ok(chat_chunk) => chat_chunk,
+                Err(e) => {
+                    eprintln!("Error parsing Ollama stream chunk: {}", e);
+                    continue;
+                }
+            };
+
+            if let Some(error) = chat_chunk.error {
+                return Err(AppError::from_stream_error(
+                    "ollama",
+                    &serde_json::json!({ "error": error }),
+                ));
+            }
+
+            if let Some(message) = chat_chunk.message.filter(|m| !m.content.is_empty()) {
+                accumulated_text.push_str(&message.content);
+                on_delta(&message.content)?;
+            }
+
+            if chat_chunk.done {
+                let prompt_tokens = chat_chunk.prompt_eval_count.unwrap_or(0);
+                let completion_tokens = chat_chunk.eval_count.unwrap_or(0);
+
+                return Ok(StreamResponse {
+                    response: accumulated_text,
+                    usage: Some(TokenUsage {
+                        prompt_tokens,
+                        completion_tokens,
+                        total_tokens: prompt_tokens + completion_tokens,
+                    }),
+                    thinking: None,
+                    content: None,
+                    stop_reason: chat_chunk
+                        .done_reason
+                        .as_deref()
+                        .map(StopReason::from_finish_reason),
+                });
+            }
+        }
+
+        if ended {
+            break;
+        }
+    }
+
+    Ok(StreamResponse {
+        response: accumulated_text,
+        usage: None,
+        thinking: None,
+        content: None,
+        stop_reason: None,
+    })
+}
+
+pub async fn fetch_models(
+    client: &reqwest::Client,
+    credentials: &OllamaCredentials,
+) -> Result<Vec<String>, AppError> {
+    let models_url = endpoints::get_models_url("ollama", Some(&credentials.endpoint_params()))?;
+
+    let response = client
+        .get(models_url)
+        .send()
+        .await
+        .map_err(|e| AppError::network("ollama", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::from_response("ollama", status, &headers, &body));
+    }
+
+    let tags_response: OllamaTagsResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::network("ollama", e))?;
+
+    Ok(tags_response
+        .models
+        .into_iter()
+        .map(|model| model.name)
+        .collect())
+}
+
+#[async_trait]
+impl LLMProvider for OllamaProvider {
+    fn provider_id(&self) -> &'static str {
+        "ollama"
+    }
+
+    async fn send_message(
+        &self,
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        let credentials = context
+            .credentials::<OllamaCredentials>("ollama", profile.as_deref())
+            .await?;
+        let ollama_request = adapt_request(model, request);
+
+        let retry = Retry::new(context.retry_policy(), |attempt| {
+            let _ = events.retrying(attempt);
+        });
+
+        stream_chat(
+            &context.client()?,
+            &credentials,
+            &ollama_request,
+            &retry,
+            |delta| events.text_delta(delta),
+        )
+        .await
+    }
+
+    async fn list_models(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let credentials = context
+            .credentials::<OllamaCredentials>("ollama", profile.as_deref())
+            .await?;
+
+        fetch_models(&context.client()?, &credentials).await
+    }
+
+    async fn has_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError> {
+        Ok(context
+            .credentials::<OllamaCredentials>("ollama", profile.as_deref())
+            .await
+            .is_ok())
+    }
+
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError> {
+        let credentials = match context
+            .credentials::<OllamaCredentials>("ollama", profile.as_deref())
+            .await
+        {
+            Ok(credentials) => credentials,
+            Err(e) => return Ok(CredentialValidation::missing(e)),
+        };
+
+        // Ollama has no key, the only thing to check is that the host answers.
+        Ok(match fetch_models(&context.client()?, &credentials).await {
+            Ok(models) => CredentialValidation::valid(AccountMetadata {
+                models,
+                ..Default::default()
+            }),
+            Err(e) => CredentialValidation::failed(CredentialStatus::NetworkError, e.to_string()),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::{ChatMessage, ImageSource};
+    use wiremock::matchers::{body_partial_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn chat_request(content: ContentType) -> ChatRequest {
+        ChatRequest {
+            messages: vec![ChatMessage {
+                role: ChatRole::User,
+                content,
+            }],
+            system: AnthropicSystemPrompt::Simple("You are offline.".to_string()),
+            max_tokens: Some(128),
+            stream: Some(true),
+            temperature: Some(0.5),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn adapt_request_moves_images_to_images_field() {
+        let request = chat_request(ContentType::StructuredContent(vec![
+            ContentItem::Text {
+                text: "Describe".to_string(),
+            },
+            ContentItem::Image {
+                source: ImageSource {
+                    source_type: "base64".to_string(),
+                    media_type: "image/jpeg".to_string(),
+                    data: "iVBORw0KGgo=".to_string(),
+                },
+            },
+        ]));
+
+        let adapted = adapt_request("llava".to_string(), request);
+
+        assert_eq!(adapted.messages[0].role, OllamaRole::System);
+        assert_eq!(adapted.messages[1].content, "Describe");
+        assert_eq!(adapted.messages[1].images, vec!["iVBORw0KGgo="]);
+        assert_eq!(adapted.options.num_predict, Some(128));
+    }
+
+    #[test]
+    fn take_line_keeps_utf8_split_across_chunks() {
+        let bytes = "{\"content\":\"👋\"}\n".as_bytes();
+        let (head, tail) = bytes.split_at(14);
+
+        let mut buffer = head.to_vec();
+        assert_eq!(take_line(&mut buffer), None);
+        buffer.extend_from_slice(tail);
+        assert_eq!(
+            take_line(&mut buffer).as_deref(),
+            Some("{\"content\":\"👋\"}\n")
+        );
+        assert!(buffer.is_empty());
+    }
+
+    #[tokio::test]
+    async fn stream_chat_replays_ndjson() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "{\"model\":\"llama3.2\",\"created_at\":\"2025-01-01T00:00:00Z\",\"message\":{\"role\":\"assistant\",\"content\":\"Hors\"},\"done\":false}\n",
+            "{\"model\":\"llama3.2\",\"created_at\":\"2025-01-01T00:00:00Z\",\"message\":{\"role\":\"assistant\",\"content\":\" ligne\"},\"done\":false}\n",
+            "{\"model\":\"llama3.2\",\"created_at\":\"2025-01-01T00:00:01Z\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"stop\",\"prompt_eval_count\":21,\"eval_count\":4}\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "llama3.2",
+                "stream": true
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "application/x-ndjson")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let credentials = OllamaCredentials { host: server.uri() };
+        let request = adapt_request(
+            "llama3.2".to_string(),
+            chat_request(ContentType::PlainText("Bonjour".to_string())),
+        );
+
+        let mut deltas = Vec::new();
+        let response = stream_chat(
+            &reqwest::Client::new(),
+            &credentials,
+            &request,
+            &Retry::never(),
+            |delta| {
+                deltas.push(delta.to_string());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(deltas, vec!["Hors", " ligne"]);
+        assert_eq!(response.response, "Hors ligne");
+        let usage = response.usage.unwrap();
+        assert_eq!(usage.prompt_tokens, 21);
+        assert_eq!(usage.completion_tokens, 4);
+        assert_eq!(usage.total_tokens, 25);
+    }
+
+    #[tokio::test]
+    async fn stream_chat_reads_a_final_object_without_newline() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "{\"model\":\"llama3.2\",\"message\":{\"role\":\"assistant\",\"content\":\"Fin\"},\"done\":false}\n",
+            "{\"model\":\"llama3.2\",\"message\":{\"role\":\"assistant\",\"content\":\"\"},\"done\":true,\"done_reason\":\"length\",\"prompt_eval_count\":5,\"eval_count\":1}",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(ResponseTemplate::new(200).set_body_string(body))
+            .mount(&server)
+            .await;
+
+        let credentials = OllamaCredentials { host: server.uri() };
+        let request = adapt_request(
+            "llama3.2".to_string(),
+            chat_request(ContentType::PlainText("Bonjour".to_string())),
+        );
+
+        let response = stream_chat(
+            &reqwest::Client::new(),
+            &credentials,
+            &request,
+            &Retry::never(),
+            |_| Ok(()),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.response, "Fin");
+        assert_eq!(response.stop_reason, Some(StopReason::MaxTokens));
+        assert_eq!(response.usage.unwrap().total_tokens, 6);
+    }
+
+    #[tokio::test]
+    async fn stream_chat_surfaces_inline_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("POST"))
+            .and(path("/api/chat"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_string("{\"error\":\"model 'nope' not found\"}\n"),
+            )
+            .mount(&server)
+            .await;
+
+        let credentials = OllamaCredentials { host: server.uri() };
+        let request = adapt_request(
+            "nope".to_string(),
+            chat_request(ContentType::PlainText("Hi".to_string())),
+        );
+
+        let result = stream_chat(
+            &reqwest::Client::new(),
+            &credentials,
+            &request,
+            &Retry::never(),
+            |_| Ok(()),
+        )
+        .await;
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "ollama API error: model 'nope' not found"
+        );
+    }
+
+    #[tokio::test]
+    async fn fetch_models_reads_tags() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/api/tags"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "models": [
+                    { "name": "llama3.2:latest", "size": 2019393189 },
+                    { "name": "llava:7b", "size": 4733363377u64 }
+                ]
+            })))
+            .mount(&server)
+            .await;
+
+        let credentials = OllamaCredentials {
+            host: format!("{}/", server.uri()),
+        };
+
+        let models = fetch_models(&reqwest::Client::new(), &credentials)
+            .await
+            .unwrap();
+
+        assert_eq!(models, vec!["llama3.2:latest", "llava:7b"]);
+    }
+}
@@ -0,0 +1,560 @@
+use crate::core::context::ProviderContext;
+use crate::core::credentials::OpenAICredentials;
+use crate::core::endpoints;
+use crate::core::error::AppError;
+use crate::core::llm_trait::LLMProvider;
+use crate::core::models::{
+    ChatRequest, ChatRole, ContentItem, ContentType, StreamResponse, TokenUsage,
+};
+use crate::core::retry::Retry;
+use crate::core::sse;
+use crate::core::stream::{StopReason, StreamEmitter};
+use crate::core::validation::{self, CredentialValidation};
+use crate::providers::anthropic::AnthropicSystemPrompt;
+use async_trait::async_trait;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub struct OpenAIProvider {}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAIRole {
+    System,
+    User,
+    Assistant,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum OpenAIContent {
+    Text(String),
+    Parts(Vec<OpenAIContentPart>),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "type")]
+pub enum OpenAIContentPart {
+    #[serde(rename = "text")]
+    Text { text: String },
+
+    #[serde(rename = "image_url")]
+    ImageUrl { image_url: OpenAIImageUrl },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct OpenAIImageUrl {
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIMessage {
+    pub role: OpenAIRole,
+    pub content: OpenAIContent,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIStreamOptions {
+    pub include_usage: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIChatRequest {
+    pub model: String,
+    pub messages: Vec<OpenAIMessage>,
+    pub stream: bool,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stream_options: Option<OpenAIStreamOptions>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_p: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub seed: Option<i32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frequency_penalty: Option<f32>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub presence_penalty: Option<f32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIStreamChunk {
+    #[serde(default)]
+    pub choices: Vec<OpenAIChoice>,
+
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIChoice {
+    pub delta: OpenAIDelta,
+    pub finish_reason: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIDelta {
+    #[serde(default)]
+    pub content: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub total_tokens: u32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIModelsResponse {
+    pub data: Vec<OpenAIModel>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OpenAIModel {
+    pub id: String,
+}
+
+impl From<OpenAIUsage> for TokenUsage {
+    fn from(usage: OpenAIUsage) -> Self {
+        TokenUsage {
+            prompt_tokens: usage.prompt_tokens,
+            completion_tokens: usage.completion_tokens,
+            total_tokens: usage.total_tokens,
+        }
+    }
+}
+
+impl OpenAICredentials {
+    pub fn endpoint_params(&self) -> HashMap<String, String> {
+        let base_url = self
+            .base_url
+            .as_deref()
+            .map(str::trim)
+            .filter(|url| !url.is_empty())
+            .unwrap_or(endpoints::OPENAI_DEFAULT_BASE_URL)
+            .trim_end_matches('/');
+
+        let mut params = HashMap::new();
+        params.insert("base_url".to_string(), base_url.to_string());
+        params
+    }
+}
+
+fn convert_content(content: &ContentType) -> OpenAIContent {
+    match content {
+        ContentType::PlainText(text) => OpenAIContent::Text(text.clone()),
+        ContentType::StructuredContent(items) => OpenAIContent::Parts(
+            items
+                .iter()
+                .filter_map(|item| match item {
+                    ContentItem::Text { text } => {
+                        Some(OpenAIContentPart::Text { text: text.clone() })
+                    }
+                    ContentItem::Image { source } => Some(OpenAIContentPart::ImageUrl {
+                        image_url: OpenAIImageUrl {
+                            url: format!("data:{};base64,{}", source.media_type, source.data),
+                        },
+                    }),
+                    _ => None,
+                })
+                .collect(),
+        ),
+    }
+}
+
+fn convert_role(role: &ChatRole) -> OpenAIRole {
+    match role {
+        ChatRole::System => OpenAIRole::System,
+        ChatRole::User => OpenAIRole::User,
+        ChatRole::Assistant => OpenAIRole::Assistant,
+    }
+}
+
+pub fn adapt_request(model: String, request: ChatRequest) -> OpenAIChatRequest {
+    let system_prompt = match request.system {
+        AnthropicSystemPrompt::Simple(text) => text,
+        AnthropicSystemPrompt::Structured(data) => data.text,
+    };
+
+    let mut messages = Vec::with_capacity(request.messages.len() + 1);
+
+    if !system_prompt.is_empty() {
+        messages.push(OpenAIMessage {
+            role: OpenAIRole::System,
+            content: OpenAIContent::Text(system_prompt),
+        });
+    }
+
+    messages.extend(request.messages.iter().map(|msg| OpenAIMessage {
+        role: convert_role(&msg.role),
+        content: convert_content(&msg.content),
+    }));
+
+    OpenAIChatRequest {
+        model,
+        messages,
+        stream: true,
+        stream_options: Some(OpenAIStreamOptions {
+            include_usage: true,
+        }),
+        temperature: request.temperature,
+        top_p: request.top_p,
+        max_tokens: request.max_tokens,
+        seed: request.seed,
+        frequency_penalty: request.frequency_penalty,
+        presence_penalty: request.presence_penalty,
+    }
+}
+
+fn authorized(
+    builder: reqwest::RequestBuilder,
+    credentials: &OpenAICredentials,
+) -> reqwest::RequestBuilder {
+    // Local servers (llama.cpp, Ollama, ...) usually run without a key.
+    let builder = if credentials.api_key.is_empty() {
+        builder
+    } else {
+        builder.header("Authorization", format!("Bearer {}", credentials.api_key))
+    };
+
+    match &credentials.organization {
+        Some(organization) if !organization.is_empty() => {
+            builder.header("OpenAI-Organization", organization)
+        }
+        _ => builder,
+    }
+}
+
+pub async fn stream_chat<F>(
+    client: &reqwest::Client,
+    credentials: &OpenAICredentials,
+    request: &OpenAIChatRequest,
+    retry: &Retry<'_>,
+    mut on_delta: F,
+) -> Result<StreamResponse, AppError>
+where
+    F: FnMut(&str) -> Result<(), String> + Send,
+{
+    let api_url = endpoints::get_api_url("openai", Some(&credentials.endpoint_params()))?;
+
+    let response = retry
+        .send("openai", || {
+            authorized(client.post(&api_url), credentials)
+                .header("Accept", "text/event-stream")
+                .json(request)
+        })
+        .await?;
+
+    let mut accumulated_text = String::new();
+    let mut tokens_usage = None;
+    let mut stop_reason = None;
+
+    let mut stream = Box::pin(sse::sse_stream(response.bytes_stream()));
+
+    while let Some(event) = stream.next().await {
+        let event = event.map_err(|e| AppError::network("openai", e))?;
+        let data = event.data.trim();
+
+        if data == "[DONE]" {
+            break;
+        }
+
+        match serde_json::from_str::<OpenAIStreamChunk>(data) {
+            Ok(stream_chunk) => {
+                if let Some(usage) = stream_chunk.usage {
+                    tokens_usage = Some(usage.into());
+                }
+
+                for choice in stream_chunk.choices {
+                    if let Some(reason) = choice.finish_reason.as_deref() {
+                        stop_reason = Some(StopReason::from_finish_reason(reason));
+                    }
+
+                    if let Some(content) = choice.delta.content.filter(|c| !c.is_empty()) {
+                        accumulated_text.push_str(&content);
+                        on_delta(&content)?;
+                    }
+                }
+            }
+            Err(e) => {
+                eprintln!("Error parsing OpenAI stream chunk: {}", e);
+            }
+        }
+    }
+
+    Ok(StreamResponse {
+        response: accumulated_text,
+        usage: tokens_usage,
+        thinking: None,
+        content: None,
+        stop_reason,
+    })
+}
+
+pub async fn fetch_models(
+    client: &reqwest::Client,
+    credentials: &OpenAICredentials,
+) -> Result<Vec<String>, AppError> {
+    let models_url = endpoints::get_models_url("openai", Some(&credentials.endpoint_params()))?;
+
+    let response = authorized(client.get(models_url), credentials)
+        .send()
+        .await
+        .map_err(|e| AppError::network("openai", e))?;
+
+    let status = response.status();
+    if !status.is_success() {
+        let headers = response.headers().clone();
+        let body = response.text().await.unwrap_or_default();
+        return Err(AppError::from_response("openai", status, &headers, &body));
+    }
+
+    let models_response: OpenAIModelsResponse = response
+        .json()
+        .await
+        .map_err(|e| AppError::network("openai", e))?;
+
+    Ok(models_response
+        .data
+        .into_iter()
+        .map(|model| model.id)
+        .collect())
+}
+
+pub async fn probe_credentials(
+    client: &reqwest::Client,
+    credentials: &OpenAICredentials,
+) -> Result<CredentialValidation, String> {
+    let models_url = endpoints::get_models_url("openai", Some(&credentials.endpoint_params()))?;
+    let request = authorized(client.get(models_url), credentials);
+
+    Ok(validation::probe_models(request, Some("openai-organization")).await)
+}
+
+#[async_trait]
+impl LLMProvider for OpenAIProvider {
+    fn provider_id(&self) -> &'static str {
+        "openai"
+    }
+
+    async fn send_message(
+        &self,
+        events: StreamEmitter,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+        model: String,
+        request: ChatRequest,
+    ) -> Result<StreamResponse, AppError> {
+        let credentials = context
+            .credentials::<OpenAICredentials>("openai", profile.as_deref())
+            .await?;
+        let openai_request = adapt_request(model, request);
+
+        let retry = Retry::new(context.retry_policy(), |attempt| {
+            let _ = events.retrying(attempt);
+        });
+
+        stream_chat(
+            &context.client()?,
+            &credentials,
+            &openai_request,
+            &retry,
+            |delta| events.text_delta(delta),
+        )
+        .await
+    }
+
+    async fn list_models(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<Vec<String>, AppError> {
+        let credentials = context
+            .credentials::<OpenAICredentials>("openai", profile.as_deref())
+            .await?;
+
+        fetch_models(&context.client()?, &credentials).await
+    }
+
+    async fn has_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<bool, AppError> {
+        Ok(context
+            .credentials::<OpenAICredentials>("openai", profile.as_deref())
+            .await
+            .is_ok())
+    }
+
+    async fn validate_credentials(
+        &self,
+        context: &dyn ProviderContext,
+        profile: Option<String>,
+    ) -> Result<CredentialValidation, AppError> {
+        match context
+            .credentials::<OpenAICredentials>("openai", profile.as_deref())
+            .await
+        {
+            Ok(credentials) => Ok(probe_credentials(&context.client()?, &credentials).await?),
+            Err(e) => Ok(CredentialValidation::missing(e)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::models::ChatMessage;
+    use wiremock::matchers::{body_partial_json, header, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    fn local_credentials(server: &MockServer) -> OpenAICredentials {
+        OpenAICredentials {
+            api_key: "sk-test".to_string(),
+            base_url: Some(format!("{}/v1/", server.uri())),
+            organization: Some("org-test".to_string()),
+        }
+    }
+
+    fn chat_request() -> ChatRequest {
+        ChatRequest {
+            messages: vec![ChatMessage {
+                role: ChatRole::User,
+                content: ContentType::PlainText("Hello".to_string()),
+            }],
+            max_tokens: Some(32),
+            stream: Some(true),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn endpoint_params_default_to_openai() {
+        let credentials = OpenAICredentials {
+            api_key: "sk-test".to_string(),
+            base_url: None,
+            organization: None,
+        };
+
+        assert_eq!(
+            endpoints::get_api_url("openai", Some(&credentials.endpoint_params())).unwrap(),
+            "https://api.openai.com/v1/chat/completions"
+        );
+    }
+
+    #[tokio::test]
+    async fn stream_chat_uses_base_url_and_reports_usage() {
+        let server = MockServer::start().await;
+        let body = concat!(
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"Hi\"},\"finish_reason\":null}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\" there\"},\"finish_reason\":\"stop\"}]}\n\n",
+            "data: {\"id\":\"c1\",\"choices\":[],\"usage\":{\"prompt_tokens\":9,\"completion_tokens\":2,\"total_tokens\":11}}\n\n",
+            "data: [DONE]\n\n",
+        );
+
+        Mock::given(method("POST"))
+            .and(path("/v1/chat/completions"))
+            .and(header("authorization", "Bearer sk-test"))
+            .and(header("openai-organization", "org-test"))
+            .and(body_partial_json(serde_json::json!({
+                "model": "llama-3.1-8b",
+                "stream_options": { "include_usage": true }
+            })))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .insert_header("content-type", "text/event-stream")
+                    .set_body_string(body),
+            )
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let request = adapt_request("llama-3.1-8b".to_string(), chat_request());
+
+        let mut deltas = Vec::new();
+        let response = stream_chat(
+            &reqwest::Client::new(),
+            &local_credentials(&server),
+            &request,
+            &Retry::never(),
+            |delta| {
+                deltas.push(delta.to_string());
+                Ok(())
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(deltas, vec!["Hi", " there"]);
+        assert_eq!(response.response, "Hi there");
+        assert_eq!(response.usage.unwrap().total_tokens, 11);
+    }
+
+    #[tokio::test]
+    async fn fetch_models_reads_v1_models() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+                "object": "list",
+                "data": [{ "id": "gpt-4o-mini", "object": "model" }]
+            })))
+            .mount(&server)
+            .await;
+
+        let models = fetch_models(&reqwest::Client::new(), &local_credentials(&server))
+            .await
+            .unwrap();
+
+        assert_eq!(models, vec!["gpt-4o-mini"]);
+    }
+
+    #[tokio::test]
+    async fn fetch_models_returns_structured_api_errors() {
+        let server = MockServer::start().await;
+
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(
+                ResponseTemplate::new(429)
+                    .insert_header("retry-after", "20")
+                    .set_body_json(serde_json::json!({
+                        "error": {
+                            "message": "Rate limit reached",
+                            "type": "requests",
+                            "code": "rate_limit_exceeded"
+                        }
+                    })),
+            )
+            .mount(&server)
+            .await;
+
+        let error = fetch_models(&reqwest::Client::new(), &local_credentials(&server))
+            .await
+            .unwrap_err();
+
+        assert_eq!(
+            error,
+            AppError::Api {
+                provider: "openai".to_string(),
+                status: Some(429),
+                error_type: Some("requests".to_string()),
+                retry_after: Some(20),
+                message: "Rate limit reached".to_string(),
+            }
+        );
+    }
+}
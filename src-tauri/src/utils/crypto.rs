@@ -1,56 +1,269 @@
-use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng, Payload};
+use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 use serde_json::Value;
 
-const CRYPTO_KEY: &str = "magic-key";
+/// Version tag prefixed to every envelope, bumped whenever the format changes.
+pub const ENVELOPE_VERSION: &str = "v1";
+pub const SECRET_LEN: usize = 32;
+pub const SALT_LEN: usize = 16;
 
-pub fn encrypt_string(input: &str) -> String {
-    let crypto = new_magic_crypt!(CRYPTO_KEY, 256);
-    crypto.encrypt_str_to_base64(input)
+const NONCE_LEN: usize = 24;
+
+pub fn random_bytes(len: usize) -> Vec<u8> {
+    let mut bytes = vec![0u8; len];
+    OsRng.fill_bytes(&mut bytes);
+    bytes
+}
+
+pub fn encode(bytes: &[u8]) -> String {
+    BASE64.encode(bytes)
+}
+
+pub fn decode(encoded: &str) -> Result<Vec<u8>, String> {
+    BASE64
+        .decode(encoded)
+        .map_err(|e| format!("Invalid base64: {}", e))
 }
 
-pub fn decrypt_string(encrypted: &str) -> Result<String, String> {
-    let crypto = new_magic_crypt!(CRYPTO_KEY, 256);
-    crypto
-        .decrypt_base64_to_string(encrypted)
-        .map_err(|e| e.to_string())
+/// Key protecting the credentials store, derived with Argon2id from the
+/// per-install secret and, when the user set one, their passphrase.
+#[derive(Clone)]
+pub struct CredentialKey(Key);
+
+impl std::fmt::Debug for CredentialKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("CredentialKey(..)")
+    }
 }
 
-pub fn encrypt_value(value: Value) -> Result<Value, String> {
+impl CredentialKey {
+    pub fn derive(
+        install_secret: &[u8],
+        salt: &[u8],
+        passphrase: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut password = install_secret.to_vec();
+        if let Some(passphrase) = passphrase {
+            password.extend_from_slice(passphrase.as_bytes());
+        }
+
+        let mut key = Key::default();
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+            .hash_password_into(&password, salt, &mut key)
+            .map_err(|e| format!("Failed to derive credentials key: {}", e))?;
+
+        Ok(Self(key))
+    }
+
+    /// Seals `plaintext` into a `v1:<nonce>:<ciphertext>` envelope. The
+    /// context is authenticated too, so an envelope cannot be moved to
+    /// another field of the store.
+    pub fn encrypt_string(&self, context: &str, plaintext: &str) -> Result<String, String> {
+        let cipher = XChaCha20Poly1305::new(&self.0);
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: plaintext.as_bytes(),
+                    aad: context.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("Failed to encrypt {}", context))?;
+
+        Ok(format!(
+            "{}:{}:{}",
+            ENVELOPE_VERSION,
+            encode(&nonce),
+            encode(&ciphertext)
+        ))
+    }
+
+    pub fn decrypt_string(&self, context: &str, envelope: &str) -> Result<String, String> {
+        let mut parts = envelope.splitn(3, ':');
+        let (version, nonce, ciphertext) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(version), Some(nonce), Some(ciphertext)) => (version, nonce, ciphertext),
+            _ => return Err(format!("{} is not an encrypted envelope", context)),
+        };
+
+        if version != ENVELOPE_VERSION {
+            return Err(format!(
+                "Unsupported envelope version '{}' for {}",
+                version, context
+            ));
+        }
+
+        let nonce = decode(nonce)?;
+        if nonce.len() != NONCE_LEN {
+            return Err(format!("Invalid nonce length for {}", context));
+        }
+        let ciphertext = decode(ciphertext)?;
+
+        let plaintext = XChaCha20Poly1305::new(&self.0)
+            .decrypt(
+                XNonce::from_slice(&nonce),
+                Payload {
+                    msg: &ciphertext,
+                    aad: context.as_bytes(),
+                },
+            )
+            .map_err(|_| format!("Failed to decrypt {}: wrong key or tampered value", context))?;
+
+        String::from_utf8(plaintext).map_err(|e| format!("Invalid UTF-8 in {}: {}", context, e))
+    }
+}
+
+pub fn is_envelope(value: &str) -> bool {
+    value
+        .strip_prefix(ENVELOPE_VERSION)
+        .is_some_and(|rest| rest.starts_with(':'))
+}
+
+/// Applies `f` to every string of a JSON object, recursing into nested
+/// objects. `f` receives the dotted path of the field, rooted at `context`.
+fn map_strings<F>(context: &str, value: Value, f: &mut F) -> Result<Value, String>
+where
+    F: FnMut(&str, String) -> Result<String, String>,
+{
     match value {
         Value::Object(map) => {
-            let mut encrypted_map = serde_json::Map::new();
+            let mut mapped = serde_json::Map::new();
             for (k, v) in map {
-                if let Value::String(s) = v {
-                    encrypted_map.insert(k, Value::String(encrypt_string(&s)));
-                } else if let Value::Object(_) = v {
-                    encrypted_map.insert(k, encrypt_value(v)?);
-                } else {
-                    encrypted_map.insert(k, v);
-                }
+                let path = format!("{}.{}", context, k);
+                let v = match v {
+                    Value::String(s) => Value::String(f(&path, s)?),
+                    Value::Object(_) => map_strings(&path, v, f)?,
+                    other => other,
+                };
+                mapped.insert(k, v);
             }
-            Ok(Value::Object(encrypted_map))
+            Ok(Value::Object(mapped))
         }
         _ => Ok(value),
     }
 }
 
-pub fn decrypt_value(value: Value) -> Result<Value, String> {
+pub fn encrypt_value(key: &CredentialKey, context: &str, value: Value) -> Result<Value, String> {
+    map_strings(context, value, &mut |path, s| key.encrypt_string(path, &s))
+}
+
+pub fn decrypt_value(key: &CredentialKey, context: &str, value: Value) -> Result<Value, String> {
+    map_strings(context, value, &mut |path, s| key.decrypt_string(path, &s))
+}
+
+/// Whether the value still holds strings written before envelopes existed.
+pub fn has_legacy_strings(value: &Value) -> bool {
     match value {
-        Value::Object(map) => {
-            let mut decrypted_map = serde_json::Map::new();
-            for (k, v) in map {
-                if let Value::String(s) = v {
-                    // Tenter de déchiffrer, sans erreur si ce n'est pas possible
-                    let decrypted = decrypt_string(&s).unwrap_or(s);
-                    decrypted_map.insert(k, Value::String(decrypted));
-                } else if let Value::Object(_) = v {
-                    decrypted_map.insert(k, decrypt_value(v)?);
-                } else {
-                    decrypted_map.insert(k, v);
-                }
-            }
-            Ok(Value::Object(decrypted_map))
+        Value::Object(map) => map.values().any(has_legacy_strings),
+        Value::String(s) => !is_envelope(s),
+        _ => false,
+    }
+}
+
+/// Re-encrypts a value written with the legacy `magic-crypt` scheme.
+pub fn migrate_legacy_value(
+    key: &CredentialKey,
+    context: &str,
+    value: Value,
+) -> Result<Value, String> {
+    map_strings(context, value, &mut |path, s| {
+        if is_envelope(&s) {
+            return Ok(s);
         }
-        _ => Ok(value),
+        let plaintext = legacy::decrypt_string(&s)
+            .map_err(|e| format!("Failed to decrypt legacy value {}: {}", path, e))?;
+        key.encrypt_string(path, &plaintext)
+    })
+}
+
+/// The fixed-key scheme used before envelopes, only kept to migrate
+/// existing stores.
+pub mod legacy {
+    use magic_crypt::{new_magic_crypt, MagicCryptTrait};
+
+    const CRYPTO_KEY: &str = "magic-key";
+
+    #[cfg(test)]
+    pub fn encrypt_string(input: &str) -> String {
+        let crypto = new_magic_crypt!(CRYPTO_KEY, 256);
+        crypto.encrypt_str_to_base64(input)
+    }
+
+    pub fn decrypt_string(encrypted: &str) -> Result<String, String> {
+        let crypto = new_magic_crypt!(CRYPTO_KEY, 256);
+        crypto
+            .decrypt_base64_to_string(encrypted)
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn key(passphrase: Option<&str>) -> CredentialKey {
+        CredentialKey::derive(&[7u8; SECRET_LEN], &[1u8; SALT_LEN], passphrase).unwrap()
+    }
+
+    #[test]
+    fn values_round_trip_through_envelopes() {
+        let key = key(None);
+        let value = json!({ "api_key": "sk-123", "base_url": null, "nested": { "token": "t" } });
+
+        let encrypted = encrypt_value(&key, "OPENAI", value.clone()).unwrap();
+        let api_key = encrypted["api_key"].as_str().unwrap();
+        assert!(api_key.starts_with("v1:"));
+        assert!(!api_key.contains("sk-123"));
+        assert!(!has_legacy_strings(&encrypted));
+
+        assert_eq!(decrypt_value(&key, "OPENAI", encrypted).unwrap(), value);
+    }
+
+    #[test]
+    fn tampered_or_moved_envelopes_fail() {
+        let key = key(None);
+        let envelope = key.encrypt_string("A.api_key", "secret").unwrap();
+
+        let mut tampered = envelope.clone();
+        let last = tampered.pop().unwrap();
+        tampered.push(if last == 'A' { 'B' } else { 'A' });
+        assert!(key.decrypt_string("A.api_key", &tampered).is_err());
+        assert!(key.decrypt_string("B.api_key", &envelope).is_err());
+        assert!(key.decrypt_string("A.api_key", "plaintext").is_err());
+        assert!(key
+            .decrypt_string("A.api_key", &envelope.replacen("v1", "v9", 1))
+            .is_err());
+    }
+
+    #[test]
+    fn passphrase_changes_the_key() {
+        let envelope = key(Some("hunter2")).encrypt_string("A", "secret").unwrap();
+
+        assert!(key(None).decrypt_string("A", &envelope).is_err());
+        assert!(key(Some("hunter3")).decrypt_string("A", &envelope).is_err());
+        assert_eq!(
+            key(Some("hunter2")).decrypt_string("A", &envelope).unwrap(),
+            "secret"
+        );
+    }
+
+    #[test]
+    fn legacy_values_are_migrated() {
+        let key = key(None);
+        let legacy = json!({ "api_key": legacy::encrypt_string("sk-legacy") });
+        assert!(has_legacy_strings(&legacy));
+
+        let migrated = migrate_legacy_value(&key, "ANTHROPIC", legacy).unwrap();
+        assert_eq!(
+            decrypt_value(&key, "ANTHROPIC", migrated).unwrap(),
+            json!({ "api_key": "sk-legacy" })
+        );
+
+        let garbage = json!({ "api_key": "not encrypted" });
+        assert!(migrate_legacy_value(&key, "ANTHROPIC", garbage).is_err());
     }
 }
@@ -102,6 +102,8 @@ const ConversationView = () => {
 				currentModelSettings.provider,
 				currentModelSettings.modelName,
 				apiRequest,
+				undefined,
+				currentTopicId(),
 			);
 		},
 		onSuccess: (response) => {
@@ -9,8 +9,9 @@ import {
 	Show,
 } from "solid-js";
 import ChatMessage from "./chat-message";
-import { listen } from "@tauri-apps/api/event";
+import { type Event, listen } from "@tauri-apps/api/event";
 import { useGlobalContext } from "@/context/global-context";
+import type { StreamEvent } from "@shared/types";
 
 interface MessageListProps {
 	mutation: any;
@@ -85,19 +86,33 @@ const MessageList = (props: MessageListProps) => {
 	};
 
 	onMount(() => {
+		let currentRequestId: string | null = null;
 		let rawResponseText = "";
 
-		const streamHandler = (event: any) => {
-			rawResponseText = event.payload as string;
+		const streamHandler = (event: Event<StreamEvent>) => {
+			const streamEvent = event.payload;
 
-			if (rawResponseText !== currentStreamedResponse()) {
-				queueMicrotask(() => setCurrentStreamedResponse(rawResponseText));
+			if (streamEvent.type === "start") {
+				currentRequestId = streamEvent.request_id;
+				rawResponseText = "";
+				return;
 			}
+
+			if (
+				streamEvent.type !== "text_delta" ||
+				streamEvent.request_id !== currentRequestId
+			) {
+				return;
+			}
+
+			rawResponseText += streamEvent.text;
+			const text = rawResponseText;
+			queueMicrotask(() => setCurrentStreamedResponse(text));
 		};
 
 		const resetHandler = () => setCurrentStreamedResponse("");
 
-		const unlistenPromise = listen("stream-response", streamHandler);
+		const unlistenPromise = listen<StreamEvent>("stream-event", streamHandler);
 		const unlistenMessageAdded = listen("message-added", resetHandler);
 
 		onCleanup(async () => {
@@ -229,16 +229,11 @@ const addMessage = async (
 		...message,
 	};
 
-	const contentStr =
-		typeof newMessage.content === "string"
-			? newMessage.content
-			: JSON.stringify(newMessage.content);
-
 	try {
 		const result = await dbApi.addMessage(
 			+newMessage.topicId,
 			newMessage.role,
-			contentStr,
+			newMessage.content,
 			newMessage.tokensUsed,
 		);
 		console.log("result", result);
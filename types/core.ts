@@ -2,6 +2,7 @@
 import type { AnthropicModelSettings } from "../src-tauri/bindings/AnthropicModelSettings.js";
 import type { ChatRole } from "../shared/types/llm/core.js";
 import type { ContentType } from "../src-tauri/bindings/ContentType.js";
+import type { JsonValue } from "../src-tauri/bindings/serde_json/JsonValue.js";
 import type { ProviderType } from "../shared/types/llm/core.js";
 
 export type AnthropicContentType = { "text": string } | { "image": Image };
@@ -18,10 +19,18 @@ export type BaseModelSettings = {
   seed: number | null;
 };
 
-export type ContentItem = { "type": "text"; text: string } | {
-  "type": "image";
-  source: ImageSource;
-};
+export type ContentItem =
+  | { "type": "text"; text: string }
+  | { "type": "image"; source: ImageSource }
+  | { "type": "thinking"; thinking: string; signature: string }
+  | { "type": "redacted_thinking"; data: string }
+  | { "type": "tool_use"; id: string; name: string; input: JsonValue }
+  | {
+    "type": "tool_result";
+    tool_use_id: string;
+    content: string;
+    is_error?: boolean;
+  };
 
 export type DbTopic = {
   id: string;